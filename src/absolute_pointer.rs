@@ -0,0 +1,428 @@
+//! Absolute pointer positioning in screen coordinates
+//!
+//! Maps pixel coordinates on a [`ScreenLayout`] of one or more monitors to the 0 to 32767 logical
+//! range of [`AbsoluteWheelMouseReport`], and sequences moves, clicks and interpolated drags into
+//! reports with [`AbsolutePointer`].
+//!
+//! Hosts map the logical range onto the bounding box of all their monitors, except Windows which
+//! maps it onto the primary monitor only, see
+//! [`ABSOLUTE_WHEEL_MOUSE_REPORT_DESCRIPTOR`](crate::device::mouse::ABSOLUTE_WHEEL_MOUSE_REPORT_DESCRIPTOR).
+//! For Windows describe only the primary monitor.
+//!
+//! ```
+//! use usbd_human_interface_device::absolute_pointer::{AbsolutePointer, Monitor, ScreenLayout};
+//!
+//! const MONITORS: &[Monitor] = &[
+//!     Monitor { x: 0, y: 0, width: 1920, height: 1080 },
+//!     Monitor { x: 1920, y: 0, width: 1280, height: 1024 },
+//! ];
+//! let layout = ScreenLayout::new(MONITORS);
+//! let mut pointer = AbsolutePointer::new();
+//!
+//! pointer.click_at(layout.position(1, 100, 200).unwrap(), 0x01).unwrap();
+//!
+//! while let Some(report) = pointer.next_report() {
+//!     // write report with AbsoluteWheelMouse::write_report and then
+//!     pointer.commit();
+//! }
+//! ```
+use crate::device::mouse::AbsoluteWheelMouseReport;
+use crate::UsbHidError;
+use heapless::Deque;
+
+/// Largest logical position
+pub const MAX_POSITION: u16 = 0x7FFF;
+/// Reports that can be queued by an [`AbsolutePointer`]
+pub const QUEUE_SIZE: usize = 8;
+
+/// A monitor, positioned in desktop pixels
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub struct Monitor {
+    /// Left edge in desktop pixels, may be negative
+    pub x: i32,
+    /// Top edge in desktop pixels, may be negative
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Position in the logical range of an absolute pointer
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub struct Position {
+    /// From 0 to [`MAX_POSITION`]
+    pub x: u16,
+    /// From 0 to [`MAX_POSITION`]
+    pub y: u16,
+}
+
+/// Monitors of a host
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ScreenLayout<'a> {
+    monitors: &'a [Monitor],
+}
+
+impl<'a> ScreenLayout<'a> {
+    #[must_use]
+    pub const fn new(monitors: &'a [Monitor]) -> Self {
+        Self { monitors }
+    }
+
+    #[must_use]
+    pub fn monitors(&self) -> &'a [Monitor] {
+        self.monitors
+    }
+
+    /// Bounding box of the monitors as left, top, width and height in desktop pixels
+    #[must_use]
+    pub fn bounds(&self) -> (i64, i64, u64, u64) {
+        let mut monitors = self.monitors.iter();
+        let Some(first) = monitors.next() else {
+            return (0, 0, 0, 0);
+        };
+        let right = |m: &Monitor| i64::from(m.x) + i64::from(m.width);
+        let bottom = |m: &Monitor| i64::from(m.y) + i64::from(m.height);
+        let (left, top, right, bottom) = monitors.fold(
+            (
+                i64::from(first.x),
+                i64::from(first.y),
+                right(first),
+                bottom(first),
+            ),
+            |(l, t, r, b), m| {
+                (
+                    l.min(m.x.into()),
+                    t.min(m.y.into()),
+                    r.max(right(m)),
+                    b.max(bottom(m)),
+                )
+            },
+        );
+        (left, top, right.abs_diff(left), bottom.abs_diff(top))
+    }
+
+    /// Logical position of pixel `x`, `y` of monitor `monitor`
+    ///
+    /// Returns `None` if there is no such monitor or the pixel is outside it.
+    #[must_use]
+    pub fn position(&self, monitor: usize, x: u32, y: u32) -> Option<Position> {
+        let m = self.monitors.get(monitor)?;
+        if x >= m.width || y >= m.height {
+            return None;
+        }
+        self.desktop_position(i64::from(m.x) + i64::from(x), i64::from(m.y) + i64::from(y))
+    }
+
+    /// Logical position of desktop pixel `x`, `y`
+    ///
+    /// The position is the center of the pixel in the logical range, so hosts land on the pixel
+    /// whether they round or truncate when scaling. Returns `None` if the pixel is outside the
+    /// bounding box of the monitors.
+    #[must_use]
+    pub fn desktop_position(&self, x: i64, y: i64) -> Option<Position> {
+        let (left, top, width, height) = self.bounds();
+        Some(Position {
+            x: to_logical(x.checked_sub(left)?, width)?,
+            y: to_logical(y.checked_sub(top)?, height)?,
+        })
+    }
+}
+
+/// Logical position of the center of `pixel` of `size` pixels
+#[allow(clippy::cast_possible_truncation)]
+fn to_logical(pixel: i64, size: u64) -> Option<u16> {
+    let pixel = u64::try_from(pixel).ok().filter(|p| *p < size)?;
+    let range = u64::from(MAX_POSITION) + 1;
+    Some(((2 * pixel + 1) * range / (2 * size)).min(u64::from(MAX_POSITION)) as u16)
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Drag {
+    from: Position,
+    to: Position,
+    buttons: u8,
+    duration: u32,
+    elapsed: u32,
+}
+
+impl Drag {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn position(&self) -> Position {
+        let interpolate = |from: u16, to: u16| {
+            let delta = i64::from(to) - i64::from(from);
+            (i64::from(from) + delta * i64::from(self.elapsed) / i64::from(self.duration)) as u16
+        };
+        Position {
+            x: interpolate(self.from.x, self.to.x),
+            y: interpolate(self.from.y, self.to.y),
+        }
+    }
+}
+
+/// Sequences absolute pointer moves, clicks and drags into reports
+///
+/// Button presses and releases are queued so each is sent in its own report, moves and drags
+/// update the position sent in the following reports. Call [`AbsolutePointer::tick`] every 1ms,
+/// and write [`AbsolutePointer::next_report`] followed by [`AbsolutePointer::commit`] whenever
+/// the endpoint is ready.
+#[derive(Clone, Debug, Default)]
+pub struct AbsolutePointer {
+    state: AbsoluteWheelMouseReport,
+    sent: Option<AbsoluteWheelMouseReport>,
+    queue: Deque<AbsoluteWheelMouseReport, QUEUE_SIZE>,
+    drag: Option<Drag>,
+}
+
+impl AbsolutePointer {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The most recent position
+    #[must_use]
+    pub fn position(&self) -> Position {
+        Position {
+            x: self.state.x,
+            y: self.state.y,
+        }
+    }
+
+    /// The most recently set buttons
+    #[must_use]
+    pub fn buttons(&self) -> u8 {
+        self.state.buttons
+    }
+
+    /// True if a drag is in progress
+    #[must_use]
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    /// Move to `position`, ending any drag in progress there
+    pub fn move_to(&mut self, position: Position) {
+        if let Some(drag) = self.drag.take() {
+            self.state.buttons &= !drag.buttons;
+        }
+        self.set_position(position);
+    }
+
+    /// Press `buttons` at the current position
+    pub fn press(&mut self, buttons: u8) -> Result<(), UsbHidError> {
+        self.reserve(1)?;
+        self.state.buttons |= buttons;
+        self.enqueue();
+        Ok(())
+    }
+
+    /// Release `buttons` at the current position
+    pub fn release(&mut self, buttons: u8) -> Result<(), UsbHidError> {
+        self.reserve(1)?;
+        self.state.buttons &= !buttons;
+        self.enqueue();
+        Ok(())
+    }
+
+    /// Move to `position` and press and release `buttons` there
+    ///
+    /// Returns [`UsbHidError::WouldBlock`] if the queue is too full or a drag is in progress.
+    pub fn click_at(&mut self, position: Position, buttons: u8) -> Result<(), UsbHidError> {
+        if self.drag.is_some() {
+            return Err(UsbHidError::WouldBlock);
+        }
+        self.reserve(3)?;
+        self.set_position(position);
+        self.enqueue();
+        self.state.buttons |= buttons;
+        self.enqueue();
+        self.state.buttons &= !buttons;
+        self.enqueue();
+        Ok(())
+    }
+
+    /// Press `buttons` at the current position, move to `position` over `duration` ms and
+    /// release `buttons` there
+    ///
+    /// Returns [`UsbHidError::WouldBlock`] if the queue is too full or a drag is in progress.
+    pub fn drag_to(
+        &mut self,
+        position: Position,
+        buttons: u8,
+        duration: u32,
+    ) -> Result<(), UsbHidError> {
+        if self.drag.is_some() {
+            return Err(UsbHidError::WouldBlock);
+        }
+        // press now, then final position and release at the end of the drag
+        self.reserve(3)?;
+        self.state.buttons |= buttons;
+        self.enqueue();
+        self.drag = Some(Drag {
+            from: self.position(),
+            to: position,
+            buttons,
+            duration: duration.max(1),
+            elapsed: 0,
+        });
+        Ok(())
+    }
+
+    /// Call every 1ms, advances drags in progress
+    pub fn tick(&mut self) {
+        let Some(mut drag) = self.drag else {
+            return;
+        };
+        drag.elapsed += 1;
+        if drag.elapsed < drag.duration {
+            self.set_position(drag.position());
+            self.drag = Some(drag);
+        } else if self.queue.capacity() - self.queue.len() >= 2 {
+            self.drag = None;
+            self.set_position(drag.to);
+            self.enqueue();
+            self.state.buttons &= !drag.buttons;
+            self.enqueue();
+        } else {
+            // wait for space to queue the final position and release
+            drag.elapsed = drag.duration;
+            self.drag = Some(drag);
+        }
+    }
+
+    /// The next report to send, or `None` if there is nothing to send
+    #[must_use]
+    pub fn next_report(&self) -> Option<AbsoluteWheelMouseReport> {
+        match self.queue.front() {
+            Some(report) => Some(*report),
+            None if self.sent != Some(self.state) => Some(self.state),
+            None => None,
+        }
+    }
+
+    /// Remove the report returned by [`AbsolutePointer::next_report`] once it has been sent
+    pub fn commit(&mut self) {
+        self.sent = Some(self.queue.pop_front().unwrap_or(self.state));
+    }
+
+    /// Discard queued reports and drags, releasing all buttons
+    pub fn reset(&mut self) {
+        *self = Self {
+            state: AbsoluteWheelMouseReport {
+                buttons: 0,
+                ..self.state
+            },
+            ..Self::default()
+        };
+    }
+
+    fn set_position(&mut self, position: Position) {
+        self.state.x = position.x.min(MAX_POSITION);
+        self.state.y = position.y.min(MAX_POSITION);
+    }
+
+    fn reserve(&self, reports: usize) -> Result<(), UsbHidError> {
+        if self.queue.capacity() - self.queue.len() < reports {
+            Err(UsbHidError::WouldBlock)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn enqueue(&mut self) {
+        if self.queue.push_back(self.state).is_err() {
+            error!("Absolute pointer queue full");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    const MONITORS: &[Monitor] = &[
+        Monitor {
+            x: 0,
+            y: 0,
+            width: 1920,
+            height: 1080,
+        },
+        Monitor {
+            x: -1280,
+            y: -200,
+            width: 1280,
+            height: 1024,
+        },
+    ];
+
+    /// Pixel a host lands on when truncating `logical` scaled by `range`
+    fn host_pixel(logical: u16, size: u64, range: u64) -> u64 {
+        u64::from(logical) * size / range
+    }
+
+    #[test]
+    fn positions_land_on_pixels() {
+        let layout = ScreenLayout::new(MONITORS);
+        assert_eq!(layout.bounds(), (-1280, -200, 3200, 1280));
+        for x in 0..3200 {
+            let logical = layout.desktop_position(x - 1280, 0).unwrap().x;
+            let expected = u64::try_from(x).unwrap();
+            assert_eq!(host_pixel(logical, 3200, 32768), expected);
+            assert_eq!(host_pixel(logical, 3200, 32767), expected);
+        }
+        assert_eq!(layout.position(0, 1920, 0), None);
+        assert_eq!(layout.position(2, 0, 0), None);
+        assert_eq!(
+            layout.position(1, 0, 0),
+            layout.desktop_position(-1280, -200)
+        );
+    }
+
+    #[test]
+    fn click_queues_each_edge() {
+        let mut pointer = AbsolutePointer::new();
+        let position = Position { x: 100, y: 200 };
+        pointer.click_at(position, 0x01).unwrap();
+        let mut buttons = [0; 3];
+        for b in &mut buttons {
+            let report = pointer.next_report().unwrap();
+            assert_eq!((report.x, report.y), (100, 200));
+            *b = report.buttons;
+            pointer.commit();
+        }
+        assert_eq!(buttons, [0x00, 0x01, 0x00]);
+        assert_eq!(pointer.next_report(), None);
+    }
+
+    #[test]
+    fn drag_interpolates() {
+        let mut pointer = AbsolutePointer::new();
+        pointer.move_to(Position { x: 0, y: 1000 });
+        pointer
+            .drag_to(Position { x: 1000, y: 0 }, 0x02, 10)
+            .unwrap();
+        assert_eq!(pointer.next_report().unwrap().buttons, 0x02);
+        pointer.commit();
+
+        for _ in 0..5 {
+            pointer.tick();
+        }
+        let report = pointer.next_report().unwrap();
+        assert_eq!((report.buttons, report.x, report.y), (0x02, 500, 500));
+        pointer.commit();
+
+        for _ in 0..5 {
+            pointer.tick();
+        }
+        assert!(!pointer.is_dragging());
+        let report = pointer.next_report().unwrap();
+        assert_eq!((report.buttons, report.x, report.y), (0x02, 1000, 0));
+        pointer.commit();
+        assert_eq!(pointer.next_report().unwrap().buttons, 0x00);
+        pointer.commit();
+        assert_eq!(pointer.next_report(), None);
+    }
+}
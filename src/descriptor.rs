@@ -2,6 +2,8 @@
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 use packed_struct::prelude::*;
 
+pub mod builder;
+
 pub(crate) const USB_CLASS_HID: u8 = 0x03;
 pub(crate) const SPEC_VERSION_1_11: u16 = 0x0111; //1.11 in BCD
 pub(crate) const COUNTRY_CODE_NOT_SUPPORTED: u8 = 0x0;
@@ -46,6 +48,16 @@ pub enum HidProtocol {
     Report = 0x01,
 }
 
+/// Report type of a `GET_REPORT` or `SET_REPORT` request - HID spec 7.2.1
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum ReportType {
+    Input = 0x01,
+    Output = 0x02,
+    Feature = 0x03,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
 #[repr(u8)]
 pub(crate) enum HidRequest {
@@ -0,0 +1,557 @@
+//! Report descriptor builder
+//!
+//! Builds HID report descriptors from short items as described in section 6.2.2 of [Device Class
+//! Definition for Human Interface Devices (Hid) Version 1.11](<https://www.usb.org/sites/default/files/hid1_11.pdf>).
+//!
+//! All methods are `const`, allowing descriptors to be generated at compile time:
+//!
+//! ```
+//! use usbd_human_interface_device::descriptor::builder::*;
+//! use usbd_human_interface_device::page::{Desktop, UsagePage};
+//!
+//! const DESCRIPTOR: ReportDescriptorBuilder<32> = ReportDescriptorBuilder::new()
+//!     .usage_page(UsagePage::GenericDesktop as u16)
+//!     .usage(Desktop::Joystick as u16)
+//!     .collection(Collection::Application)
+//!     .usage(Desktop::X as u16)
+//!     .logical_minimum(0)
+//!     .logical_maximum(255)
+//!     .report_size(8)
+//!     .report_count(1)
+//!     .input(ItemFlags::DATA_VARIABLE_ABSOLUTE)
+//!     .end_collection();
+//!
+//! assert_eq!(DESCRIPTOR.as_bytes().len(), 20);
+//! ```
+//!
+//! The same methods build descriptors at runtime, e.g. from a layout read from flash, and the
+//! result can be moved into a [`heapless::Vec`] to be owned by the interface, see
+//! [`InterfaceBuilder::with_owned_descriptor`](crate::interface::InterfaceBuilder::with_owned_descriptor).
+//! A [`checked`](ReportDescriptorBuilder::checked) builder records exceeding its capacity instead
+//! of panicking, so the conversion fails rather than the firmware:
+//!
+//! ```
+//! use heapless::Vec;
+//! use usbd_human_interface_device::descriptor::builder::*;
+//! use usbd_human_interface_device::page::{Desktop, UsagePage};
+//!
+//! # let axes = [Desktop::X, Desktop::Y, Desktop::Z];
+//! let mut builder = ReportDescriptorBuilder::<32>::checked()
+//!     .usage_page(UsagePage::GenericDesktop as u16)
+//!     .usage(Desktop::Joystick as u16)
+//!     .collection(Collection::Application);
+//! for axis in axes {
+//!     builder = builder.usage(axis as u16);
+//! }
+//! let descriptor: Vec<u8, 32> = builder
+//!     .logical_minimum(0)
+//!     .logical_maximum(255)
+//!     .report_size(8)
+//!     .report_count(3)
+//!     .input(ItemFlags::DATA_VARIABLE_ABSOLUTE)
+//!     .end_collection()
+//!     .try_into()
+//!     .unwrap();
+//!
+//! assert_eq!(descriptor.len(), 24);
+//! ```
+
+use crate::usb_class::UsbHidBuilderError;
+use heapless::Vec;
+
+/// Collection types - HID spec 6.2.2.6
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Collection {
+    Physical = 0x00,
+    Application = 0x01,
+    Logical = 0x02,
+    Report = 0x03,
+    NamedArray = 0x04,
+    UsageSwitch = 0x05,
+    UsageModifier = 0x06,
+}
+
+/// Input, Output and Feature item data flags - HID spec 6.2.2.5
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ItemFlags(u16);
+
+impl ItemFlags {
+    pub const DATA_ARRAY_ABSOLUTE: Self = Self(0x00);
+    pub const CONSTANT: Self = Self(0x01);
+    pub const DATA_VARIABLE_ABSOLUTE: Self = Self(0x02);
+    pub const CONSTANT_VARIABLE_ABSOLUTE: Self = Self(0x03);
+    pub const DATA_VARIABLE_RELATIVE: Self = Self(0x06);
+
+    #[must_use]
+    pub const fn wrap(self) -> Self {
+        Self(self.0 | 0x08)
+    }
+
+    #[must_use]
+    pub const fn non_linear(self) -> Self {
+        Self(self.0 | 0x10)
+    }
+
+    #[must_use]
+    pub const fn no_preferred_state(self) -> Self {
+        Self(self.0 | 0x20)
+    }
+
+    #[must_use]
+    pub const fn null_state(self) -> Self {
+        Self(self.0 | 0x40)
+    }
+
+    /// Only valid for Output and Feature items
+    #[must_use]
+    pub const fn volatile(self) -> Self {
+        Self(self.0 | 0x80)
+    }
+
+    #[must_use]
+    pub const fn buffered_bytes(self) -> Self {
+        Self(self.0 | 0x100)
+    }
+
+    #[must_use]
+    pub const fn bits(self) -> u16 {
+        self.0
+    }
+}
+
+const MAIN_INPUT: u8 = 0x80;
+const MAIN_OUTPUT: u8 = 0x90;
+const MAIN_COLLECTION: u8 = 0xA0;
+const MAIN_FEATURE: u8 = 0xB0;
+const MAIN_END_COLLECTION: u8 = 0xC0;
+
+const GLOBAL_USAGE_PAGE: u8 = 0x04;
+const GLOBAL_LOGICAL_MINIMUM: u8 = 0x14;
+const GLOBAL_LOGICAL_MAXIMUM: u8 = 0x24;
+const GLOBAL_PHYSICAL_MINIMUM: u8 = 0x34;
+const GLOBAL_PHYSICAL_MAXIMUM: u8 = 0x44;
+const GLOBAL_UNIT_EXPONENT: u8 = 0x54;
+const GLOBAL_UNIT: u8 = 0x64;
+const GLOBAL_REPORT_SIZE: u8 = 0x74;
+const GLOBAL_REPORT_ID: u8 = 0x84;
+const GLOBAL_REPORT_COUNT: u8 = 0x94;
+const GLOBAL_PUSH: u8 = 0xA4;
+const GLOBAL_POP: u8 = 0xB4;
+
+const LOCAL_USAGE: u8 = 0x08;
+const LOCAL_USAGE_MINIMUM: u8 = 0x18;
+const LOCAL_USAGE_MAXIMUM: u8 = 0x28;
+
+/// Fixed capacity report descriptor builder
+///
+/// Items are encoded using the smallest data size able to hold their value. Logical and physical
+/// extents are encoded as signed values, all other item data is encoded unsigned.
+///
+/// Exceeding the capacity `N` panics, which is a compile time error when used in a `const` context,
+/// unless the builder was created with [`ReportDescriptorBuilder::checked`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReportDescriptorBuilder<const N: usize> {
+    buffer: [u8; N],
+    len: usize,
+    checked: bool,
+    overflow: bool,
+}
+
+impl<const N: usize> Default for ReportDescriptorBuilder<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> ReportDescriptorBuilder<N> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            buffer: [0; N],
+            len: 0,
+            checked: false,
+            overflow: false,
+        }
+    }
+
+    /// Builder that records exceeding the capacity `N` instead of panicking
+    ///
+    /// For descriptors built at runtime from untrusted data. Items that do not fit are dropped, see
+    /// [`ReportDescriptorBuilder::is_overflow`].
+    #[must_use]
+    pub const fn checked() -> Self {
+        Self {
+            checked: true,
+            ..Self::new()
+        }
+    }
+
+    /// True if items of a [`checked`](ReportDescriptorBuilder::checked) builder exceeded the
+    /// capacity `N`
+    #[must_use]
+    pub const fn is_overflow(&self) -> bool {
+        self.overflow
+    }
+
+    /// The encoded report descriptor, incomplete if [`ReportDescriptorBuilder::is_overflow`]
+    #[must_use]
+    pub const fn as_bytes(&self) -> &[u8] {
+        self.buffer.split_at(self.len).0
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of bytes that can be appended before the capacity `N` is exceeded
+    #[must_use]
+    pub const fn remaining(&self) -> usize {
+        N - self.len
+    }
+
+    /// Append already encoded items, e.g. an existing report descriptor
+    #[must_use]
+    pub const fn extend_from_slice(mut self, items: &[u8]) -> Self {
+        let mut i = 0;
+        while i < items.len() {
+            self = self.byte(items[i]);
+            i += 1;
+        }
+        self
+    }
+
+    const fn byte(mut self, value: u8) -> Self {
+        if self.checked && self.len >= N {
+            self.overflow = true;
+            return self;
+        }
+        core::assert!(self.len < N, "Report descriptor capacity exceeded");
+        self.buffer[self.len] = value;
+        self.len += 1;
+        self
+    }
+
+    const fn item(mut self, prefix: u8, data: u32, size: usize) -> Self {
+        let size_code = match size {
+            0 => 0,
+            1 => 1,
+            2 => 2,
+            _ => 3,
+        };
+        self = self.byte(prefix | size_code);
+        let bytes = data.to_le_bytes();
+        let mut i = 0;
+        while i < size {
+            self = self.byte(bytes[i]);
+            i += 1;
+        }
+        self
+    }
+
+    const fn unsigned_item(self, prefix: u8, value: u32) -> Self {
+        let size = if value <= 0xFF {
+            1
+        } else if value <= 0xFFFF {
+            2
+        } else {
+            4
+        };
+        self.item(prefix, value, size)
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    const fn signed_item(self, prefix: u8, value: i32) -> Self {
+        let size = if value >= i8::MIN as i32 && value <= i8::MAX as i32 {
+            1
+        } else if value >= i16::MIN as i32 && value <= i16::MAX as i32 {
+            2
+        } else {
+            4
+        };
+        self.item(prefix, value as u32, size)
+    }
+
+    #[must_use]
+    pub const fn usage_page(self, page: u16) -> Self {
+        self.unsigned_item(GLOBAL_USAGE_PAGE, page as u32)
+    }
+
+    #[must_use]
+    pub const fn usage(self, usage: u16) -> Self {
+        self.unsigned_item(LOCAL_USAGE, usage as u32)
+    }
+
+    /// Usage including its usage page, overriding the current global usage page
+    #[must_use]
+    pub const fn extended_usage(self, page: u16, usage: u16) -> Self {
+        self.item(LOCAL_USAGE, (page as u32) << 16 | usage as u32, 4)
+    }
+
+    #[must_use]
+    pub const fn usage_minimum(self, usage: u16) -> Self {
+        self.unsigned_item(LOCAL_USAGE_MINIMUM, usage as u32)
+    }
+
+    #[must_use]
+    pub const fn usage_maximum(self, usage: u16) -> Self {
+        self.unsigned_item(LOCAL_USAGE_MAXIMUM, usage as u32)
+    }
+
+    #[must_use]
+    pub const fn logical_minimum(self, value: i32) -> Self {
+        self.signed_item(GLOBAL_LOGICAL_MINIMUM, value)
+    }
+
+    #[must_use]
+    pub const fn logical_maximum(self, value: i32) -> Self {
+        self.signed_item(GLOBAL_LOGICAL_MAXIMUM, value)
+    }
+
+    #[must_use]
+    pub const fn physical_minimum(self, value: i32) -> Self {
+        self.signed_item(GLOBAL_PHYSICAL_MINIMUM, value)
+    }
+
+    #[must_use]
+    pub const fn physical_maximum(self, value: i32) -> Self {
+        self.signed_item(GLOBAL_PHYSICAL_MAXIMUM, value)
+    }
+
+    /// Unit exponent, a 4 bit signed value in the range -8 to 7
+    #[allow(clippy::cast_sign_loss)]
+    #[must_use]
+    pub const fn unit_exponent(self, exponent: i8) -> Self {
+        self.item(GLOBAL_UNIT_EXPONENT, (exponent as u32) & 0x0F, 1)
+    }
+
+    /// Unit, encoded as per HID spec 6.2.2.7 (e.g. `0x14` - English Rotation, degrees)
+    #[must_use]
+    pub const fn unit(self, unit: u32) -> Self {
+        if unit == 0 {
+            self.item(GLOBAL_UNIT, 0, 1)
+        } else {
+            self.unsigned_item(GLOBAL_UNIT, unit)
+        }
+    }
+
+    /// Report size in bits
+    #[must_use]
+    pub const fn report_size(self, bits: u32) -> Self {
+        self.unsigned_item(GLOBAL_REPORT_SIZE, bits)
+    }
+
+    #[must_use]
+    pub const fn report_count(self, count: u32) -> Self {
+        self.unsigned_item(GLOBAL_REPORT_COUNT, count)
+    }
+
+    #[must_use]
+    pub const fn report_id(self, id: u8) -> Self {
+        self.item(GLOBAL_REPORT_ID, id as u32, 1)
+    }
+
+    #[must_use]
+    pub const fn push(self) -> Self {
+        self.item(GLOBAL_PUSH, 0, 0)
+    }
+
+    #[must_use]
+    pub const fn pop(self) -> Self {
+        self.item(GLOBAL_POP, 0, 0)
+    }
+
+    #[must_use]
+    pub const fn collection(self, collection: Collection) -> Self {
+        self.item(MAIN_COLLECTION, collection as u32, 1)
+    }
+
+    #[must_use]
+    pub const fn end_collection(self) -> Self {
+        self.item(MAIN_END_COLLECTION, 0, 0)
+    }
+
+    #[must_use]
+    pub const fn input(self, flags: ItemFlags) -> Self {
+        self.unsigned_item(MAIN_INPUT, flags.bits() as u32)
+    }
+
+    #[must_use]
+    pub const fn output(self, flags: ItemFlags) -> Self {
+        self.unsigned_item(MAIN_OUTPUT, flags.bits() as u32)
+    }
+
+    #[must_use]
+    pub const fn feature(self, flags: ItemFlags) -> Self {
+        self.unsigned_item(MAIN_FEATURE, flags.bits() as u32)
+    }
+
+    /// Constant padding of `bits` bits in an input report
+    #[must_use]
+    pub const fn input_padding(self, bits: u32) -> Self {
+        if bits == 0 {
+            self
+        } else {
+            self.report_size(bits)
+                .report_count(1)
+                .input(ItemFlags::CONSTANT)
+        }
+    }
+
+    /// Constant padding of `bits` bits in an output report
+    #[must_use]
+    pub const fn output_padding(self, bits: u32) -> Self {
+        if bits == 0 {
+            self
+        } else {
+            self.report_size(bits)
+                .report_count(1)
+                .output(ItemFlags::CONSTANT)
+        }
+    }
+
+    /// Constant padding of `bits` bits in a feature report
+    #[must_use]
+    pub const fn feature_padding(self, bits: u32) -> Self {
+        if bits == 0 {
+            self
+        } else {
+            self.report_size(bits)
+                .report_count(1)
+                .feature(ItemFlags::CONSTANT)
+        }
+    }
+}
+
+/// Fails with [`UsbHidBuilderError::SliceLengthOverflow`] if the descriptor exceeded the capacity
+/// of a [`checked`](ReportDescriptorBuilder::checked) builder
+impl<const N: usize> TryFrom<ReportDescriptorBuilder<N>> for Vec<u8, N> {
+    type Error = UsbHidBuilderError;
+
+    fn try_from(builder: ReportDescriptorBuilder<N>) -> Result<Self, Self::Error> {
+        if builder.is_overflow() {
+            error!("Report descriptor capacity exceeded");
+            return Err(UsbHidBuilderError::SliceLengthOverflow);
+        }
+        let mut vec = Self::new();
+        // capacity N holds at most N bytes
+        unwrap!(vec.extend_from_slice(builder.as_bytes()));
+        Ok(vec)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::device::mouse::BOOT_MOUSE_REPORT_DESCRIPTOR;
+    use crate::page::{Desktop, UsagePage};
+
+    #[test]
+    fn boot_mouse_descriptor() {
+        const DESCRIPTOR: ReportDescriptorBuilder<64> = ReportDescriptorBuilder::new()
+            .usage_page(UsagePage::GenericDesktop as u16)
+            .usage(Desktop::Mouse as u16)
+            .collection(Collection::Application)
+            .usage(Desktop::Pointer as u16)
+            .collection(Collection::Physical)
+            .report_count(3)
+            .report_size(1)
+            .usage_page(UsagePage::Button as u16)
+            .usage_minimum(1)
+            .usage_maximum(3)
+            .logical_minimum(0)
+            .logical_maximum(1)
+            .input(ItemFlags::DATA_VARIABLE_ABSOLUTE)
+            .report_count(1)
+            .report_size(5)
+            .input(ItemFlags::CONSTANT)
+            .report_size(8)
+            .report_count(2)
+            .usage_page(UsagePage::GenericDesktop as u16)
+            .usage(Desktop::X as u16)
+            .usage(Desktop::Y as u16)
+            .logical_minimum(-127)
+            .logical_maximum(127)
+            .input(ItemFlags::DATA_VARIABLE_RELATIVE)
+            .end_collection()
+            .end_collection();
+        const BYTES: &[u8] = DESCRIPTOR.as_bytes();
+
+        assert_eq!(BYTES, BOOT_MOUSE_REPORT_DESCRIPTOR);
+    }
+
+    #[test]
+    fn runtime_descriptor() {
+        const DESCRIPTOR: ReportDescriptorBuilder<16> = ReportDescriptorBuilder::new()
+            .usage_page(UsagePage::Button as u16)
+            .usage_minimum(1)
+            .usage_maximum(12)
+            .report_size(1)
+            .report_count(12)
+            .input(ItemFlags::DATA_VARIABLE_ABSOLUTE);
+
+        let buttons = core::hint::black_box(12);
+        let descriptor: Vec<u8, 16> = ReportDescriptorBuilder::new()
+            .usage_page(UsagePage::Button as u16)
+            .usage_minimum(1)
+            .usage_maximum(buttons)
+            .report_size(1)
+            .report_count(buttons.into())
+            .input(ItemFlags::DATA_VARIABLE_ABSOLUTE)
+            .try_into()
+            .unwrap();
+
+        assert_eq!(descriptor, DESCRIPTOR.as_bytes());
+        assert_eq!(DESCRIPTOR.remaining(), 4);
+    }
+
+    #[test]
+    fn checked_overflow() {
+        let builder = ReportDescriptorBuilder::<4>::checked()
+            .usage_page(UsagePage::Button as u16)
+            .usage_minimum(1)
+            .usage_maximum(12);
+
+        assert!(builder.is_overflow());
+        assert_eq!(builder.as_bytes(), [0x05, 0x09, 0x19, 0x01]);
+        assert!(matches!(
+            Vec::try_from(builder),
+            Err(UsbHidBuilderError::SliceLengthOverflow)
+        ));
+
+        let builder = ReportDescriptorBuilder::<4>::checked()
+            .usage_page(UsagePage::Button as u16)
+            .usage_minimum(1);
+        assert!(!builder.is_overflow());
+        assert_eq!(Vec::try_from(builder).unwrap(), [0x05, 0x09, 0x19, 0x01]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Report descriptor capacity exceeded")]
+    fn unchecked_overflow() {
+        let _ = ReportDescriptorBuilder::<2>::new()
+            .usage_page(UsagePage::Button as u16)
+            .usage_minimum(1);
+    }
+
+    #[test]
+    fn signed_item_sizes() {
+        let descriptor = ReportDescriptorBuilder::<32>::new()
+            .logical_minimum(-32768)
+            .logical_maximum(255)
+            .physical_maximum(65535);
+
+        assert_eq!(
+            descriptor.as_bytes(),
+            [0x16, 0x00, 0x80, 0x26, 0xFF, 0x00, 0x47, 0xFF, 0xFF, 0x00, 0x00]
+        );
+    }
+}
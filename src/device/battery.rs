@@ -0,0 +1,258 @@
+//!Battery strength reporting add-on for existing devices
+//!
+//! Adds a Generic Device Controls Battery Strength item, with Battery System Charging and AC
+//! Present flags, to the application collection of another device's report descriptor. The
+//! battery state is a feature report, read by the host with `GET_REPORT`, so that it can be added
+//! to devices that do not use report IDs alongside their existing input report. Linux exposes it
+//! through `upower` as the device's battery.
+//!
+//! ```
+//! use usbd_human_interface_device::descriptor::builder::ReportDescriptorBuilder;
+//! use usbd_human_interface_device::device::battery::{with_battery, WithBatteryConfig};
+//! use usbd_human_interface_device::device::mouse::{BootMouseConfig, BOOT_MOUSE_REPORT_DESCRIPTOR};
+//! use usbd_human_interface_device::descriptor::InterfaceProtocol;
+//! use usbd_human_interface_device::interface::InterfaceBuilder;
+//! use fugit::ExtU32;
+//!
+//! static DESCRIPTOR: ReportDescriptorBuilder<128> = with_battery(BOOT_MOUSE_REPORT_DESCRIPTOR);
+//!
+//! let config = WithBatteryConfig::new(BootMouseConfig::new(
+//!     InterfaceBuilder::new(DESCRIPTOR.as_bytes())
+//!         .unwrap()
+//!         .boot_device(InterfaceProtocol::Mouse)
+//!         .in_endpoint(10.millis())
+//!         .unwrap()
+//!         .without_out_endpoint()
+//!         .build(),
+//! ));
+//! ```
+use crate::descriptor::builder::{ItemFlags, ReportDescriptorBuilder};
+use crate::descriptor::ReportType;
+use crate::device::DeviceClass;
+use crate::interface::UsbAllocatable;
+use crate::page::{BatterySystem, GenericDevice, UsagePage};
+use crate::UsbHidError;
+use packed_struct::prelude::*;
+use usb_device::bus::{StringIndex, UsbBus, UsbBusAllocator};
+use usb_device::UsbError;
+
+/// Largest battery level
+pub const MAX_LEVEL: u8 = 100;
+
+const END_COLLECTION: u8 = 0xC0;
+
+/// Battery report descriptor items
+///
+/// Global items are pushed and popped so the items can be placed anywhere in a descriptor without
+/// affecting the items that follow.
+#[must_use]
+pub const fn battery_items<const N: usize>(
+    descriptor: ReportDescriptorBuilder<N>,
+) -> ReportDescriptorBuilder<N> {
+    descriptor
+        .push()
+        .usage_page(UsagePage::GenericDevice as u16)
+        .usage(GenericDevice::BatteryStrength as u16)
+        .logical_minimum(0)
+        .logical_maximum(MAX_LEVEL as i32)
+        .physical_minimum(0)
+        .physical_maximum(0)
+        .unit(0)
+        .unit_exponent(0)
+        .report_size(8)
+        .report_count(1)
+        .feature(ItemFlags::DATA_VARIABLE_ABSOLUTE)
+        .usage_page(UsagePage::BatterySystem as u16)
+        .usage(BatterySystem::Charging as u16)
+        .usage(BatterySystem::AcPresent as u16)
+        .logical_maximum(1)
+        .report_size(1)
+        .report_count(2)
+        .feature(ItemFlags::DATA_VARIABLE_ABSOLUTE)
+        .feature_padding(6)
+        .pop()
+}
+
+/// Add the battery items to the end of the top level collection of `descriptor`
+///
+/// `descriptor` must not use report IDs and must end with the End Collection of its application
+/// collection, as all the report descriptors in this crate do.
+#[must_use]
+pub const fn with_battery<const N: usize>(descriptor: &[u8]) -> ReportDescriptorBuilder<N> {
+    let (items, end) = descriptor.split_at(descriptor.len() - 1);
+    core::assert!(
+        end[0] == END_COLLECTION,
+        "Descriptor does not end with End Collection"
+    );
+    battery_items(ReportDescriptorBuilder::new().extend_from_slice(items)).end_collection()
+}
+
+/// Battery feature report
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "2", bit_numbering = "msb0")]
+pub struct BatteryReport {
+    /// Battery level from 0 to [`MAX_LEVEL`] percent
+    #[packed_field(bytes = "0")]
+    pub level: u8,
+    #[packed_field(bits = "15")]
+    pub charging: bool,
+    #[packed_field(bits = "14")]
+    pub ac_present: bool,
+}
+
+/// `D` with battery strength reporting
+///
+/// The report descriptor of `D` must include the [`battery_items`], see [`with_battery`].
+pub struct WithBattery<D> {
+    device: D,
+    battery: BatteryReport,
+}
+
+impl<D> WithBattery<D> {
+    pub fn device(&mut self) -> &mut D {
+        &mut self.device
+    }
+
+    #[must_use]
+    pub fn battery(&self) -> BatteryReport {
+        self.battery
+    }
+
+    /// Set the battery level in percent, clamped to [`MAX_LEVEL`]
+    pub fn set_level(&mut self, percent: u8) {
+        self.battery.level = percent.min(MAX_LEVEL);
+    }
+
+    pub fn set_charging(&mut self, charging: bool) {
+        self.battery.charging = charging;
+    }
+
+    pub fn set_ac_present(&mut self, ac_present: bool) {
+        self.battery.ac_present = ac_present;
+    }
+}
+
+impl<'a, D: DeviceClass<'a>> DeviceClass<'a> for WithBattery<D> {
+    type I = D::I;
+
+    fn interface(&mut self) -> &mut Self::I {
+        self.device.interface()
+    }
+
+    fn reset(&mut self) {
+        self.device.reset();
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        self.device.tick()
+    }
+
+    fn set_report(
+        &mut self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &[u8],
+    ) -> usb_device::Result<()> {
+        if report_type == ReportType::Feature {
+            warn!("Unsupported SET_REPORT for the battery feature report");
+            Err(UsbError::Unsupported)
+        } else {
+            self.device.set_report(report_type, report_id, data)
+        }
+    }
+
+    fn get_report(
+        &mut self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &mut [u8],
+    ) -> usb_device::Result<usize> {
+        if report_type == ReportType::Feature {
+            let report = self.battery.pack().map_err(|_| {
+                error!("Error packing BatteryReport");
+                UsbError::ParseError
+            })?;
+            let Some(data) = data.get_mut(..report.len()) else {
+                error!("GetReport failed, buffer too short");
+                return Err(UsbError::BufferOverflow);
+            };
+            data.copy_from_slice(&report);
+            Ok(report.len())
+        } else {
+            self.device.get_report(report_type, report_id, data)
+        }
+    }
+
+    fn get_report_ack(&mut self, report_type: ReportType, report_id: u8) -> usb_device::Result<()> {
+        if report_type == ReportType::Feature {
+            Ok(())
+        } else {
+            self.device.get_report_ack(report_type, report_id)
+        }
+    }
+
+    fn get_string(&mut self, index: StringIndex, lang_id: u16) -> Option<&'a str> {
+        self.device.get_string(index, lang_id)
+    }
+}
+
+/// Config for `C`, adding battery strength reporting
+pub struct WithBatteryConfig<C> {
+    config: C,
+}
+
+impl<C> WithBatteryConfig<C> {
+    /// `config` must use a report descriptor including the [`battery_items`], see [`with_battery`]
+    #[must_use]
+    pub fn new(config: C) -> Self {
+        Self { config }
+    }
+}
+
+impl<'a, B: UsbBus + 'a, C: UsbAllocatable<'a, B>> UsbAllocatable<'a, B> for WithBatteryConfig<C> {
+    type Allocated = WithBattery<C::Allocated>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        WithBattery {
+            device: self.config.allocate(usb_alloc),
+            battery: BatteryReport::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::device::mouse::BOOT_MOUSE_REPORT_DESCRIPTOR;
+
+    #[test]
+    fn battery_report() {
+        let report = BatteryReport {
+            level: 85,
+            charging: false,
+            ac_present: true,
+        };
+        assert_eq!(report.pack().unwrap(), [85, 0x02]);
+    }
+
+    #[test]
+    fn descriptor_inserted_into_collection() {
+        const DESCRIPTOR: ReportDescriptorBuilder<128> = with_battery(BOOT_MOUSE_REPORT_DESCRIPTOR);
+        let descriptor = DESCRIPTOR.as_bytes();
+        let mouse_len = BOOT_MOUSE_REPORT_DESCRIPTOR.len();
+
+        assert_eq!(
+            descriptor[..mouse_len - 1],
+            BOOT_MOUSE_REPORT_DESCRIPTOR[..mouse_len - 1]
+        );
+        // Push, Usage Page (Generic Device Controls), Usage (Battery Strength)
+        assert_eq!(
+            descriptor[mouse_len - 1..mouse_len + 4],
+            [0xA4, 0x05, 0x06, 0x09, 0x20]
+        );
+        // Pop, End Collection
+        assert_eq!(descriptor[descriptor.len() - 2..], [0xB4, 0xC0]);
+    }
+}
@@ -214,3 +214,336 @@ impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for ConsumerControlFixedConfig<'a
         }
     }
 }
+
+/// Consumer code held by a [`ManagedConsumerControl`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct HeldCode {
+    code: Consumer,
+    /// Time in ms until the code is released, `None` if held until released
+    release_in: Option<u32>,
+    /// The code has been sent to the host
+    sent: bool,
+    /// The code is left out of the next report, so that the host sees it pressed again
+    repress: bool,
+}
+
+/// Consumer control tracking up to four held codes
+///
+/// Codes are pressed and released with [`ManagedConsumerControl::press`] and
+/// [`ManagedConsumerControl::release`], or pressed and released after a delay with
+/// [`ManagedConsumerControl::tap`]. Reports that could not be sent are retried and scheduled
+/// releases happen in [`tick`](DeviceClass::tick), which must be called every 1ms. A tapped code
+/// is held for its full duration after it has been sent, so taps are never lost.
+///
+/// Tapping a code that has already been sent releases it for one report and presses it again, so
+/// the host sees a new press. Pressing a held code again only cancels its scheduled release.
+pub struct ManagedConsumerControl<'a, B: UsbBus> {
+    interface: ManagedIdleInterface<'a, B, MultipleConsumerReport, InBytes8, OutNone>,
+    codes: [Option<HeldCode>; 4],
+    pending: bool,
+}
+
+impl<'a, B: UsbBus> ManagedConsumerControl<'a, B> {
+    /// Press `code` until it is released
+    ///
+    /// Codes beyond the fourth held code are ignored.
+    pub fn press(&mut self, code: Consumer) -> Result<(), UsbHidError> {
+        self.hold(code, None);
+        self.write_pending()
+    }
+
+    /// Press `code` and release it `hold_ms` after it has been sent
+    ///
+    /// If `code` is already held and has been sent it is released and pressed again.
+    pub fn tap(&mut self, code: Consumer, hold_ms: u32) -> Result<(), UsbHidError> {
+        self.hold(code, Some(hold_ms));
+        self.write_pending()
+    }
+
+    pub fn release(&mut self, code: Consumer) -> Result<(), UsbHidError> {
+        for slot in &mut self.codes {
+            if slot.is_some_and(|held| held.code == code) {
+                *slot = None;
+                self.pending = true;
+            }
+        }
+        self.write_pending()
+    }
+
+    /// Release all codes
+    pub fn release_all(&mut self) -> Result<(), UsbHidError> {
+        self.codes = [None; 4];
+        self.pending = true;
+        self.write_pending()
+    }
+
+    /// Codes currently held
+    pub fn codes(&self) -> impl Iterator<Item = Consumer> + '_ {
+        self.codes.iter().flatten().map(|held| held.code)
+    }
+
+    fn hold(&mut self, code: Consumer, release_in: Option<u32>) {
+        if let Some(held) = self
+            .codes
+            .iter_mut()
+            .flatten()
+            .find(|held| held.code == code)
+        {
+            held.release_in = release_in;
+            if release_in.is_some() && held.sent {
+                held.repress = true;
+                held.sent = false;
+                self.pending = true;
+            }
+        } else if let Some(slot) = self.codes.iter_mut().find(|slot| slot.is_none()) {
+            *slot = Some(HeldCode {
+                code,
+                release_in,
+                sent: false,
+                repress: false,
+            });
+            self.pending = true;
+        } else {
+            warn!(
+                "Consumer code {:X} ignored, four codes already held",
+                u16::from(code)
+            );
+        }
+    }
+
+    fn report(&self) -> MultipleConsumerReport {
+        let mut report = MultipleConsumerReport::default();
+        let held = self.codes.iter().flatten().filter(|held| !held.repress);
+        for (code, held) in report.codes.iter_mut().zip(held) {
+            *code = held.code;
+        }
+        report
+    }
+
+    fn write_pending(&mut self) -> Result<(), UsbHidError> {
+        if !self.pending {
+            return Ok(());
+        }
+        match self.interface.write_report(&self.report()) {
+            Ok(()) | Err(UsbHidError::Duplicate) => {
+                self.pending = false;
+                for held in self.codes.iter_mut().flatten() {
+                    if held.repress {
+                        // pressed again by the next report
+                        held.repress = false;
+                        self.pending = true;
+                    } else {
+                        held.sent = true;
+                    }
+                }
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for ManagedConsumerControl<'a, B> {
+    type I = Interface<'a, B, InBytes8, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        self.interface.interface()
+    }
+
+    fn reset(&mut self) {
+        self.interface.reset();
+        self.codes = [None; 4];
+        self.pending = false;
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        for slot in &mut self.codes {
+            if let Some(HeldCode {
+                release_in: Some(release_in),
+                sent: true,
+                ..
+            }) = slot
+            {
+                *release_in = release_in.saturating_sub(1);
+                if *release_in == 0 {
+                    *slot = None;
+                    self.pending = true;
+                }
+            }
+        }
+        match self.write_pending() {
+            Ok(()) | Err(UsbHidError::WouldBlock) => {}
+            Err(e) => return Err(e),
+        }
+        self.interface.tick()
+    }
+}
+
+pub struct ManagedConsumerControlConfig<'a> {
+    interface: ManagedIdleInterfaceConfig<'a, MultipleConsumerReport, InBytes8, OutNone>,
+}
+
+impl<'a> ManagedConsumerControlConfig<'a> {
+    #[must_use]
+    pub fn new(
+        interface: ManagedIdleInterfaceConfig<'a, MultipleConsumerReport, InBytes8, OutNone>,
+    ) -> Self {
+        Self { interface }
+    }
+}
+
+impl<'a> Default for ManagedConsumerControlConfig<'a> {
+    #[must_use]
+    fn default() -> Self {
+        Self::new(ManagedIdleInterfaceConfig::new(
+            unwrap!(
+                unwrap!(InterfaceBuilder::new(MULTIPLE_CODE_REPORT_DESCRIPTOR))
+                    .description("Consumer Control")
+                    .in_endpoint(10.millis())
+            )
+            .without_out_endpoint()
+            .build(),
+        ))
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for ManagedConsumerControlConfig<'a> {
+    type Allocated = ManagedConsumerControl<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: self.interface.allocate(usb_alloc),
+            codes: [None; 4],
+            pending: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::test_bus::{build_device, TestBusState, TestUsbBus};
+    use std::vec::Vec;
+
+    /// Codes of each report written to the host
+    fn written(state: &TestBusState) -> Vec<[u16; 4]> {
+        state
+            .take_written()
+            .iter()
+            .map(|report| {
+                let report = MultipleConsumerReport::unpack_from_slice(report).unwrap();
+                report.codes.map(u16::from)
+            })
+            .collect()
+    }
+
+    const MUTE: u16 = Consumer::Mute as u16;
+    const PLAY_PAUSE: u16 = Consumer::PlayPause as u16;
+
+    #[test]
+    fn tap_released_after_hold() {
+        let state = TestBusState::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&state));
+        let mut consumer = ManagedConsumerControlConfig::default().allocate(&usb_alloc);
+        let _usb_dev = build_device(&usb_alloc);
+
+        consumer.tap(Consumer::Mute, 2).unwrap();
+        assert_eq!(written(&state), [[MUTE, 0, 0, 0]]);
+
+        consumer.tick().unwrap();
+        assert!(written(&state).is_empty());
+        consumer.tick().unwrap();
+        assert_eq!(written(&state), [[0; 4]]);
+        assert_eq!(consumer.codes().count(), 0);
+    }
+
+    #[test]
+    fn release_before_tap_is_sent() {
+        let state = TestBusState::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&state));
+        let mut consumer = ManagedConsumerControlConfig::default().allocate(&usb_alloc);
+        let _usb_dev = build_device(&usb_alloc);
+
+        consumer.press(Consumer::Mute).unwrap();
+        assert_eq!(written(&state), [[MUTE, 0, 0, 0]]);
+
+        state.set_blocked(true);
+        assert!(matches!(
+            consumer.tap(Consumer::PlayPause, 10),
+            Err(UsbHidError::WouldBlock)
+        ));
+        // the pending report is now the same as the last report sent
+        consumer.release(Consumer::PlayPause).unwrap();
+
+        state.set_blocked(false);
+        for _ in 0..20 {
+            consumer.tick().unwrap();
+        }
+        assert!(written(&state).is_empty());
+        assert_eq!(consumer.codes().collect::<Vec<_>>(), [Consumer::Mute]);
+    }
+
+    #[test]
+    fn would_block_retried_by_tick() {
+        let state = TestBusState::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&state));
+        let mut consumer = ManagedConsumerControlConfig::default().allocate(&usb_alloc);
+        let _usb_dev = build_device(&usb_alloc);
+
+        consumer.press(Consumer::Mute).unwrap();
+        assert_eq!(written(&state), [[MUTE, 0, 0, 0]]);
+
+        state.set_blocked(true);
+        assert!(matches!(
+            consumer.tap(Consumer::PlayPause, 1),
+            Err(UsbHidError::WouldBlock)
+        ));
+        consumer.tick().unwrap();
+        assert!(written(&state).is_empty());
+
+        // the tap is held for its full duration once it has been sent
+        state.set_blocked(false);
+        consumer.tick().unwrap();
+        assert_eq!(written(&state), [[MUTE, PLAY_PAUSE, 0, 0]]);
+        consumer.tick().unwrap();
+        assert_eq!(written(&state), [[MUTE, 0, 0, 0]]);
+    }
+
+    #[test]
+    fn fifth_code_ignored() {
+        let state = TestBusState::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&state));
+        let mut consumer = ManagedConsumerControlConfig::default().allocate(&usb_alloc);
+        let _usb_dev = build_device(&usb_alloc);
+
+        let codes = [
+            Consumer::Mute,
+            Consumer::PlayPause,
+            Consumer::VolumeIncrement,
+            Consumer::VolumeDecrement,
+        ];
+        for code in codes {
+            consumer.press(code).unwrap();
+        }
+        state.take_written();
+
+        consumer.press(Consumer::Stop).unwrap();
+        assert!(written(&state).is_empty());
+        assert_eq!(consumer.codes().collect::<Vec<_>>(), codes);
+    }
+
+    #[test]
+    fn tap_held_code_presses_again() {
+        let state = TestBusState::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&state));
+        let mut consumer = ManagedConsumerControlConfig::default().allocate(&usb_alloc);
+        let _usb_dev = build_device(&usb_alloc);
+
+        consumer.tap(Consumer::Mute, 10).unwrap();
+        consumer.tap(Consumer::Mute, 10).unwrap();
+        consumer.tick().unwrap();
+        assert_eq!(written(&state), [[MUTE, 0, 0, 0], [0; 4], [MUTE, 0, 0, 0]]);
+    }
+}
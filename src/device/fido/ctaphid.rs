@@ -0,0 +1,797 @@
+//! CTAPHID transport
+//!
+//! Frames FIDO messages into the 64 byte packets exchanged by [`RawFido`] as described in
+//! section 11.2 of the [Client to Authenticator Protocol (CTAP)](https://fidoalliance.org/specs/fido-v2.1-ps-20210615/fido-client-to-authenticator-protocol-v2.1-ps-20210615.html#usb)
+//! specification. [`CtapHid`] allocates channels, reassembles requests from initialization and
+//! continuation packets, answers PING, WINK, INIT and CANCEL itself and passes complete MSG (U2F
+//! APDU) and CBOR (CTAP2) requests to an [`Authenticator`].
+//!
+//! Only one transaction is processed at a time, other channels receive
+//! [`CtapHidError::ChannelBusy`] until the response has been sent. [`CtapHid::tick`] must be
+//! called every 1ms to time out stalled requests and send KEEPALIVE packets while a CBOR request
+//! is pending.
+use crate::device::fido::{RawFido, RawFidoReport};
+use crate::UsbHidError;
+use heapless::Deque;
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use usb_device::bus::UsbBus;
+
+/// Size of a CTAPHID packet
+pub const PACKET_SIZE: usize = 64;
+/// Largest message, 57 bytes in the initialization packet and 59 bytes in each of 128
+/// continuation packets
+pub const MAX_MESSAGE_SIZE: usize = INIT_DATA_SIZE + 128 * CONT_DATA_SIZE;
+/// Channel used by `CTAPHID_INIT` to allocate a new channel
+pub const BROADCAST_CID: u32 = 0xFFFF_FFFF;
+/// CTAPHID protocol version reported by `CTAPHID_INIT`
+pub const PROTOCOL_VERSION: u8 = 2;
+/// Time allowed between the packets of a request before it is abandoned
+pub const TRANSACTION_TIMEOUT_MS: u32 = 500;
+/// Interval between KEEPALIVE packets while a CBOR request is pending
+pub const KEEPALIVE_INTERVAL_MS: u32 = 100;
+
+const INIT_DATA_SIZE: usize = PACKET_SIZE - 7;
+const CONT_DATA_SIZE: usize = PACKET_SIZE - 5;
+const INIT_NONCE_SIZE: usize = 8;
+const COMMAND_FLAG: u8 = 0x80;
+/// Single packet responses, such as errors for other channels, that can be queued at once
+const QUEUE_SIZE: usize = 4;
+
+/// CTAPHID commands
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, IntoPrimitive, TryFromPrimitive)]
+#[repr(u8)]
+pub enum Command {
+    Ping = 0x01,
+    Msg = 0x03,
+    Lock = 0x04,
+    Init = 0x06,
+    Wink = 0x08,
+    Cbor = 0x10,
+    Cancel = 0x11,
+    Keepalive = 0x3B,
+    Error = 0x3F,
+}
+
+/// `CTAPHID_ERROR` codes
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, IntoPrimitive, TryFromPrimitive)]
+#[repr(u8)]
+pub enum CtapHidError {
+    InvalidCommand = 0x01,
+    InvalidParameter = 0x02,
+    InvalidLength = 0x03,
+    InvalidSequence = 0x04,
+    MessageTimeout = 0x05,
+    ChannelBusy = 0x06,
+    LockRequired = 0x0A,
+    InvalidChannel = 0x0B,
+    Other = 0x7F,
+}
+
+/// `CTAPHID_KEEPALIVE` status
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, IntoPrimitive, TryFromPrimitive)]
+#[repr(u8)]
+pub enum KeepaliveStatus {
+    /// The authenticator is still processing the request
+    Processing = 0x01,
+    /// The authenticator is waiting for user presence
+    UserPresenceNeeded = 0x02,
+}
+
+/// Capabilities reported by `CTAPHID_INIT`
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Capabilities {
+    /// Implements [`Authenticator::wink`]
+    pub wink: bool,
+    /// Implements [`Authenticator::cbor`]
+    pub cbor: bool,
+    /// Does not implement [`Authenticator::msg`]
+    pub nmsg: bool,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self {
+            wink: true,
+            cbor: false,
+            nmsg: false,
+        }
+    }
+}
+
+impl Capabilities {
+    #[must_use]
+    pub fn bits(self) -> u8 {
+        u8::from(self.wink) | (u8::from(self.cbor) << 2) | (u8::from(self.nmsg) << 3)
+    }
+}
+
+/// Progress of a request passed to an [`Authenticator`]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Response {
+    /// The response of the given length has been written
+    Complete(usize),
+    /// The request is still being processed, it will be passed to the [`Authenticator`] again
+    Pending(KeepaliveStatus),
+}
+
+/// Application message handling
+///
+/// Requests are passed in full. A request returning [`Response::Pending`] is passed again, with
+/// the same request data, each time [`CtapHid::poll`] is called until it completes.
+pub trait Authenticator {
+    /// Handle a `CTAPHID_MSG` request containing a U2F APDU
+    fn msg(&mut self, _request: &[u8], _response: &mut [u8]) -> Result<Response, CtapHidError> {
+        Err(CtapHidError::InvalidCommand)
+    }
+
+    /// Handle a `CTAPHID_CBOR` request containing a CTAP2 command
+    fn cbor(&mut self, _request: &[u8], _response: &mut [u8]) -> Result<Response, CtapHidError> {
+        Err(CtapHidError::InvalidCommand)
+    }
+
+    /// Identify the authenticator to the user, e.g. by flashing an LED
+    fn wink(&mut self) {}
+
+    /// The host cancelled the pending CBOR request
+    ///
+    /// The request should complete with the CTAP2 `CTAP2_ERR_KEEPALIVE_CANCEL` status.
+    fn cancel(&mut self) {}
+
+    #[must_use]
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+
+    /// Major, minor and build device version numbers reported by `CTAPHID_INIT`
+    #[must_use]
+    fn version(&self) -> [u8; 3] {
+        [0; 3]
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum State {
+    Idle,
+    Receiving {
+        cid: u32,
+        command: u8,
+        len: usize,
+        received: usize,
+        seq: u8,
+        idle_ms: u32,
+    },
+    Processing {
+        cid: u32,
+        command: Command,
+        len: usize,
+        keepalive_ms: u32,
+        status: Option<KeepaliveStatus>,
+    },
+    Responding {
+        cid: u32,
+        command: u8,
+        len: usize,
+        sent: usize,
+        seq: Option<u8>,
+    },
+}
+
+impl State {
+    fn cid(&self) -> Option<u32> {
+        match *self {
+            Self::Idle => None,
+            Self::Receiving { cid, .. }
+            | Self::Processing { cid, .. }
+            | Self::Responding { cid, .. } => Some(cid),
+        }
+    }
+}
+
+fn packet_cid(packet: &[u8]) -> u32 {
+    u32::from_be_bytes([packet[0], packet[1], packet[2], packet[3]])
+}
+
+fn single_packet(cid: u32, command: Command, data: &[u8]) -> RawFidoReport {
+    let mut report = RawFidoReport::default();
+    report.packet[..4].copy_from_slice(&cid.to_be_bytes());
+    report.packet[4] = u8::from(command) | COMMAND_FLAG;
+    #[allow(clippy::cast_possible_truncation)]
+    report.packet[5..7].copy_from_slice(&(data.len() as u16).to_be_bytes());
+    report.packet[7..7 + data.len()].copy_from_slice(data);
+    report
+}
+
+/// CTAPHID transport with messages of up to `N` bytes
+///
+/// `N` defaults to [`MAX_MESSAGE_SIZE`], smaller values reduce the memory used for the request
+/// and response buffers, longer requests are rejected with [`CtapHidError::InvalidLength`].
+pub struct CtapHid<const N: usize = MAX_MESSAGE_SIZE> {
+    state: State,
+    next_cid: u32,
+    request: [u8; N],
+    response: [u8; N],
+    queue: Deque<RawFidoReport, QUEUE_SIZE>,
+}
+
+impl<const N: usize> Default for CtapHid<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> CtapHid<N> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            state: State::Idle,
+            next_cid: 1,
+            request: [0; N],
+            response: [0; N],
+            queue: Deque::new(),
+        }
+    }
+
+    /// Abandon any transaction and discard queued packets
+    pub fn reset(&mut self) {
+        self.state = State::Idle;
+        self.queue.clear();
+    }
+
+    /// True while a request is being received, processed or responded to
+    #[must_use]
+    pub fn is_busy(&self) -> bool {
+        self.state != State::Idle
+    }
+
+    /// Handle a packet received from the host
+    pub fn handle_packet<A: Authenticator>(
+        &mut self,
+        packet: &RawFidoReport,
+        authenticator: &mut A,
+    ) {
+        let packet = &packet.packet;
+        let cid = packet_cid(packet);
+        if packet[4] & COMMAND_FLAG == 0 {
+            self.handle_continuation(cid, packet, authenticator);
+        } else {
+            self.handle_init(cid, packet, authenticator);
+        }
+    }
+
+    fn handle_init<A: Authenticator>(
+        &mut self,
+        cid: u32,
+        packet: &[u8; PACKET_SIZE],
+        authenticator: &mut A,
+    ) {
+        let command = packet[4] & !COMMAND_FLAG;
+        let len = usize::from(u16::from_be_bytes([packet[5], packet[6]]));
+
+        if command == u8::from(Command::Init) {
+            self.init(cid, len, &packet[7..7 + INIT_NONCE_SIZE], authenticator);
+            return;
+        }
+        if cid == 0 || cid == BROADCAST_CID {
+            self.error(cid, CtapHidError::InvalidChannel);
+            return;
+        }
+
+        match self.state {
+            State::Idle => {}
+            // A new request abandons the response to the previous one
+            State::Responding { cid: busy, .. } if busy == cid => {}
+            State::Processing {
+                cid: busy,
+                command: Command::Cbor,
+                ..
+            } if busy == cid && command == u8::from(Command::Cancel) => {
+                authenticator.cancel();
+                return;
+            }
+            State::Receiving { cid: busy, .. } if busy == cid => {
+                self.state = State::Idle;
+                self.error(cid, CtapHidError::InvalidSequence);
+                return;
+            }
+            _ => {
+                if command != u8::from(Command::Cancel) {
+                    self.error(cid, CtapHidError::ChannelBusy);
+                }
+                return;
+            }
+        }
+
+        if command == u8::from(Command::Cancel) {
+            // Nothing to cancel
+            self.state = State::Idle;
+            return;
+        }
+        if len > N {
+            self.state = State::Idle;
+            self.error(cid, CtapHidError::InvalidLength);
+            return;
+        }
+
+        let received = len.min(INIT_DATA_SIZE);
+        self.request[..received].copy_from_slice(&packet[7..7 + received]);
+        self.state = State::Receiving {
+            cid,
+            command,
+            len,
+            received,
+            seq: 0,
+            idle_ms: 0,
+        };
+        if received == len {
+            self.dispatch(authenticator);
+        }
+    }
+
+    fn handle_continuation<A: Authenticator>(
+        &mut self,
+        cid: u32,
+        packet: &[u8; PACKET_SIZE],
+        authenticator: &mut A,
+    ) {
+        let State::Receiving {
+            cid: busy,
+            len,
+            ref mut received,
+            ref mut seq,
+            ref mut idle_ms,
+            ..
+        } = self.state
+        else {
+            trace!("Ignoring spurious CTAPHID continuation packet");
+            return;
+        };
+        if busy != cid {
+            trace!("Ignoring spurious CTAPHID continuation packet");
+            return;
+        }
+        if packet[4] != *seq {
+            self.state = State::Idle;
+            self.error(cid, CtapHidError::InvalidSequence);
+            return;
+        }
+
+        let n = (len - *received).min(CONT_DATA_SIZE);
+        self.request[*received..*received + n].copy_from_slice(&packet[5..5 + n]);
+        *received += n;
+        *seq += 1;
+        *idle_ms = 0;
+
+        if *received == len {
+            self.dispatch(authenticator);
+        }
+    }
+
+    fn init<A: Authenticator>(&mut self, cid: u32, len: usize, nonce: &[u8], authenticator: &A) {
+        if cid == 0 {
+            self.error(cid, CtapHidError::InvalidChannel);
+            return;
+        }
+        if len != INIT_NONCE_SIZE {
+            self.error(cid, CtapHidError::InvalidLength);
+            return;
+        }
+
+        let new_cid = if cid == BROADCAST_CID {
+            self.allocate_cid()
+        } else {
+            // Resynchronise an existing channel, abandoning its transaction
+            if self.state.cid() == Some(cid) {
+                self.state = State::Idle;
+            }
+            cid
+        };
+
+        let mut data = [0; 17];
+        data[..8].copy_from_slice(nonce);
+        data[8..12].copy_from_slice(&new_cid.to_be_bytes());
+        data[12] = PROTOCOL_VERSION;
+        data[13..16].copy_from_slice(&authenticator.version());
+        data[16] = authenticator.capabilities().bits();
+        self.enqueue(single_packet(cid, Command::Init, &data));
+    }
+
+    fn allocate_cid(&mut self) -> u32 {
+        let cid = self.next_cid;
+        self.next_cid = self.next_cid.wrapping_add(1);
+        if self.next_cid == BROADCAST_CID {
+            self.next_cid = 1;
+        }
+        cid
+    }
+
+    fn dispatch<A: Authenticator>(&mut self, authenticator: &mut A) {
+        let State::Receiving {
+            cid, command, len, ..
+        } = self.state
+        else {
+            return;
+        };
+
+        match Command::try_from(command) {
+            Ok(Command::Ping) => {
+                self.response[..len].copy_from_slice(&self.request[..len]);
+                self.respond(cid, Command::Ping, len);
+            }
+            Ok(Command::Wink) if len == 0 => {
+                authenticator.wink();
+                self.respond(cid, Command::Wink, 0);
+            }
+            Ok(Command::Wink) => {
+                self.state = State::Idle;
+                self.error(cid, CtapHidError::InvalidLength);
+            }
+            Ok(command @ (Command::Msg | Command::Cbor)) => {
+                self.state = State::Processing {
+                    cid,
+                    command,
+                    len,
+                    keepalive_ms: 0,
+                    status: None,
+                };
+                self.poll(authenticator);
+            }
+            _ => {
+                self.state = State::Idle;
+                self.error(cid, CtapHidError::InvalidCommand);
+            }
+        }
+    }
+
+    /// Pass a pending request to `authenticator` again
+    pub fn poll<A: Authenticator>(&mut self, authenticator: &mut A) {
+        let State::Processing {
+            cid,
+            command,
+            len,
+            ref mut status,
+            ..
+        } = self.state
+        else {
+            return;
+        };
+
+        let request = &self.request[..len];
+        let result = if command == Command::Msg {
+            authenticator.msg(request, &mut self.response)
+        } else {
+            authenticator.cbor(request, &mut self.response)
+        };
+
+        match result {
+            Ok(Response::Pending(s)) => *status = Some(s),
+            Ok(Response::Complete(len)) if len <= N => self.respond(cid, command, len),
+            Ok(Response::Complete(_)) => {
+                error!("CTAPHID response too long");
+                self.state = State::Idle;
+                self.error(cid, CtapHidError::Other);
+            }
+            Err(e) => {
+                self.state = State::Idle;
+                self.error(cid, e);
+            }
+        }
+    }
+
+    /// Call every 1ms
+    pub fn tick(&mut self) {
+        match self.state {
+            State::Receiving {
+                cid,
+                ref mut idle_ms,
+                ..
+            } => {
+                *idle_ms += 1;
+                if *idle_ms >= TRANSACTION_TIMEOUT_MS {
+                    self.state = State::Idle;
+                    self.error(cid, CtapHidError::MessageTimeout);
+                }
+            }
+            State::Processing {
+                cid,
+                command: Command::Cbor,
+                ref mut keepalive_ms,
+                status: Some(status),
+                ..
+            } => {
+                *keepalive_ms += 1;
+                if *keepalive_ms >= KEEPALIVE_INTERVAL_MS {
+                    *keepalive_ms = 0;
+                    self.enqueue(single_packet(cid, Command::Keepalive, &[u8::from(status)]));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn respond(&mut self, cid: u32, command: Command, len: usize) {
+        self.state = State::Responding {
+            cid,
+            command: command.into(),
+            len,
+            sent: 0,
+            seq: None,
+        };
+    }
+
+    fn error(&mut self, cid: u32, error: CtapHidError) {
+        warn!("CTAPHID error {} on channel {}", u8::from(error), cid);
+        self.enqueue(single_packet(cid, Command::Error, &[error.into()]));
+    }
+
+    fn enqueue(&mut self, packet: RawFidoReport) {
+        if self.queue.push_back(packet).is_err() {
+            warn!("CTAPHID packet queue full, dropping packet");
+        }
+    }
+
+    /// The next packet to send to the host
+    ///
+    /// Call [`CtapHid::packet_sent`] once it has been written.
+    #[must_use]
+    pub fn peek_packet(&self) -> Option<RawFidoReport> {
+        if let Some(packet) = self.queue.front() {
+            return Some(*packet);
+        }
+
+        let State::Responding {
+            cid,
+            command,
+            len,
+            sent,
+            seq,
+        } = self.state
+        else {
+            return None;
+        };
+
+        let mut report = RawFidoReport::default();
+        report.packet[..4].copy_from_slice(&cid.to_be_bytes());
+        match seq {
+            None => {
+                report.packet[4] = command | COMMAND_FLAG;
+                #[allow(clippy::cast_possible_truncation)]
+                report.packet[5..7].copy_from_slice(&(len as u16).to_be_bytes());
+                let n = len.min(INIT_DATA_SIZE);
+                report.packet[7..7 + n].copy_from_slice(&self.response[..n]);
+            }
+            Some(seq) => {
+                report.packet[4] = seq;
+                let n = (len - sent).min(CONT_DATA_SIZE);
+                report.packet[5..5 + n].copy_from_slice(&self.response[sent..sent + n]);
+            }
+        }
+        Some(report)
+    }
+
+    /// The packet returned by [`CtapHid::peek_packet`] has been sent
+    pub fn packet_sent(&mut self) {
+        if self.queue.pop_front().is_some() {
+            return;
+        }
+
+        if let State::Responding {
+            len,
+            ref mut sent,
+            ref mut seq,
+            ..
+        } = self.state
+        {
+            match seq {
+                None => {
+                    *sent = len.min(INIT_DATA_SIZE);
+                    *seq = Some(0);
+                }
+                Some(s) => {
+                    *sent += (len - *sent).min(CONT_DATA_SIZE);
+                    *s += 1;
+                }
+            }
+            if *sent == len {
+                self.state = State::Idle;
+            }
+        }
+    }
+
+    /// Read and handle all available packets from `fido`, then write as many packets as possible
+    pub fn service<B: UsbBus, A: Authenticator>(
+        &mut self,
+        fido: &mut RawFido<'_, B>,
+        authenticator: &mut A,
+    ) -> Result<(), UsbHidError> {
+        loop {
+            match fido.read_report() {
+                Ok(report) => self.handle_packet(&report, authenticator),
+                Err(usb_device::UsbError::WouldBlock) => break,
+                Err(e) => return Err(UsbHidError::from(e)),
+            }
+        }
+        self.poll(authenticator);
+
+        while let Some(packet) = self.peek_packet() {
+            match fido.write_report(&packet) {
+                Ok(()) => self.packet_sent(),
+                Err(UsbHidError::WouldBlock) => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    const CID: u32 = 0x0102_0304;
+
+    #[derive(Default)]
+    struct Echo {
+        pending: usize,
+        winks: usize,
+        cancelled: bool,
+    }
+
+    impl Authenticator for Echo {
+        fn cbor(&mut self, request: &[u8], response: &mut [u8]) -> Result<Response, CtapHidError> {
+            if self.cancelled {
+                response[0] = 0x2D;
+                return Ok(Response::Complete(1));
+            }
+            if self.pending > 0 {
+                self.pending -= 1;
+                return Ok(Response::Pending(KeepaliveStatus::UserPresenceNeeded));
+            }
+            response[..request.len()].copy_from_slice(request);
+            Ok(Response::Complete(request.len()))
+        }
+
+        fn wink(&mut self) {
+            self.winks += 1;
+        }
+
+        fn cancel(&mut self) {
+            self.cancelled = true;
+        }
+    }
+
+    fn init_packet(cid: u32, command: Command, len: usize, data: &[u8]) -> RawFidoReport {
+        let mut report = single_packet(cid, command, data);
+        report.packet[5..7].copy_from_slice(&u16::try_from(len).unwrap().to_be_bytes());
+        report
+    }
+
+    fn cont_packet(cid: u32, seq: u8, data: &[u8]) -> RawFidoReport {
+        let mut report = RawFidoReport::default();
+        report.packet[..4].copy_from_slice(&cid.to_be_bytes());
+        report.packet[4] = seq;
+        report.packet[5..5 + data.len()].copy_from_slice(data);
+        report
+    }
+
+    fn next(ctap: &mut CtapHid) -> Option<[u8; PACKET_SIZE]> {
+        let packet = ctap.peek_packet()?;
+        ctap.packet_sent();
+        Some(packet.packet)
+    }
+
+    #[test]
+    fn init_allocates_channel() {
+        let mut ctap = CtapHid::<64>::new();
+        let nonce = [1, 2, 3, 4, 5, 6, 7, 8];
+        ctap.handle_packet(
+            &init_packet(BROADCAST_CID, Command::Init, 8, &nonce),
+            &mut Echo::default(),
+        );
+
+        let packet = ctap.peek_packet().unwrap().packet;
+        assert_eq!(packet[..7], [0xFF, 0xFF, 0xFF, 0xFF, 0x86, 0x00, 0x11]);
+        assert_eq!(packet[7..15], nonce);
+        assert_eq!(packet[15..19], [0, 0, 0, 1]);
+        assert_eq!(packet[19], PROTOCOL_VERSION);
+        assert_eq!(packet[23], 0x01);
+    }
+
+    #[test]
+    fn ping_fragmentation() {
+        let mut ctap = CtapHid::new();
+        let mut echo = Echo::default();
+        let data: [u8; 200] = core::array::from_fn(|i| u8::try_from(i).unwrap());
+
+        ctap.handle_packet(
+            &init_packet(CID, Command::Ping, 200, &data[..57]),
+            &mut echo,
+        );
+        ctap.handle_packet(&cont_packet(CID, 0, &data[57..116]), &mut echo);
+        ctap.handle_packet(&cont_packet(CID, 1, &data[116..175]), &mut echo);
+        assert!(ctap.peek_packet().is_none());
+        ctap.handle_packet(&cont_packet(CID, 2, &data[175..]), &mut echo);
+
+        let init = next(&mut ctap).unwrap();
+        assert_eq!(init[4..7], [0x81, 0x00, 200]);
+        assert_eq!(init[7..], data[..57]);
+        for seq in 0..3 {
+            let cont = next(&mut ctap).unwrap();
+            assert_eq!(cont[4], seq);
+        }
+        assert!(next(&mut ctap).is_none());
+        assert!(!ctap.is_busy());
+    }
+
+    #[test]
+    fn other_channel_busy() {
+        let mut ctap = CtapHid::new();
+        let mut echo = Echo::default();
+        ctap.handle_packet(&init_packet(CID, Command::Ping, 100, &[0; 57]), &mut echo);
+        ctap.handle_packet(&init_packet(CID + 1, Command::Ping, 1, &[0]), &mut echo);
+
+        let error = next(&mut ctap).unwrap();
+        assert_eq!(error[..8], [0x01, 0x02, 0x03, 0x05, 0xBF, 0x00, 0x01, 0x06]);
+    }
+
+    #[test]
+    fn invalid_sequence() {
+        let mut ctap = CtapHid::new();
+        let mut echo = Echo::default();
+        ctap.handle_packet(&init_packet(CID, Command::Ping, 100, &[0; 57]), &mut echo);
+        ctap.handle_packet(&cont_packet(CID, 1, &[0; 43]), &mut echo);
+
+        assert_eq!(next(&mut ctap).unwrap()[4..8], [0xBF, 0x00, 0x01, 0x04]);
+        assert!(!ctap.is_busy());
+    }
+
+    #[test]
+    fn request_timeout() {
+        let mut ctap = CtapHid::new();
+        ctap.handle_packet(
+            &init_packet(CID, Command::Ping, 100, &[0; 57]),
+            &mut Echo::default(),
+        );
+        for _ in 0..TRANSACTION_TIMEOUT_MS {
+            ctap.tick();
+        }
+
+        assert_eq!(next(&mut ctap).unwrap()[4..8], [0xBF, 0x00, 0x01, 0x05]);
+        assert!(!ctap.is_busy());
+    }
+
+    #[test]
+    fn keepalive_and_cancel() {
+        let mut ctap = CtapHid::new();
+        let mut echo = Echo {
+            pending: 10,
+            ..Default::default()
+        };
+        ctap.handle_packet(&init_packet(CID, Command::Cbor, 1, &[0x04]), &mut echo);
+        for _ in 0..KEEPALIVE_INTERVAL_MS {
+            ctap.tick();
+        }
+        assert_eq!(next(&mut ctap).unwrap()[4..8], [0xBB, 0x00, 0x01, 0x02]);
+
+        ctap.handle_packet(&init_packet(CID, Command::Cancel, 0, &[]), &mut echo);
+        assert!(next(&mut ctap).is_none());
+        ctap.poll(&mut echo);
+        assert_eq!(next(&mut ctap).unwrap()[4..8], [0x90, 0x00, 0x01, 0x2D]);
+    }
+
+    #[test]
+    fn wink() {
+        let mut ctap = CtapHid::new();
+        let mut echo = Echo::default();
+        ctap.handle_packet(&init_packet(CID, Command::Wink, 0, &[]), &mut echo);
+
+        assert_eq!(echo.winks, 1);
+        assert_eq!(next(&mut ctap).unwrap()[4..7], [0x88, 0x00, 0x00]);
+    }
+}
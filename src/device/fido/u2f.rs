@@ -0,0 +1,538 @@
+//! U2F (CTAP1) message handling
+//!
+//! Parses the ISO 7816-4 APDUs carried by `CTAPHID_MSG` requests and dispatches the REGISTER,
+//! AUTHENTICATE and VERSION commands of the [FIDO U2F Raw Message Formats](https://fidoalliance.org/specs/fido-u2f-v1.2-ps-20170411/fido-u2f-raw-message-formats-v1.2-ps-20170411.html).
+//! Key generation, signing and user presence are provided by the application through [`U2fCrypto`].
+//!
+//! [`U2f`] implements [`Authenticator`], combined with [`CtapHid`](super::ctaphid::CtapHid) and
+//! [`RawFido`](super::RawFido) it forms a complete security key.
+use crate::device::fido::ctaphid::{Authenticator, CtapHidError, Response};
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+/// Version string returned by the VERSION command
+pub const VERSION: &[u8] = b"U2F_V2";
+/// Size of the challenge and application parameters
+pub const PARAMETER_SIZE: usize = 32;
+/// Size of an uncompressed P-256 public key
+pub const PUBLIC_KEY_SIZE: usize = 65;
+/// Largest key handle
+pub const MAX_KEY_HANDLE_SIZE: usize = 255;
+/// Largest DER encoded P-256 ECDSA signature
+pub const MAX_SIGNATURE_SIZE: usize = 72;
+
+const REGISTER_RESERVED: u8 = 0x05;
+const REGISTER_SIGNATURE_RESERVED: u8 = 0x00;
+const USER_PRESENCE_FLAG: u8 = 0x01;
+const STATUS_WORD_SIZE: usize = 2;
+const REGISTER_MESSAGE_SIZE: usize = 1 + 2 * PARAMETER_SIZE + MAX_KEY_HANDLE_SIZE + PUBLIC_KEY_SIZE;
+const AUTHENTICATE_MESSAGE_SIZE: usize = 2 * PARAMETER_SIZE + 1 + 4;
+
+/// ISO 7816-4 status words used by U2F
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, IntoPrimitive, TryFromPrimitive)]
+#[repr(u16)]
+pub enum StatusWord {
+    NoError = 0x9000,
+    WrongLength = 0x6700,
+    ConditionsNotSatisfied = 0x6985,
+    WrongData = 0x6A80,
+    InsNotSupported = 0x6D00,
+    ClaNotSupported = 0x6E00,
+}
+
+/// U2F instructions
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, IntoPrimitive, TryFromPrimitive)]
+#[repr(u8)]
+pub enum Instruction {
+    Register = 0x01,
+    Authenticate = 0x02,
+    Version = 0x03,
+}
+
+/// AUTHENTICATE control byte, sent as P1
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, IntoPrimitive, TryFromPrimitive)]
+#[repr(u8)]
+pub enum AuthenticateControl {
+    /// Only check whether the key handle was created by this authenticator for the application
+    CheckOnly = 0x07,
+    /// Sign after testing for user presence
+    EnforceUserPresence = 0x03,
+    /// Sign without testing for user presence
+    DontEnforceUserPresence = 0x08,
+}
+
+/// Command APDU, in short or extended length encoding
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Apdu<'a> {
+    pub cla: u8,
+    pub ins: u8,
+    pub p1: u8,
+    pub p2: u8,
+    pub data: &'a [u8],
+    /// Maximum response length expected by the host, 0 meaning the encoding's maximum
+    pub le: Option<usize>,
+}
+
+impl<'a> Apdu<'a> {
+    pub fn parse(apdu: &'a [u8]) -> Result<Self, StatusWord> {
+        let [cla, ins, p1, p2, body @ ..] = apdu else {
+            return Err(StatusWord::WrongLength);
+        };
+
+        let (data, le): (&[u8], _) = match *body {
+            [] => (&[], None),
+            // Short encoding, Le only
+            [le] => (&[], Some(usize::from(le))),
+            // Extended encoding, Le only
+            [0, le1, le2] => (&[], Some(usize::from(u16::from_be_bytes([le1, le2])))),
+            // Extended encoding with data
+            [0, lc1, lc2, ref rest @ ..] => {
+                let lc = usize::from(u16::from_be_bytes([lc1, lc2]));
+                match rest.len().checked_sub(lc) {
+                    Some(0) => (rest, None),
+                    Some(2) => (
+                        &rest[..lc],
+                        Some(usize::from(u16::from_be_bytes([rest[lc], rest[lc + 1]]))),
+                    ),
+                    _ => return Err(StatusWord::WrongLength),
+                }
+            }
+            // Short encoding with data
+            [lc, ref rest @ ..] => {
+                let lc = usize::from(lc);
+                match rest.len().checked_sub(lc) {
+                    Some(0) => (rest, None),
+                    Some(1) => (&rest[..lc], Some(usize::from(rest[lc]))),
+                    _ => return Err(StatusWord::WrongLength),
+                }
+            }
+        };
+
+        Ok(Self {
+            cla: *cla,
+            ins: *ins,
+            p1: *p1,
+            p2: *p2,
+            data,
+            le,
+        })
+    }
+}
+
+/// Cryptographic operations and user presence, provided by the application
+///
+/// Keys are P-256 ECDSA keys and signatures are DER encoded ECDSA signatures over the SHA-256 hash
+/// of the given message.
+pub trait U2fCrypto {
+    /// Test for user presence, e.g. a button pressed within the last few seconds
+    ///
+    /// Returning `true` should consume the presence so it is not reused for a further request.
+    fn user_present(&mut self) -> bool;
+
+    /// Generate a new key pair for `application`
+    ///
+    /// Writes the uncompressed public key to `public_key` and the key handle identifying the private
+    /// key to `key_handle`, returning the length of the key handle.
+    fn generate_key(
+        &mut self,
+        application: &[u8; PARAMETER_SIZE],
+        public_key: &mut [u8; PUBLIC_KEY_SIZE],
+        key_handle: &mut [u8; MAX_KEY_HANDLE_SIZE],
+    ) -> Result<usize, StatusWord>;
+
+    /// DER encoded X.509 attestation certificate
+    fn attestation_certificate(&self) -> &[u8];
+
+    /// Sign `message` with the attestation private key, returning the signature length
+    fn attestation_sign(
+        &mut self,
+        message: &[u8],
+        signature: &mut [u8; MAX_SIGNATURE_SIZE],
+    ) -> Result<usize, StatusWord>;
+
+    /// True if `key_handle` was generated by this authenticator for `application`
+    fn key_handle_valid(&mut self, application: &[u8; PARAMETER_SIZE], key_handle: &[u8]) -> bool;
+
+    /// Sign `message` with the private key identified by `key_handle`, returning the signature
+    /// length
+    fn sign(
+        &mut self,
+        key_handle: &[u8],
+        message: &[u8],
+        signature: &mut [u8; MAX_SIGNATURE_SIZE],
+    ) -> Result<usize, StatusWord>;
+
+    /// Increment and return the signature counter
+    fn next_counter(&mut self) -> u32;
+
+    /// Identify the authenticator to the user, see [`Authenticator::wink`]
+    fn wink(&mut self) {}
+}
+
+/// Writes a response, tracking the length
+struct Writer<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> Writer<'a> {
+    fn write(&mut self, data: &[u8]) -> Result<(), StatusWord> {
+        let end = self.len + data.len();
+        // Always leave space for the status word
+        if end + STATUS_WORD_SIZE > self.buffer.len() {
+            error!("U2F response buffer too short");
+            return Err(StatusWord::WrongLength);
+        }
+        self.buffer[self.len..end].copy_from_slice(data);
+        self.len = end;
+        Ok(())
+    }
+}
+
+fn parameter(data: &[u8]) -> &[u8; PARAMETER_SIZE] {
+    unwrap!(data[..PARAMETER_SIZE].try_into())
+}
+
+/// U2F authenticator
+pub struct U2f<C> {
+    crypto: C,
+}
+
+impl<C: U2fCrypto> U2f<C> {
+    #[must_use]
+    pub fn new(crypto: C) -> Self {
+        Self { crypto }
+    }
+
+    pub fn crypto(&mut self) -> &mut C {
+        &mut self.crypto
+    }
+
+    /// Handle a request APDU, writing the response data and status word to `response`
+    ///
+    /// Returns the response length. `response` must have space for at least the status word.
+    pub fn handle_apdu(&mut self, request: &[u8], response: &mut [u8]) -> usize {
+        let mut writer = Writer {
+            buffer: response,
+            len: 0,
+        };
+        let status = match self.dispatch(request, &mut writer) {
+            Ok(()) => StatusWord::NoError,
+            Err(status) => {
+                writer.len = 0;
+                status
+            }
+        };
+        let len = writer.len;
+        response[len..len + STATUS_WORD_SIZE].copy_from_slice(&u16::from(status).to_be_bytes());
+        len + STATUS_WORD_SIZE
+    }
+
+    fn dispatch(&mut self, request: &[u8], writer: &mut Writer) -> Result<(), StatusWord> {
+        let apdu = Apdu::parse(request)?;
+        if apdu.cla != 0 {
+            return Err(StatusWord::ClaNotSupported);
+        }
+        match Instruction::try_from(apdu.ins) {
+            Ok(Instruction::Register) => self.register(apdu.data, writer),
+            Ok(Instruction::Authenticate) => self.authenticate(apdu.p1, apdu.data, writer),
+            Ok(Instruction::Version) if apdu.data.is_empty() => writer.write(VERSION),
+            Ok(Instruction::Version) => Err(StatusWord::WrongLength),
+            Err(_) => Err(StatusWord::InsNotSupported),
+        }
+    }
+
+    /// REGISTER, data is the challenge parameter followed by the application parameter
+    fn register(&mut self, data: &[u8], writer: &mut Writer) -> Result<(), StatusWord> {
+        if data.len() != 2 * PARAMETER_SIZE {
+            return Err(StatusWord::WrongLength);
+        }
+        let challenge = parameter(data);
+        let application = parameter(&data[PARAMETER_SIZE..]);
+
+        if !self.crypto.user_present() {
+            return Err(StatusWord::ConditionsNotSatisfied);
+        }
+
+        let mut public_key = [0; PUBLIC_KEY_SIZE];
+        let mut key_handle = [0; MAX_KEY_HANDLE_SIZE];
+        let key_handle_len =
+            self.crypto
+                .generate_key(application, &mut public_key, &mut key_handle)?;
+        let key_handle = &key_handle[..key_handle_len.min(MAX_KEY_HANDLE_SIZE)];
+
+        let mut message = [0; REGISTER_MESSAGE_SIZE];
+        let mut message_len = 0;
+        for part in [
+            &[REGISTER_SIGNATURE_RESERVED][..],
+            application,
+            challenge,
+            key_handle,
+            &public_key,
+        ] {
+            message[message_len..message_len + part.len()].copy_from_slice(part);
+            message_len += part.len();
+        }
+        let mut signature = [0; MAX_SIGNATURE_SIZE];
+        let signature_len = self
+            .crypto
+            .attestation_sign(&message[..message_len], &mut signature)?;
+
+        #[allow(clippy::cast_possible_truncation)]
+        let key_handle_len = key_handle.len() as u8;
+        writer.write(&[REGISTER_RESERVED])?;
+        writer.write(&public_key)?;
+        writer.write(&[key_handle_len])?;
+        writer.write(key_handle)?;
+        writer.write(self.crypto.attestation_certificate())?;
+        writer.write(&signature[..signature_len.min(MAX_SIGNATURE_SIZE)])
+    }
+
+    /// AUTHENTICATE, data is the challenge parameter, the application parameter, the key handle
+    /// length and the key handle
+    fn authenticate(&mut self, p1: u8, data: &[u8], writer: &mut Writer) -> Result<(), StatusWord> {
+        let control = AuthenticateControl::try_from(p1).map_err(|_| StatusWord::WrongData)?;
+        let Some(&key_handle_len) = data.get(2 * PARAMETER_SIZE) else {
+            return Err(StatusWord::WrongLength);
+        };
+        let key_handle = &data[2 * PARAMETER_SIZE + 1..];
+        if key_handle.len() != usize::from(key_handle_len) {
+            return Err(StatusWord::WrongLength);
+        }
+        let challenge = parameter(data);
+        let application = parameter(&data[PARAMETER_SIZE..]);
+
+        if !self.crypto.key_handle_valid(application, key_handle) {
+            return Err(StatusWord::WrongData);
+        }
+
+        let user_presence = match control {
+            // A valid key handle is reported with this status word
+            AuthenticateControl::CheckOnly => return Err(StatusWord::ConditionsNotSatisfied),
+            AuthenticateControl::EnforceUserPresence => {
+                if !self.crypto.user_present() {
+                    return Err(StatusWord::ConditionsNotSatisfied);
+                }
+                USER_PRESENCE_FLAG
+            }
+            AuthenticateControl::DontEnforceUserPresence => 0,
+        };
+        let counter = self.crypto.next_counter().to_be_bytes();
+
+        let mut message = [0; AUTHENTICATE_MESSAGE_SIZE];
+        message[..PARAMETER_SIZE].copy_from_slice(application);
+        message[PARAMETER_SIZE] = user_presence;
+        message[PARAMETER_SIZE + 1..PARAMETER_SIZE + 5].copy_from_slice(&counter);
+        message[PARAMETER_SIZE + 5..].copy_from_slice(challenge);
+        let mut signature = [0; MAX_SIGNATURE_SIZE];
+        let signature_len = self.crypto.sign(key_handle, &message, &mut signature)?;
+
+        writer.write(&[user_presence])?;
+        writer.write(&counter)?;
+        writer.write(&signature[..signature_len.min(MAX_SIGNATURE_SIZE)])
+    }
+}
+
+impl<C: U2fCrypto> Authenticator for U2f<C> {
+    fn msg(&mut self, request: &[u8], response: &mut [u8]) -> Result<Response, CtapHidError> {
+        if response.len() < STATUS_WORD_SIZE {
+            return Err(CtapHidError::Other);
+        }
+        Ok(Response::Complete(self.handle_apdu(request, response)))
+    }
+
+    fn wink(&mut self) {
+        self.crypto.wink();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    /// Software authenticator with a single key, signatures are the first bytes of the message
+    #[derive(Default)]
+    struct SoftCrypto {
+        present: bool,
+        counter: u32,
+    }
+
+    impl U2fCrypto for SoftCrypto {
+        fn user_present(&mut self) -> bool {
+            core::mem::take(&mut self.present)
+        }
+
+        fn generate_key(
+            &mut self,
+            application: &[u8; PARAMETER_SIZE],
+            public_key: &mut [u8; PUBLIC_KEY_SIZE],
+            key_handle: &mut [u8; MAX_KEY_HANDLE_SIZE],
+        ) -> Result<usize, StatusWord> {
+            public_key.fill(0x04);
+            key_handle[..4].copy_from_slice(&application[..4]);
+            Ok(4)
+        }
+
+        fn attestation_certificate(&self) -> &[u8] {
+            &[0x30, 0x00]
+        }
+
+        fn attestation_sign(
+            &mut self,
+            message: &[u8],
+            signature: &mut [u8; MAX_SIGNATURE_SIZE],
+        ) -> Result<usize, StatusWord> {
+            signature[..2].copy_from_slice(&message[..2]);
+            Ok(2)
+        }
+
+        fn key_handle_valid(
+            &mut self,
+            application: &[u8; PARAMETER_SIZE],
+            key_handle: &[u8],
+        ) -> bool {
+            key_handle == &application[..4]
+        }
+
+        fn sign(
+            &mut self,
+            _key_handle: &[u8],
+            message: &[u8],
+            signature: &mut [u8; MAX_SIGNATURE_SIZE],
+        ) -> Result<usize, StatusWord> {
+            signature[..6].copy_from_slice(&message[PARAMETER_SIZE..PARAMETER_SIZE + 6]);
+            Ok(6)
+        }
+
+        fn next_counter(&mut self) -> u32 {
+            self.counter += 1;
+            self.counter
+        }
+    }
+
+    const APPLICATION: [u8; PARAMETER_SIZE] = [0xAA; PARAMETER_SIZE];
+    const CHALLENGE: [u8; PARAMETER_SIZE] = [0xCC; PARAMETER_SIZE];
+
+    fn authenticate_apdu(control: AuthenticateControl, key_handle: &[u8]) -> [u8; 76] {
+        let mut apdu = [0; 76];
+        apdu[..7].copy_from_slice(&[0x00, 0x02, control.into(), 0x00, 0x00, 0x00, 0x45]);
+        apdu[7..39].copy_from_slice(&CHALLENGE);
+        apdu[39..71].copy_from_slice(&APPLICATION);
+        apdu[71] = 4;
+        apdu[72..].copy_from_slice(key_handle);
+        apdu
+    }
+
+    #[test]
+    fn parse_encodings() {
+        let short = Apdu::parse(&[0x00, 0x03, 0x00, 0x00, 0x02, 0xAB, 0xCD, 0x00]).unwrap();
+        assert_eq!(short.data, [0xAB, 0xCD]);
+        assert_eq!(short.le, Some(0));
+
+        let extended = Apdu::parse(&[0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x01, 0xAB]).unwrap();
+        assert_eq!(extended.data, [0xAB]);
+        assert_eq!(extended.le, None);
+
+        let le_only = Apdu::parse(&[0x00, 0x03, 0x00, 0x00, 0x00, 0x01, 0x00]).unwrap();
+        assert!(le_only.data.is_empty());
+        assert_eq!(le_only.le, Some(256));
+
+        assert_eq!(
+            Apdu::parse(&[0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x05, 0xAB]),
+            Err(StatusWord::WrongLength)
+        );
+    }
+
+    #[test]
+    fn version() {
+        let mut u2f = U2f::new(SoftCrypto::default());
+        let mut response = [0; 16];
+        let len = u2f.handle_apdu(&[0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00], &mut response);
+        assert_eq!(response[..len], *b"U2F_V2\x90\x00");
+    }
+
+    #[test]
+    fn unsupported_commands() {
+        let mut u2f = U2f::new(SoftCrypto::default());
+        let mut response = [0; 16];
+
+        let len = u2f.handle_apdu(&[0x00, 0x40, 0x00, 0x00], &mut response);
+        assert_eq!(response[..len], [0x6D, 0x00]);
+        let len = u2f.handle_apdu(&[0x80, 0x03, 0x00, 0x00], &mut response);
+        assert_eq!(response[..len], [0x6E, 0x00]);
+    }
+
+    #[test]
+    fn register() {
+        let mut u2f = U2f::new(SoftCrypto::default());
+        let mut apdu = [0; 71];
+        apdu[..7].copy_from_slice(&[0x00, 0x01, 0x03, 0x00, 0x00, 0x00, 0x40]);
+        apdu[7..39].copy_from_slice(&CHALLENGE);
+        apdu[39..].copy_from_slice(&APPLICATION);
+        let mut response = [0; 256];
+
+        let len = u2f.handle_apdu(&apdu, &mut response);
+        assert_eq!(response[..len], [0x69, 0x85]);
+
+        u2f.crypto().present = true;
+        let len = u2f.handle_apdu(&apdu, &mut response);
+        assert_eq!(len, 1 + 65 + 1 + 4 + 2 + 2 + 2);
+        assert_eq!(response[0], REGISTER_RESERVED);
+        assert_eq!(response[66..71], [4, 0xAA, 0xAA, 0xAA, 0xAA]);
+        // certificate, then signature over 0x00 followed by the application parameter
+        assert_eq!(response[71..len], [0x30, 0x00, 0x00, 0xAA, 0x90, 0x00]);
+    }
+
+    #[test]
+    fn authenticate() {
+        let mut u2f = U2f::new(SoftCrypto::default());
+        let mut response = [0; 64];
+
+        let apdu = authenticate_apdu(AuthenticateControl::CheckOnly, &[0xAA; 4]);
+        let len = u2f.handle_apdu(&apdu, &mut response);
+        assert_eq!(response[..len], [0x69, 0x85]);
+
+        let apdu = authenticate_apdu(AuthenticateControl::CheckOnly, &[0xBB; 4]);
+        let len = u2f.handle_apdu(&apdu, &mut response);
+        assert_eq!(response[..len], [0x6A, 0x80]);
+
+        let apdu = authenticate_apdu(AuthenticateControl::EnforceUserPresence, &[0xAA; 4]);
+        let len = u2f.handle_apdu(&apdu, &mut response);
+        assert_eq!(response[..len], [0x69, 0x85]);
+
+        u2f.crypto().present = true;
+        let len = u2f.handle_apdu(&apdu, &mut response);
+        // user presence, counter, signature over the presence, counter and challenge
+        assert_eq!(
+            response[..len],
+            [0x01, 0, 0, 0, 1, 0x01, 0, 0, 0, 1, 0xCC, 0x90, 0x00]
+        );
+
+        let apdu = authenticate_apdu(AuthenticateControl::DontEnforceUserPresence, &[0xAA; 4]);
+        let len = u2f.handle_apdu(&apdu, &mut response);
+        assert_eq!(response[..5], [0x00, 0, 0, 0, 2]);
+        assert_eq!(response[len - 2..len], [0x90, 0x00]);
+    }
+
+    #[test]
+    fn version_over_ctaphid() {
+        use crate::device::fido::ctaphid::CtapHid;
+        use crate::device::fido::RawFidoReport;
+
+        let mut ctap = CtapHid::<256>::new();
+        let mut u2f = U2f::new(SoftCrypto::default());
+        let mut request = RawFidoReport::default();
+        request.packet[..12]
+            .copy_from_slice(&[1, 2, 3, 4, 0x83, 0x00, 0x05, 0x00, 0x03, 0x00, 0x00, 0x00]);
+
+        ctap.handle_packet(&request, &mut u2f);
+
+        let response = ctap.peek_packet().unwrap().packet;
+        assert_eq!(response[..7], [1, 2, 3, 4, 0x83, 0x00, 0x08]);
+        assert_eq!(response[7..15], *b"U2F_V2\x90\x00");
+    }
+}
@@ -0,0 +1,1663 @@
+//!Force feedback joystick implementing the Physical Interface Device (PID) class
+//!
+//! See [Device Class Definition for Physical Interface Devices (PID) Version 1.0](<https://www.usb.org/sites/default/files/documents/pid1_01.pdf>).
+//!
+//! The host downloads effects with the Create New Effect and Block Load feature reports and the
+//! Set Effect, Set Envelope, Set Condition, Set Periodic, Set Constant Force and Set Ramp Force
+//! output reports, then plays them with the Effect Operation report. The report layout follows the
+//! PID specification closely enough for both the Linux `hid-pidff` driver and `DirectInput`.
+//!
+//! Downloaded effects are kept in a fixed capacity table of [`MAX_EFFECTS`] effects by
+//! [`ForceFeedback`], which combines all playing effects into a single force per axis every
+//! [`tick`](DeviceClass::tick). Read the result with [`ForceFeedbackJoystick::force`] and drive the
+//! actuators from it.
+//!
+//! Forces, levels, coefficients and offsets are in the range `-10_000..=10_000` as used by PID and
+//! `DirectInput`, angles are in hundredths of a degree and times are in ms.
+use crate::descriptor::builder::{Collection, ItemFlags, ReportDescriptorBuilder};
+use crate::interface::InterfaceClass;
+use crate::page::{Desktop, Pid, UsagePage};
+use crate::usb_class::prelude::*;
+use core::default::Default;
+use fugit::ExtU32;
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use packed_struct::prelude::*;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+use usb_device::UsbError;
+
+/// Number of effects that can be downloaded at the same time
+pub const MAX_EFFECTS: usize = 16;
+/// Largest force, level or coefficient
+pub const MAX_FORCE: i16 = 10_000;
+/// Number of force feedback axes, X and Y
+pub const AXES: usize = 2;
+/// Effect duration meaning the effect plays until stopped
+pub const DURATION_INFINITE: u16 = 0xFFFF;
+/// Loop count meaning the effect repeats until stopped
+pub const LOOP_COUNT_INFINITE: u8 = 0xFF;
+
+/// Pool bytes reported to the host for each effect
+const EFFECT_BLOCK_SIZE: u16 = 64;
+const MAX_GAIN: i32 = 0xFF;
+const FULL_CIRCLE: u32 = 36_000;
+
+const JOYSTICK_REPORT_ID: u8 = 0x01;
+const SET_EFFECT_REPORT_ID: u8 = 0x11;
+const SET_ENVELOPE_REPORT_ID: u8 = 0x12;
+const SET_CONDITION_REPORT_ID: u8 = 0x13;
+const SET_PERIODIC_REPORT_ID: u8 = 0x14;
+const SET_CONSTANT_FORCE_REPORT_ID: u8 = 0x15;
+const SET_RAMP_FORCE_REPORT_ID: u8 = 0x16;
+const EFFECT_OPERATION_REPORT_ID: u8 = 0x1A;
+const BLOCK_FREE_REPORT_ID: u8 = 0x1B;
+const DEVICE_CONTROL_REPORT_ID: u8 = 0x1C;
+const DEVICE_GAIN_REPORT_ID: u8 = 0x1D;
+const CREATE_NEW_EFFECT_REPORT_ID: u8 = 0x21;
+const BLOCK_LOAD_REPORT_ID: u8 = 0x22;
+const PID_POOL_REPORT_ID: u8 = 0x23;
+
+/// Effect types, in the order of the Effect Type usages in the report descriptor
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum EffectType {
+    ConstantForce = 1,
+    Ramp = 2,
+    Square = 3,
+    Sine = 4,
+    Triangle = 5,
+    SawtoothUp = 6,
+    SawtoothDown = 7,
+    Spring = 8,
+    Damper = 9,
+    Inertia = 10,
+    Friction = 11,
+}
+
+const EFFECT_TYPE_USAGES: [Pid; 11] = [
+    Pid::EtConstantForce,
+    Pid::EtRamp,
+    Pid::EtSquare,
+    Pid::EtSine,
+    Pid::EtTriangle,
+    Pid::EtSawtoothUp,
+    Pid::EtSawtoothDown,
+    Pid::EtSpring,
+    Pid::EtDamper,
+    Pid::EtInertia,
+    Pid::EtFriction,
+];
+
+impl EffectType {
+    /// Condition effects act on each axis separately, depending on the [`AxisInput`]
+    #[must_use]
+    pub fn is_condition(self) -> bool {
+        matches!(
+            self,
+            Self::Spring | Self::Damper | Self::Inertia | Self::Friction
+        )
+    }
+}
+
+/// Block Load status returned to the host after Create New Effect
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum BlockLoadStatus {
+    Success = 1,
+    Full = 2,
+    #[default]
+    Error = 3,
+}
+
+/// PID Device Control operations
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+enum DeviceControl {
+    EnableActuators = 1,
+    DisableActuators = 2,
+    StopAllEffects = 3,
+    Reset = 4,
+    Pause = 5,
+    Continue = 6,
+}
+
+/// Effect Operation report operations
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+enum EffectOperation {
+    Start = 1,
+    StartSolo = 2,
+    Stop = 3,
+}
+
+/// Effect Block Index field, effects are numbered from 1
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+const fn effect_block_index<const N: usize>(
+    builder: ReportDescriptorBuilder<N>,
+) -> ReportDescriptorBuilder<N> {
+    builder
+        .usage(Pid::EffectBlockIndex as u16)
+        .logical_minimum(1)
+        .logical_maximum(MAX_EFFECTS as i32)
+        .report_size(8)
+        .report_count(1)
+}
+
+/// Effect Type collection usages, followed by an output or feature array item and end collection
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+const fn effect_type_array<const N: usize>(
+    builder: ReportDescriptorBuilder<N>,
+) -> ReportDescriptorBuilder<N> {
+    let mut builder = builder
+        .usage(Pid::EffectType as u16)
+        .collection(Collection::Logical);
+    let mut i = 0;
+    while i < EFFECT_TYPE_USAGES.len() {
+        builder = builder.usage(EFFECT_TYPE_USAGES[i] as u16);
+        i += 1;
+    }
+    builder
+        .logical_minimum(1)
+        .logical_maximum(EFFECT_TYPE_USAGES.len() as i32)
+        .report_size(8)
+        .report_count(1)
+}
+
+/// Time in ms
+const fn milliseconds<const N: usize>(
+    builder: ReportDescriptorBuilder<N>,
+) -> ReportDescriptorBuilder<N> {
+    builder
+        .logical_minimum(0)
+        .logical_maximum(0xFFFF)
+        .unit(0x1001)
+        .unit_exponent(-3)
+        .report_size(16)
+}
+
+/// Angle in hundredths of a degree
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+const fn hundredths_of_degree<const N: usize>(
+    builder: ReportDescriptorBuilder<N>,
+) -> ReportDescriptorBuilder<N> {
+    builder
+        .logical_minimum(0)
+        .logical_maximum(FULL_CIRCLE as i32 - 1)
+        .unit(0x14)
+        .unit_exponent(-2)
+        .report_size(16)
+}
+
+const fn no_unit<const N: usize>(
+    builder: ReportDescriptorBuilder<N>,
+) -> ReportDescriptorBuilder<N> {
+    builder.unit(0).unit_exponent(0)
+}
+
+/// Capacity reserved for the force feedback joystick report descriptor
+pub const DESCRIPTOR_CAPACITY: usize = 1024;
+
+/// Force feedback joystick report descriptor
+///
+/// Two 16 bit axes and 8 buttons in input report 1, followed by the PID output and feature reports
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+pub const FORCE_FEEDBACK_JOYSTICK_DESCRIPTOR: ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> = {
+    let dva = ItemFlags::DATA_VARIABLE_ABSOLUTE;
+    let array = ItemFlags::DATA_ARRAY_ABSOLUTE;
+    let b = ReportDescriptorBuilder::new()
+        .usage_page(UsagePage::GenericDesktop as u16)
+        .usage(Desktop::Joystick as u16)
+        .collection(Collection::Application)
+        // Joystick
+        .report_id(JOYSTICK_REPORT_ID)
+        .usage(Desktop::Pointer as u16)
+        .collection(Collection::Physical)
+        .usage(Desktop::X as u16)
+        .usage(Desktop::Y as u16)
+        .logical_minimum(-(i16::MAX as i32))
+        .logical_maximum(i16::MAX as i32)
+        .report_size(16)
+        .report_count(2)
+        .input(dva)
+        .end_collection()
+        .usage_page(UsagePage::Button as u16)
+        .usage_minimum(1)
+        .usage_maximum(8)
+        .logical_minimum(0)
+        .logical_maximum(1)
+        .report_size(1)
+        .report_count(8)
+        .input(dva)
+        .usage_page(UsagePage::PhysicalInterfaceDevice as u16)
+        // Set Effect
+        .usage(Pid::SetEffectReport as u16)
+        .collection(Collection::Logical)
+        .report_id(SET_EFFECT_REPORT_ID);
+    let b = effect_block_index(b).output(dva);
+    let b = effect_type_array(b).output(array).end_collection();
+    let b = milliseconds(
+        b.usage(Pid::Duration as u16)
+            .usage(Pid::TriggerRepeatInterval as u16)
+            .usage(Pid::SamplePeriod as u16)
+            .usage(Pid::StartDelay as u16),
+    )
+    .report_count(4)
+    .output(dva);
+    let b = no_unit(b)
+        .usage(Pid::Gain as u16)
+        .logical_minimum(0)
+        .logical_maximum(MAX_GAIN)
+        .report_size(8)
+        .report_count(1)
+        .output(dva)
+        .usage(Pid::TriggerButton as u16)
+        .logical_maximum(8)
+        .output(dva)
+        .usage(Pid::AxesEnable as u16)
+        .collection(Collection::Logical)
+        .extended_usage(UsagePage::GenericDesktop as u16, Desktop::X as u16)
+        .extended_usage(UsagePage::GenericDesktop as u16, Desktop::Y as u16)
+        .logical_maximum(1)
+        .report_size(1)
+        .report_count(AXES as u32)
+        .output(dva)
+        .end_collection()
+        .usage(Pid::DirectionEnable as u16)
+        .report_count(1)
+        .output(dva)
+        .output_padding(8 - AXES as u32 - 1)
+        .usage(Pid::Direction as u16)
+        .collection(Collection::Logical)
+        .extended_usage(UsagePage::Ordinal as u16, 1)
+        .extended_usage(UsagePage::Ordinal as u16, 2);
+    let b = hundredths_of_degree(b)
+        .report_count(AXES as u32)
+        .output(dva);
+    let b = no_unit(b)
+        .end_collection()
+        .end_collection()
+        // Set Envelope
+        .usage(Pid::SetEnvelopeReport as u16)
+        .collection(Collection::Logical)
+        .report_id(SET_ENVELOPE_REPORT_ID);
+    let b = effect_block_index(b)
+        .output(dva)
+        .usage(Pid::AttackLevel as u16)
+        .usage(Pid::FadeLevel as u16)
+        .logical_minimum(0)
+        .logical_maximum(MAX_FORCE as i32)
+        .report_size(16)
+        .report_count(2)
+        .output(dva);
+    let b = milliseconds(b.usage(Pid::AttackTime as u16).usage(Pid::FadeTime as u16)).output(dva);
+    let b = no_unit(b)
+        .end_collection()
+        // Set Condition
+        .usage(Pid::SetConditionReport as u16)
+        .collection(Collection::Logical)
+        .report_id(SET_CONDITION_REPORT_ID);
+    let b = effect_block_index(b)
+        .output(dva)
+        .usage(Pid::ParameterBlockOffset as u16)
+        .logical_minimum(0)
+        .logical_maximum(AXES as i32 - 1)
+        .report_size(4)
+        .output(dva)
+        .output_padding(4)
+        .usage(Pid::CpOffset as u16)
+        .usage(Pid::PositiveCoefficient as u16)
+        .usage(Pid::NegativeCoefficient as u16)
+        .logical_minimum(-(MAX_FORCE as i32))
+        .logical_maximum(MAX_FORCE as i32)
+        .report_size(16)
+        .report_count(3)
+        .output(dva)
+        .usage(Pid::PositiveSaturation as u16)
+        .usage(Pid::NegativeSaturation as u16)
+        .usage(Pid::DeadBand as u16)
+        .logical_minimum(0)
+        .output(dva)
+        .end_collection()
+        // Set Periodic
+        .usage(Pid::SetPeriodicReport as u16)
+        .collection(Collection::Logical)
+        .report_id(SET_PERIODIC_REPORT_ID);
+    let b = effect_block_index(b)
+        .output(dva)
+        .usage(Pid::Magnitude as u16)
+        .logical_minimum(0)
+        .logical_maximum(MAX_FORCE as i32)
+        .report_size(16)
+        .output(dva)
+        .usage(Pid::Offset as u16)
+        .logical_minimum(-(MAX_FORCE as i32))
+        .output(dva);
+    let b = hundredths_of_degree(b.usage(Pid::Phase as u16)).output(dva);
+    let b = milliseconds(b.usage(Pid::Period as u16)).output(dva);
+    let b = no_unit(b)
+        .end_collection()
+        // Set Constant Force
+        .usage(Pid::SetConstantForceReport as u16)
+        .collection(Collection::Logical)
+        .report_id(SET_CONSTANT_FORCE_REPORT_ID);
+    let b = effect_block_index(b)
+        .output(dva)
+        .usage(Pid::Magnitude as u16)
+        .logical_minimum(-(MAX_FORCE as i32))
+        .logical_maximum(MAX_FORCE as i32)
+        .report_size(16)
+        .output(dva)
+        .end_collection()
+        // Set Ramp Force
+        .usage(Pid::SetRampForceReport as u16)
+        .collection(Collection::Logical)
+        .report_id(SET_RAMP_FORCE_REPORT_ID);
+    let b = effect_block_index(b)
+        .output(dva)
+        .usage(Pid::RampStart as u16)
+        .usage(Pid::RampEnd as u16)
+        .logical_minimum(-(MAX_FORCE as i32))
+        .logical_maximum(MAX_FORCE as i32)
+        .report_size(16)
+        .report_count(2)
+        .output(dva)
+        .end_collection()
+        // Effect Operation
+        .usage(Pid::EffectOperationReport as u16)
+        .collection(Collection::Logical)
+        .report_id(EFFECT_OPERATION_REPORT_ID);
+    let b = effect_block_index(b)
+        .output(dva)
+        .usage(Pid::EffectOperation as u16)
+        .collection(Collection::Logical)
+        .usage(Pid::OpEffectStart as u16)
+        .usage(Pid::OpEffectStartSolo as u16)
+        .usage(Pid::OpEffectStop as u16)
+        .logical_minimum(1)
+        .logical_maximum(3)
+        .output(array)
+        .end_collection()
+        .usage(Pid::LoopCount as u16)
+        .logical_minimum(0)
+        .logical_maximum(LOOP_COUNT_INFINITE as i32)
+        .output(dva)
+        .end_collection()
+        // Block Free
+        .usage(Pid::PidBlockFreeReport as u16)
+        .collection(Collection::Logical)
+        .report_id(BLOCK_FREE_REPORT_ID);
+    let b = effect_block_index(b)
+        .output(dva)
+        .end_collection()
+        // Device Control
+        .usage(Pid::PidDeviceControlReport as u16)
+        .collection(Collection::Logical)
+        .report_id(DEVICE_CONTROL_REPORT_ID)
+        .usage(Pid::PidDeviceControl as u16)
+        .collection(Collection::Logical)
+        .usage(Pid::DcEnableActuators as u16)
+        .usage(Pid::DcDisableActuators as u16)
+        .usage(Pid::DcStopAllEffects as u16)
+        .usage(Pid::DcDeviceReset as u16)
+        .usage(Pid::DcDevicePause as u16)
+        .usage(Pid::DcDeviceContinue as u16)
+        .logical_minimum(1)
+        .logical_maximum(6)
+        .output(array)
+        .end_collection()
+        .end_collection()
+        // Device Gain
+        .usage(Pid::DeviceGainReport as u16)
+        .collection(Collection::Logical)
+        .report_id(DEVICE_GAIN_REPORT_ID)
+        .usage(Pid::DeviceGain as u16)
+        .logical_minimum(0)
+        .logical_maximum(MAX_GAIN)
+        .output(dva)
+        .end_collection()
+        // Create New Effect
+        .usage(Pid::CreateNewEffectReport as u16)
+        .collection(Collection::Logical)
+        .report_id(CREATE_NEW_EFFECT_REPORT_ID);
+    let b = effect_type_array(b)
+        .feature(array)
+        .end_collection()
+        .extended_usage(UsagePage::GenericDesktop as u16, Desktop::ByteCount as u16)
+        .logical_minimum(0)
+        .logical_maximum(0x1FF)
+        .report_size(16)
+        .feature(dva)
+        .end_collection()
+        // Block Load
+        .usage(Pid::PidBlockLoadReport as u16)
+        .collection(Collection::Logical)
+        .report_id(BLOCK_LOAD_REPORT_ID);
+    let b = effect_block_index(b)
+        .feature(dva)
+        .usage(Pid::BlockLoadStatus as u16)
+        .collection(Collection::Logical)
+        .usage(Pid::BlockLoadSuccess as u16)
+        .usage(Pid::BlockLoadFull as u16)
+        .usage(Pid::BlockLoadError as u16)
+        .logical_maximum(3)
+        .feature(array)
+        .end_collection()
+        .usage(Pid::RamPoolAvailable as u16)
+        .logical_minimum(0)
+        .logical_maximum(0xFFFF)
+        .report_size(16)
+        .feature(dva)
+        .end_collection()
+        // PID Pool
+        .usage(Pid::PidPoolReport as u16)
+        .collection(Collection::Logical)
+        .report_id(PID_POOL_REPORT_ID)
+        .usage(Pid::RamPoolSize as u16)
+        .feature(dva)
+        .usage(Pid::SimultaneousEffectsMax as u16)
+        .logical_maximum(0xFF)
+        .report_size(8)
+        .feature(dva)
+        .usage(Pid::DeviceManagedPool as u16)
+        .usage(Pid::SharedParameterBlocks as u16)
+        .logical_maximum(1)
+        .report_size(1)
+        .report_count(2)
+        .feature(dva)
+        .feature_padding(6)
+        .end_collection();
+    b.end_collection()
+};
+
+/// Force feedback joystick input report
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "5")]
+pub struct ForceFeedbackJoystickReport {
+    #[packed_field]
+    pub x: i16,
+    #[packed_field]
+    pub y: i16,
+    #[packed_field]
+    pub buttons: u8,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "17")]
+struct SetEffectReport {
+    #[packed_field]
+    effect_block_index: u8,
+    #[packed_field]
+    effect_type: u8,
+    #[packed_field]
+    duration: u16,
+    #[packed_field]
+    trigger_repeat_interval: u16,
+    #[packed_field]
+    sample_period: u16,
+    #[packed_field]
+    start_delay: u16,
+    #[packed_field]
+    gain: u8,
+    #[packed_field]
+    trigger_button: u8,
+    /// Axes enable bits followed by the direction enable bit
+    #[packed_field]
+    enable: u8,
+    #[packed_field(element_size_bytes = "2")]
+    direction: [u16; 2],
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "9")]
+struct SetEnvelopeReport {
+    #[packed_field]
+    effect_block_index: u8,
+    #[packed_field]
+    attack_level: u16,
+    #[packed_field]
+    fade_level: u16,
+    #[packed_field]
+    attack_time: u16,
+    #[packed_field]
+    fade_time: u16,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "14")]
+struct SetConditionReport {
+    #[packed_field]
+    effect_block_index: u8,
+    #[packed_field]
+    parameter_block_offset: u8,
+    #[packed_field]
+    center_point_offset: i16,
+    #[packed_field]
+    positive_coefficient: i16,
+    #[packed_field]
+    negative_coefficient: i16,
+    #[packed_field]
+    positive_saturation: u16,
+    #[packed_field]
+    negative_saturation: u16,
+    #[packed_field]
+    dead_band: u16,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "9")]
+struct SetPeriodicReport {
+    #[packed_field]
+    effect_block_index: u8,
+    #[packed_field]
+    magnitude: u16,
+    #[packed_field]
+    offset: i16,
+    #[packed_field]
+    phase: u16,
+    #[packed_field]
+    period: u16,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "3")]
+struct SetConstantForceReport {
+    #[packed_field]
+    effect_block_index: u8,
+    #[packed_field]
+    magnitude: i16,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "5")]
+struct SetRampForceReport {
+    #[packed_field]
+    effect_block_index: u8,
+    #[packed_field]
+    start: i16,
+    #[packed_field]
+    end: i16,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "3")]
+struct EffectOperationReport {
+    #[packed_field]
+    effect_block_index: u8,
+    #[packed_field]
+    operation: u8,
+    #[packed_field]
+    loop_count: u8,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "3")]
+struct CreateNewEffectReport {
+    #[packed_field]
+    effect_type: u8,
+    #[packed_field]
+    byte_count: u16,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "4")]
+struct BlockLoadReport {
+    #[packed_field]
+    effect_block_index: u8,
+    #[packed_field]
+    status: u8,
+    #[packed_field]
+    ram_pool_available: u16,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "4")]
+struct PidPoolReport {
+    #[packed_field]
+    ram_pool_size: u16,
+    #[packed_field]
+    simultaneous_effects_max: u8,
+    /// Device managed pool bit followed by the shared parameter blocks bit
+    #[packed_field]
+    flags: u8,
+}
+
+/// Unpack a report payload, ignoring any trailing bytes
+fn unpack<T: PackedStructSlice>(payload: &[u8]) -> Option<T> {
+    let size = T::packed_bytes_size(None).ok()?;
+    T::unpack_from_slice(payload.get(..size)?).ok()
+}
+
+/// Sine of `angle` hundredths of a degree, scaled to `±10_000`
+///
+/// Uses Bhaskara I's approximation, which is within 0.2% of the true value
+#[allow(clippy::cast_possible_truncation)]
+fn sin(angle: u32) -> i32 {
+    let angle = angle % FULL_CIRCLE;
+    let half = FULL_CIRCLE / 2;
+    let (x, sign) = if angle < half {
+        (angle, 1)
+    } else {
+        (angle - half, -1)
+    };
+    let p = i64::from(x) * i64::from(half - x);
+    sign * (40_000 * p / (405_000_000 - p)) as i32
+}
+
+fn cos(angle: u32) -> i32 {
+    sin(angle + FULL_CIRCLE / 4)
+}
+
+/// Position, velocity and acceleration of an axis, used by condition effects
+///
+/// All values are normalised to the range `-10_000..=10_000`
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub struct AxisInput {
+    pub position: i16,
+    pub velocity: i16,
+    pub acceleration: i16,
+}
+
+/// Envelope applied to constant force and periodic effects
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub struct Envelope {
+    pub attack_level: u16,
+    pub attack_time: u16,
+    pub fade_level: u16,
+    pub fade_time: u16,
+}
+
+/// Condition parameters for a single axis
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub struct Condition {
+    pub center_point_offset: i16,
+    pub positive_coefficient: i16,
+    pub negative_coefficient: i16,
+    pub positive_saturation: u16,
+    pub negative_saturation: u16,
+    pub dead_band: u16,
+}
+
+impl Condition {
+    /// Force opposing the displacement of `metric` from the center point
+    fn force(&self, effect_type: EffectType, metric: i16) -> i32 {
+        let metric = i32::from(metric);
+        let center = i32::from(self.center_point_offset);
+        let dead_band = i32::from(self.dead_band);
+        let (displacement, coefficient, saturation) = if metric > center + dead_band {
+            (
+                metric - center - dead_band,
+                self.positive_coefficient,
+                self.positive_saturation,
+            )
+        } else if metric < center - dead_band {
+            (
+                metric - center + dead_band,
+                self.negative_coefficient,
+                self.negative_saturation,
+            )
+        } else {
+            return 0;
+        };
+        // friction is a constant force against the direction of motion
+        let displacement = if effect_type == EffectType::Friction {
+            displacement.signum() * i32::from(MAX_FORCE)
+        } else {
+            displacement
+        };
+        let saturation = i32::from(saturation);
+        (-displacement * i32::from(coefficient) / i32::from(MAX_FORCE))
+            .clamp(-saturation, saturation)
+    }
+}
+
+/// Periodic effect parameters
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub struct Periodic {
+    pub magnitude: u16,
+    pub offset: i16,
+    /// Hundredths of a degree
+    pub phase: u16,
+    /// ms
+    pub period: u16,
+}
+
+impl Periodic {
+    /// Waveform value at `t` ms, scaled to `±10_000`
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    fn wave(self, effect_type: EffectType, t: u32) -> i32 {
+        let phase = if self.period == 0 {
+            u32::from(self.phase)
+        } else {
+            let period = u32::from(self.period);
+            (t % period) * FULL_CIRCLE / period + u32::from(self.phase)
+        } % FULL_CIRCLE;
+        let max = i32::from(MAX_FORCE);
+        let quarter = (FULL_CIRCLE / 4) as i32;
+        let phase = phase as i32;
+        match effect_type {
+            EffectType::Square if phase < 2 * quarter => max,
+            EffectType::Square => -max,
+            EffectType::Triangle if phase < quarter => phase * max / quarter,
+            EffectType::Triangle if phase < 3 * quarter => (2 * quarter - phase) * max / quarter,
+            EffectType::Triangle => (phase - 4 * quarter) * max / quarter,
+            EffectType::SawtoothUp => phase * max / (2 * quarter) - max,
+            EffectType::SawtoothDown => max - phase * max / (2 * quarter),
+            _ => sin(phase as u32),
+        }
+    }
+}
+
+/// Ramp force parameters
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub struct Ramp {
+    pub start: i16,
+    pub end: i16,
+}
+
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+struct Playback {
+    playing: bool,
+    elapsed: u32,
+    loops: u8,
+}
+
+/// A downloaded effect
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Effect {
+    pub effect_type: EffectType,
+    /// ms, [`DURATION_INFINITE`] plays until stopped
+    pub duration: u16,
+    /// ms, not used for playback
+    pub trigger_repeat_interval: u16,
+    /// ms, effects are evaluated every tick regardless of the sample period
+    pub sample_period: u16,
+    /// ms
+    pub start_delay: u16,
+    pub gain: u8,
+    /// Not used for playback, effects are only started by the host
+    pub trigger_button: u8,
+    pub axes_enable: [bool; AXES],
+    pub direction_enable: bool,
+    /// Hundredths of a degree, clockwise from north
+    pub direction: [u16; AXES],
+    pub envelope: Option<Envelope>,
+    /// Condition parameters for each axis, set by the parameter block offset of Set Condition
+    pub conditions: [Option<Condition>; AXES],
+    pub periodic: Periodic,
+    pub constant_force: i16,
+    pub ramp: Ramp,
+    playback: Playback,
+}
+
+impl Effect {
+    #[must_use]
+    pub fn new(effect_type: EffectType) -> Self {
+        Self {
+            effect_type,
+            duration: DURATION_INFINITE,
+            trigger_repeat_interval: 0,
+            sample_period: 0,
+            start_delay: 0,
+            gain: 0xFF,
+            trigger_button: 0,
+            axes_enable: [true; AXES],
+            direction_enable: false,
+            direction: [0; AXES],
+            envelope: None,
+            conditions: [None; AXES],
+            periodic: Periodic::default(),
+            constant_force: 0,
+            ramp: Ramp::default(),
+            playback: Playback::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn is_playing(&self) -> bool {
+        self.playback.playing
+    }
+
+    fn start(&mut self, loop_count: u8) {
+        self.playback = Playback {
+            playing: true,
+            elapsed: 0,
+            loops: loop_count.max(1),
+        };
+    }
+
+    fn stop(&mut self) {
+        self.playback.playing = false;
+    }
+
+    /// Advance playback by 1ms, repeating or stopping the effect at the end of its duration
+    fn advance(&mut self) {
+        let playback = &mut self.playback;
+        if !playback.playing {
+            return;
+        }
+        playback.elapsed = playback.elapsed.saturating_add(1);
+        if self.duration == DURATION_INFINITE
+            || playback.elapsed < u32::from(self.start_delay) + u32::from(self.duration)
+        {
+            return;
+        }
+        if playback.loops == LOOP_COUNT_INFINITE || playback.loops > 1 {
+            if playback.loops != LOOP_COUNT_INFINITE {
+                playback.loops -= 1;
+            }
+            // the start delay only applies to the first iteration
+            playback.elapsed = self.start_delay.into();
+        } else {
+            playback.playing = false;
+        }
+    }
+
+    /// Time into the current iteration, `None` when stopped or during the start delay
+    fn play_time(&self) -> Option<u32> {
+        if self.playback.playing {
+            self.playback.elapsed.checked_sub(self.start_delay.into())
+        } else {
+            None
+        }
+    }
+
+    /// Scale `magnitude` by the envelope at `t` ms
+    fn envelope_level(&self, magnitude: i32, t: u32) -> i32 {
+        let Some(envelope) = self.envelope else {
+            return magnitude;
+        };
+        let level = magnitude.abs();
+        // t is bounded by the u16 duration when the fade is in effect
+        #[allow(clippy::cast_possible_wrap)]
+        let t = t.min(u32::from(u16::MAX)) as i32;
+        let attack_time = i32::from(envelope.attack_time);
+        let fade_time = i32::from(envelope.fade_time);
+        let duration = i32::from(self.duration);
+        let level = if t < attack_time {
+            let attack_level = i32::from(envelope.attack_level);
+            attack_level + (level - attack_level) * t / attack_time
+        } else if self.duration != DURATION_INFINITE && t + fade_time > duration {
+            let fade_level = i32::from(envelope.fade_level);
+            fade_level + (level - fade_level) * (duration - t).max(0) / fade_time
+        } else {
+            level
+        };
+        if magnitude < 0 {
+            -level
+        } else {
+            level
+        }
+    }
+
+    /// Force applied by this effect on each axis, before the device gain
+    fn force(&self, inputs: &[AxisInput; AXES]) -> [i32; AXES] {
+        let Some(t) = self.play_time() else {
+            return [0; AXES];
+        };
+        let gain = |force: i32| force * i32::from(self.gain) / MAX_GAIN;
+
+        if self.effect_type.is_condition() {
+            let mut force = [0; AXES];
+            for ((f, condition), input) in force.iter_mut().zip(&self.conditions).zip(inputs) {
+                if let Some(condition) = condition {
+                    let metric = match self.effect_type {
+                        EffectType::Spring => input.position,
+                        EffectType::Inertia => input.acceleration,
+                        _ => input.velocity,
+                    };
+                    *f = gain(condition.force(self.effect_type, metric));
+                }
+            }
+            return force;
+        }
+
+        let magnitude = match self.effect_type {
+            EffectType::ConstantForce => self.envelope_level(self.constant_force.into(), t),
+            EffectType::Ramp => {
+                let start = i32::from(self.ramp.start);
+                let end = i32::from(self.ramp.end);
+                if self.duration == DURATION_INFINITE || self.duration == 0 {
+                    start
+                } else {
+                    let duration = i32::from(self.duration);
+                    #[allow(clippy::cast_possible_wrap)]
+                    let t = (t as i32).min(duration);
+                    start + (end - start) * t / duration
+                }
+            }
+            _ => {
+                let magnitude = self.envelope_level(self.periodic.magnitude.into(), t);
+                i32::from(self.periodic.offset)
+                    + magnitude * self.periodic.wave(self.effect_type, t) / i32::from(MAX_FORCE)
+            }
+        };
+        let magnitude = gain(magnitude);
+
+        if self.direction_enable {
+            // polar direction, clockwise from north with north being -Y
+            let direction = u32::from(self.direction[0]);
+            [
+                magnitude * sin(direction) / i32::from(MAX_FORCE),
+                -magnitude * cos(direction) / i32::from(MAX_FORCE),
+            ]
+        } else {
+            self.axes_enable
+                .map(|enabled| if enabled { magnitude } else { 0 })
+        }
+    }
+}
+
+/// Force feedback effect table and PID state
+///
+/// Handles the PID reports and combines the playing effects into a force per axis. Used by
+/// [`ForceFeedbackJoystick`], but has no USB dependencies so can also be driven directly.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ForceFeedback {
+    effects: [Option<Effect>; MAX_EFFECTS],
+    block_load: BlockLoadReport,
+    device_gain: u8,
+    actuators_enabled: bool,
+    paused: bool,
+    inputs: [AxisInput; AXES],
+    force: [i16; AXES],
+}
+
+impl Default for ForceFeedback {
+    fn default() -> Self {
+        Self {
+            effects: [None; MAX_EFFECTS],
+            block_load: BlockLoadReport::default(),
+            device_gain: 0xFF,
+            actuators_enabled: true,
+            paused: false,
+            inputs: [AxisInput::default(); AXES],
+            force: [0; AXES],
+        }
+    }
+}
+
+impl ForceFeedback {
+    /// Free all effects and restore the default device state
+    pub fn reset(&mut self) {
+        *self = Self {
+            inputs: self.inputs,
+            ..Self::default()
+        };
+    }
+
+    /// Combined force of all playing effects for each axis as of the last [`ForceFeedback::tick`]
+    #[must_use]
+    pub fn force(&self) -> [i16; AXES] {
+        self.force
+    }
+
+    /// Axis state used by spring, damper, inertia and friction effects
+    pub fn set_axis_inputs(&mut self, inputs: [AxisInput; AXES]) {
+        self.inputs = inputs;
+    }
+
+    /// Effect with the 1 based `effect_block_index` used by the host
+    #[must_use]
+    pub fn effect(&self, effect_block_index: u8) -> Option<&Effect> {
+        self.effects
+            .get(usize::from(effect_block_index).checked_sub(1)?)?
+            .as_ref()
+    }
+
+    fn effect_mut(&mut self, effect_block_index: u8) -> Option<&mut Effect> {
+        let effect = self
+            .effects
+            .get_mut(usize::from(effect_block_index).checked_sub(1)?)?
+            .as_mut();
+        if effect.is_none() {
+            warn!("Effect {} has not been created", effect_block_index);
+        }
+        effect
+    }
+
+    /// Iterate over the downloaded effects and their effect block indices
+    pub fn effects(&self) -> impl Iterator<Item = (u8, &Effect)> {
+        (1..)
+            .zip(self.effects.iter())
+            .filter_map(|(i, e)| Some((i, e.as_ref()?)))
+    }
+
+    #[must_use]
+    pub fn device_gain(&self) -> u8 {
+        self.device_gain
+    }
+
+    #[must_use]
+    pub fn actuators_enabled(&self) -> bool {
+        self.actuators_enabled
+    }
+
+    #[must_use]
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Advance effect playback by 1ms and recalculate the force
+    pub fn tick(&mut self) {
+        let mut force = [0; AXES];
+        if self.actuators_enabled && !self.paused {
+            for effect in self.effects.iter().flatten() {
+                for (f, effect_force) in force.iter_mut().zip(effect.force(&self.inputs)) {
+                    *f += effect_force;
+                }
+            }
+        }
+        let max = i32::from(MAX_FORCE);
+        self.force = force.map(|f| {
+            let f = (f * i32::from(self.device_gain) / MAX_GAIN).clamp(-max, max);
+            i16::try_from(f).unwrap_or_default()
+        });
+
+        if !self.paused {
+            for effect in self.effects.iter_mut().flatten() {
+                effect.advance();
+            }
+        }
+    }
+
+    /// Handle a PID output report, `data` starts with the report ID
+    pub fn set_output_report(&mut self, data: &[u8]) {
+        let Some((&report_id, payload)) = data.split_first() else {
+            return;
+        };
+        let handled = match report_id {
+            SET_EFFECT_REPORT_ID => unpack(payload).map(|r| self.set_effect(r)),
+            SET_ENVELOPE_REPORT_ID => unpack(payload).map(|r| self.set_envelope(r)),
+            SET_CONDITION_REPORT_ID => unpack(payload).map(|r| self.set_condition(r)),
+            SET_PERIODIC_REPORT_ID => unpack(payload).map(|r: SetPeriodicReport| {
+                if let Some(effect) = self.effect_mut(r.effect_block_index) {
+                    effect.periodic = Periodic {
+                        magnitude: r.magnitude,
+                        offset: r.offset,
+                        phase: r.phase,
+                        period: r.period,
+                    };
+                }
+            }),
+            SET_CONSTANT_FORCE_REPORT_ID => unpack(payload).map(|r: SetConstantForceReport| {
+                if let Some(effect) = self.effect_mut(r.effect_block_index) {
+                    effect.constant_force = r.magnitude;
+                }
+            }),
+            SET_RAMP_FORCE_REPORT_ID => unpack(payload).map(|r: SetRampForceReport| {
+                if let Some(effect) = self.effect_mut(r.effect_block_index) {
+                    effect.ramp = Ramp {
+                        start: r.start,
+                        end: r.end,
+                    };
+                }
+            }),
+            EFFECT_OPERATION_REPORT_ID => unpack(payload).map(|r| self.effect_operation(r)),
+            BLOCK_FREE_REPORT_ID => payload.first().map(|&i| {
+                if let Some(slot) = usize::from(i)
+                    .checked_sub(1)
+                    .and_then(|i| self.effects.get_mut(i))
+                {
+                    *slot = None;
+                }
+            }),
+            DEVICE_CONTROL_REPORT_ID => payload.first().map(|&c| self.device_control(c)),
+            DEVICE_GAIN_REPORT_ID => payload.first().map(|&gain| self.device_gain = gain),
+            _ => None,
+        };
+        if handled.is_none() {
+            warn!("Unhandled PID output report {:X}", report_id);
+        }
+    }
+
+    fn set_effect(&mut self, report: SetEffectReport) {
+        let Ok(effect_type) = EffectType::try_from(report.effect_type) else {
+            warn!("Unsupported effect type {}", report.effect_type);
+            return;
+        };
+        if let Some(effect) = self.effect_mut(report.effect_block_index) {
+            effect.effect_type = effect_type;
+            effect.duration = report.duration;
+            effect.trigger_repeat_interval = report.trigger_repeat_interval;
+            effect.sample_period = report.sample_period;
+            effect.start_delay = report.start_delay;
+            effect.gain = report.gain;
+            effect.trigger_button = report.trigger_button;
+            for (i, enabled) in effect.axes_enable.iter_mut().enumerate() {
+                *enabled = report.enable & (1 << i) != 0;
+            }
+            effect.direction_enable = report.enable & (1 << AXES) != 0;
+            effect.direction = report.direction;
+        }
+    }
+
+    fn set_envelope(&mut self, report: SetEnvelopeReport) {
+        if let Some(effect) = self.effect_mut(report.effect_block_index) {
+            effect.envelope = Some(Envelope {
+                attack_level: report.attack_level,
+                attack_time: report.attack_time,
+                fade_level: report.fade_level,
+                fade_time: report.fade_time,
+            });
+        }
+    }
+
+    fn set_condition(&mut self, report: SetConditionReport) {
+        if let Some(effect) = self.effect_mut(report.effect_block_index) {
+            if let Some(condition) = effect
+                .conditions
+                .get_mut(usize::from(report.parameter_block_offset & 0x0F))
+            {
+                *condition = Some(Condition {
+                    center_point_offset: report.center_point_offset,
+                    positive_coefficient: report.positive_coefficient,
+                    negative_coefficient: report.negative_coefficient,
+                    positive_saturation: report.positive_saturation,
+                    negative_saturation: report.negative_saturation,
+                    dead_band: report.dead_band,
+                });
+            }
+        }
+    }
+
+    fn effect_operation(&mut self, report: EffectOperationReport) {
+        let Ok(operation) = EffectOperation::try_from(report.operation) else {
+            warn!("Unsupported effect operation {}", report.operation);
+            return;
+        };
+        if operation == EffectOperation::StartSolo {
+            for effect in self.effects.iter_mut().flatten() {
+                effect.stop();
+            }
+        }
+        if let Some(effect) = self.effect_mut(report.effect_block_index) {
+            match operation {
+                EffectOperation::Start | EffectOperation::StartSolo => {
+                    effect.start(report.loop_count);
+                }
+                EffectOperation::Stop => effect.stop(),
+            }
+        }
+    }
+
+    fn device_control(&mut self, control: u8) {
+        match DeviceControl::try_from(control) {
+            Ok(DeviceControl::EnableActuators) => self.actuators_enabled = true,
+            Ok(DeviceControl::DisableActuators) => self.actuators_enabled = false,
+            Ok(DeviceControl::StopAllEffects) => {
+                for effect in self.effects.iter_mut().flatten() {
+                    effect.stop();
+                }
+            }
+            Ok(DeviceControl::Reset) => self.reset(),
+            Ok(DeviceControl::Pause) => self.paused = true,
+            Ok(DeviceControl::Continue) => self.paused = false,
+            Err(_) => warn!("Unsupported device control {}", control),
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn ram_pool_available(&self) -> u16 {
+        let free = self.effects.iter().filter(|e| e.is_none()).count();
+        free as u16 * EFFECT_BLOCK_SIZE
+    }
+
+    /// Handle the Create New Effect feature report, `data` starts with the report ID
+    ///
+    /// The outcome is returned to the host by the following Block Load feature report
+    pub fn create_new_effect(&mut self, data: &[u8]) {
+        let report = data
+            .split_first()
+            .filter(|(&id, _)| id == CREATE_NEW_EFFECT_REPORT_ID)
+            .and_then(|(_, payload)| unpack::<CreateNewEffectReport>(payload));
+        let effect_type = report.and_then(|r| EffectType::try_from(r.effect_type).ok());
+
+        let (index, status) = match effect_type {
+            None => (0, BlockLoadStatus::Error),
+            Some(effect_type) => match self.effects.iter().position(Option::is_none) {
+                None => (0, BlockLoadStatus::Full),
+                Some(i) => {
+                    self.effects[i] = Some(Effect::new(effect_type));
+                    (i + 1, BlockLoadStatus::Success)
+                }
+            },
+        };
+        self.block_load = BlockLoadReport {
+            effect_block_index: u8::try_from(index).unwrap_or_default(),
+            status: status.into(),
+            ram_pool_available: self.ram_pool_available(),
+        };
+        trace!("Create new effect: {:?}", status);
+    }
+
+    /// Write the Block Load feature report to `data`, returning the number of bytes written
+    pub fn block_load_report(&self, data: &mut [u8]) -> usb_device::Result<usize> {
+        write_feature_report(BLOCK_LOAD_REPORT_ID, &self.block_load, data)
+    }
+
+    /// Write the PID Pool feature report to `data`, returning the number of bytes written
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn pid_pool_report(&self, data: &mut [u8]) -> usb_device::Result<usize> {
+        let report = PidPoolReport {
+            ram_pool_size: MAX_EFFECTS as u16 * EFFECT_BLOCK_SIZE,
+            simultaneous_effects_max: MAX_EFFECTS as u8,
+            // device managed pool
+            flags: 0x01,
+        };
+        write_feature_report(PID_POOL_REPORT_ID, &report, data)
+    }
+}
+
+fn write_feature_report<T: PackedStruct>(
+    report_id: u8,
+    report: &T,
+    data: &mut [u8],
+) -> usb_device::Result<usize>
+where
+    T::ByteArray: AsRef<[u8]>,
+{
+    let packed = report.pack().map_err(|_| {
+        error!("Error packing feature report");
+        UsbError::ParseError
+    })?;
+    let packed = packed.as_ref();
+    let len = packed.len() + 1;
+    let Some(buffer) = data.get_mut(..len) else {
+        return Err(UsbError::BufferOverflow);
+    };
+    buffer[0] = report_id;
+    buffer[1..].copy_from_slice(packed);
+    Ok(len)
+}
+
+/// Force feedback joystick with two axes and 8 buttons
+///
+/// Call [`DeviceClass::tick`] (via [`UsbHidClass::tick`](crate::usb_class::UsbHidClass::tick))
+/// every 1ms to receive effect updates and play effects.
+pub struct ForceFeedbackJoystick<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes8, OutBytes32, ReportSingle>,
+    force_feedback: ForceFeedback,
+}
+
+impl<'a, B: UsbBus> ForceFeedbackJoystick<'a, B> {
+    pub fn write_report(
+        &mut self,
+        report: &ForceFeedbackJoystickReport,
+    ) -> Result<(), UsbHidError> {
+        let packed = report.pack().map_err(|_| {
+            error!("Error packing ForceFeedbackJoystickReport");
+            UsbHidError::SerializationError
+        })?;
+        let mut data = [JOYSTICK_REPORT_ID; 6];
+        data[1..].copy_from_slice(&packed);
+        self.interface
+            .write_report(&data)
+            .map(|_| ())
+            .map_err(UsbHidError::from)
+    }
+
+    /// Combined force of all playing effects for the X and Y axes
+    #[must_use]
+    pub fn force(&self) -> [i16; AXES] {
+        self.force_feedback.force()
+    }
+
+    /// Axis state used by spring, damper, inertia and friction effects
+    pub fn set_axis_inputs(&mut self, inputs: [AxisInput; AXES]) {
+        self.force_feedback.set_axis_inputs(inputs);
+    }
+
+    #[must_use]
+    pub fn force_feedback(&self) -> &ForceFeedback {
+        &self.force_feedback
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for ForceFeedbackJoystick<'a, B> {
+    type I = Interface<'a, B, InBytes8, OutBytes32, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {
+        self.force_feedback.reset();
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        let mut data = [0; 32];
+        loop {
+            match self.interface.read_report(&mut data) {
+                Ok(n) => self.force_feedback.set_output_report(&data[..n]),
+                Err(UsbError::WouldBlock) => break,
+                Err(e) => return Err(UsbHidError::from(e)),
+            }
+        }
+        self.force_feedback.tick();
+        Ok(())
+    }
+
+    fn set_report(
+        &mut self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &[u8],
+    ) -> usb_device::Result<()> {
+        match (report_type, report_id) {
+            (ReportType::Output, _) => self.force_feedback.set_output_report(data),
+            (ReportType::Feature, CREATE_NEW_EFFECT_REPORT_ID) => {
+                self.force_feedback.create_new_effect(data);
+            }
+            _ => {
+                warn!(
+                    "Unsupported SET_REPORT {:?} report ID {:X}",
+                    report_type, report_id
+                );
+                return Err(UsbError::Unsupported);
+            }
+        }
+        Ok(())
+    }
+
+    fn get_report(
+        &mut self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &mut [u8],
+    ) -> usb_device::Result<usize> {
+        match (report_type, report_id) {
+            (ReportType::Feature, BLOCK_LOAD_REPORT_ID) => {
+                self.force_feedback.block_load_report(data)
+            }
+            (ReportType::Feature, PID_POOL_REPORT_ID) => self.force_feedback.pid_pool_report(data),
+            (ReportType::Feature, _) => Err(UsbError::Unsupported),
+            _ => InterfaceClass::get_report(&self.interface, data),
+        }
+    }
+
+    fn get_report_ack(&mut self, report_type: ReportType, _: u8) -> usb_device::Result<()> {
+        if report_type == ReportType::Feature {
+            Ok(())
+        } else {
+            InterfaceClass::get_report_ack(&mut self.interface)
+        }
+    }
+}
+
+pub struct ForceFeedbackJoystickConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes8, OutBytes32, ReportSingle>,
+}
+
+impl<'a> Default for ForceFeedbackJoystickConfig<'a> {
+    #[must_use]
+    fn default() -> Self {
+        let descriptor: &'static ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> =
+            &FORCE_FEEDBACK_JOYSTICK_DESCRIPTOR;
+        Self::new(
+            unwrap!(
+                unwrap!(unwrap!(InterfaceBuilder::new(descriptor.as_bytes()))
+                    .description("Force Feedback Joystick")
+                    .in_endpoint(10.millis()))
+                .with_out_endpoint(1.millis())
+            )
+            .build(),
+        )
+    }
+}
+
+impl<'a> ForceFeedbackJoystickConfig<'a> {
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes8, OutBytes32, ReportSingle>) -> Self {
+        Self { interface }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for ForceFeedbackJoystickConfig<'a> {
+    type Allocated = ForceFeedbackJoystick<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+            force_feedback: ForceFeedback::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::test_bus::{TestBusState, TestUsbBus};
+    use std::vec::Vec;
+
+    fn output<T: PackedStruct>(ffb: &mut ForceFeedback, report_id: u8, report: &T)
+    where
+        T::ByteArray: AsRef<[u8]>,
+    {
+        let mut data = Vec::from([report_id]);
+        data.extend_from_slice(report.pack().unwrap().as_ref());
+        ffb.set_output_report(&data);
+    }
+
+    fn create(ffb: &mut ForceFeedback, effect_type: EffectType) -> u8 {
+        ffb.create_new_effect(&[CREATE_NEW_EFFECT_REPORT_ID, effect_type.into(), 0, 0]);
+        let mut data = [0; 8];
+        assert_eq!(ffb.block_load_report(&mut data).unwrap(), 5);
+        assert_eq!(data[0], BLOCK_LOAD_REPORT_ID);
+        assert_eq!(data[2], u8::from(BlockLoadStatus::Success));
+        data[1]
+    }
+
+    fn start(ffb: &mut ForceFeedback, effect_block_index: u8) {
+        output(
+            ffb,
+            EFFECT_OPERATION_REPORT_ID,
+            &EffectOperationReport {
+                effect_block_index,
+                operation: EffectOperation::Start.into(),
+                loop_count: 1,
+            },
+        );
+    }
+
+    #[test]
+    fn descriptor_fits() {
+        let len = FORCE_FEEDBACK_JOYSTICK_DESCRIPTOR.len();
+        assert!(len > 0 && len < DESCRIPTOR_CAPACITY);
+    }
+
+    #[test]
+    fn block_load_full() {
+        let mut ffb = ForceFeedback::default();
+        for i in 1..=MAX_EFFECTS {
+            assert_eq!(usize::from(create(&mut ffb, EffectType::Sine)), i);
+        }
+        ffb.create_new_effect(&[CREATE_NEW_EFFECT_REPORT_ID, EffectType::Sine.into(), 0, 0]);
+        let mut data = [0; 8];
+        ffb.block_load_report(&mut data).unwrap();
+        assert_eq!(&data[..5], &[BLOCK_LOAD_REPORT_ID, 0, 2, 0, 0]);
+
+        // freeing a block makes room again
+        ffb.set_output_report(&[BLOCK_FREE_REPORT_ID, 3]);
+        assert_eq!(create(&mut ffb, EffectType::Spring), 3);
+    }
+
+    #[test]
+    fn pid_pool() {
+        let ffb = ForceFeedback::default();
+        let mut data = [0; 8];
+        assert_eq!(ffb.pid_pool_report(&mut data).unwrap(), 5);
+        assert_eq!(&data[..5], &[PID_POOL_REPORT_ID, 0x00, 0x04, 16, 0x01]);
+    }
+
+    #[test]
+    fn constant_force_direction_and_gain() {
+        let mut ffb = ForceFeedback::default();
+        let index = create(&mut ffb, EffectType::ConstantForce);
+        output(
+            &mut ffb,
+            SET_EFFECT_REPORT_ID,
+            &SetEffectReport {
+                effect_block_index: index,
+                effect_type: EffectType::ConstantForce.into(),
+                duration: 1000,
+                gain: 0xFF,
+                // direction enable
+                enable: 0x04,
+                direction: [9000, 0],
+                ..Default::default()
+            },
+        );
+        output(
+            &mut ffb,
+            SET_CONSTANT_FORCE_REPORT_ID,
+            &SetConstantForceReport {
+                effect_block_index: index,
+                magnitude: 5000,
+            },
+        );
+        ffb.tick();
+        assert_eq!(ffb.force(), [0, 0]);
+
+        start(&mut ffb, index);
+        ffb.tick();
+        assert_eq!(ffb.force(), [5000, 0]);
+
+        ffb.set_output_report(&[DEVICE_GAIN_REPORT_ID, 0x80]);
+        ffb.tick();
+        assert_eq!(ffb.force(), [2509, 0]);
+
+        // stops at the end of its duration
+        for _ in 0..1000 {
+            ffb.tick();
+        }
+        assert_eq!(ffb.force(), [0, 0]);
+        assert!(!ffb.effect(index).unwrap().is_playing());
+    }
+
+    #[test]
+    fn infinite_effect_plays_indefinitely() {
+        let mut effect = Effect::new(EffectType::ConstantForce);
+        effect.start(1);
+        effect.playback.elapsed = u32::MAX - 1;
+        effect.advance();
+        effect.advance();
+        assert!(effect.is_playing());
+        assert_eq!(effect.playback.elapsed, u32::MAX);
+    }
+
+    #[test]
+    fn spring_opposes_displacement() {
+        let mut ffb = ForceFeedback::default();
+        let index = create(&mut ffb, EffectType::Spring);
+        output(
+            &mut ffb,
+            SET_CONDITION_REPORT_ID,
+            &SetConditionReport {
+                effect_block_index: index,
+                parameter_block_offset: 0,
+                center_point_offset: 0,
+                positive_coefficient: 5000,
+                negative_coefficient: 5000,
+                positive_saturation: 10_000,
+                negative_saturation: 10_000,
+                dead_band: 1000,
+            },
+        );
+        start(&mut ffb, index);
+
+        let position = |position| AxisInput {
+            position,
+            ..Default::default()
+        };
+        ffb.set_axis_inputs([position(3000), position(3000)]);
+        ffb.tick();
+        // no condition was set for the Y axis
+        assert_eq!(ffb.force(), [-1000, 0]);
+
+        ffb.set_axis_inputs([position(-500), position(0)]);
+        ffb.tick();
+        assert_eq!(ffb.force(), [0, 0]);
+    }
+
+    #[test]
+    fn periodic_waveforms() {
+        let periodic = Periodic {
+            magnitude: 10_000,
+            offset: 0,
+            phase: 0,
+            period: 100,
+        };
+        assert_eq!(periodic.wave(EffectType::Sine, 25), 10_000);
+        assert_eq!(periodic.wave(EffectType::Sine, 75), -10_000);
+        assert_eq!(periodic.wave(EffectType::Square, 60), -10_000);
+        assert_eq!(periodic.wave(EffectType::Triangle, 50), 0);
+        assert_eq!(periodic.wave(EffectType::SawtoothUp, 0), -10_000);
+        assert_eq!(periodic.wave(EffectType::SawtoothDown, 50), 0);
+    }
+
+    #[test]
+    fn device_control() {
+        let mut ffb = ForceFeedback::default();
+        let index = create(&mut ffb, EffectType::ConstantForce);
+        ffb.set_output_report(&[SET_CONSTANT_FORCE_REPORT_ID, index, 0xE8, 0x03]);
+        start(&mut ffb, index);
+        ffb.tick();
+        assert_eq!(ffb.force(), [1000, 1000]);
+
+        ffb.set_output_report(&[
+            DEVICE_CONTROL_REPORT_ID,
+            DeviceControl::DisableActuators.into(),
+        ]);
+        ffb.tick();
+        assert_eq!(ffb.force(), [0, 0]);
+        assert!(!ffb.actuators_enabled());
+
+        ffb.set_output_report(&[DEVICE_CONTROL_REPORT_ID, DeviceControl::Reset.into()]);
+        assert!(ffb.actuators_enabled());
+        assert_eq!(ffb.effects().count(), 0);
+    }
+
+    #[test]
+    fn unknown_feature_report_rejected() {
+        let state = TestBusState::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&state));
+        let mut joystick = ForceFeedbackJoystickConfig::default().allocate(&usb_alloc);
+
+        assert!(joystick
+            .set_report(
+                ReportType::Feature,
+                CREATE_NEW_EFFECT_REPORT_ID,
+                &[
+                    CREATE_NEW_EFFECT_REPORT_ID,
+                    EffectType::ConstantForce.into(),
+                    0x00,
+                    0x00,
+                ]
+            )
+            .is_ok());
+        assert!(matches!(
+            joystick.set_report(
+                ReportType::Feature,
+                PID_POOL_REPORT_ID,
+                &[PID_POOL_REPORT_ID]
+            ),
+            Err(UsbError::Unsupported)
+        ));
+        assert!(matches!(
+            joystick.set_report(ReportType::Input, JOYSTICK_REPORT_ID, &[JOYSTICK_REPORT_ID]),
+            Err(UsbError::Unsupported)
+        ));
+    }
+}
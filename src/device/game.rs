@@ -0,0 +1,204 @@
+//!Game controller with Game Controls usage page controls
+//!
+//! Movement, turn, pitch and lean axes, a point of view hat switch and trigger, fire/jump and
+//! flipper controls are labelled with Game Controls usages, so game engines that read semantic
+//! usages can bind actions without a user mapping. As with the
+//! [simulation controls](crate::device::simulation) the application collection is a Generic
+//! Desktop Joystick so the device is enumerated as a game controller.
+use crate::device::joystick::HatSwitch;
+use crate::usb_class::prelude::*;
+use core::default::Default;
+use fugit::ExtU32;
+use packed_struct::prelude::*;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+
+/// Game controller report descriptor
+///
+/// Signed 16 bit move right/left, move forward/backward, turn right/left, pitch forward/backward
+/// and lean right/left axes, a point of view hat switch, trigger, fire/jump, flipper and secondary
+/// flipper controls and 8 buttons
+#[rustfmt::skip]
+pub const GAME_CONTROLLER_REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01,                   // Usage Page (Generic Desktop)
+    0x09, 0x04,                   // Usage (Joystick)
+    0xa1, 0x01,                   // Collection (Application)
+    0x05, 0x05,                   //   Usage Page (Game Controls)
+    0x09, 0x01,                   //   Usage (3D Game Controller)
+    0xa1, 0x00,                   //   Collection (Physical)
+    0x09, 0x24,                   //     Usage (Move Right/Left)
+    0x09, 0x25,                   //     Usage (Move Forward/Backward)
+    0x09, 0x21,                   //     Usage (Turn Right/Left)
+    0x09, 0x22,                   //     Usage (Pitch Forward/Backward)
+    0x09, 0x27,                   //     Usage (Lean Right/Left)
+    0x16, 0x01, 0x80,             //     Logical Minimum (-32767)
+    0x26, 0xff, 0x7f,             //     Logical Maximum (32767)
+    0x75, 0x10,                   //     Report Size (16)
+    0x95, 0x05,                   //     Report Count (5)
+    0x81, 0x02,                   //     Input (Data, Variable, Absolute)
+    0x09, 0x20,                   //     Usage (Point of View)
+    0xa1, 0x00,                   //     Collection (Physical)
+    0x05, 0x01,                   //       Usage Page (Generic Desktop)
+    0x09, 0x39,                   //       Usage (Hat switch)
+    0x15, 0x00,                   //       Logical Minimum (0)
+    0x25, 0x07,                   //       Logical Maximum (7)
+    0x35, 0x00,                   //       Physical Minimum (0)
+    0x46, 0x3b, 0x01,             //       Physical Maximum (315)
+    0x65, 0x14,                   //       Unit (English Rotation, Degrees)
+    0x75, 0x04,                   //       Report Size (4)
+    0x95, 0x01,                   //       Report Count (1)
+    0x81, 0x42,                   //       Input (Data, Variable, Absolute, Null State)
+    0x65, 0x00,                   //       Unit (None)
+    0x45, 0x00,                   //       Physical Maximum (0)
+    0xc0,                         //     End Collection
+    0x05, 0x05,                   //     Usage Page (Game Controls)
+    0x09, 0x39,                   //     Usage (Gamepad Trigger)
+    0x09, 0x37,                   //     Usage (Gamepad Fire/Jump)
+    0x09, 0x2a,                   //     Usage (Flipper)
+    0x09, 0x2b,                   //     Usage (Secondary Flipper)
+    0x25, 0x01,                   //     Logical Maximum (1)
+    0x75, 0x01,                   //     Report Size (1)
+    0x95, 0x04,                   //     Report Count (4)
+    0x81, 0x02,                   //     Input (Data, Variable, Absolute)
+    0xc0,                         //   End Collection
+    0x05, 0x09,                   //   Usage Page (Button)
+    0x19, 0x01,                   //   Usage Minimum (1)
+    0x29, 0x08,                   //   Usage Maximum (8)
+    0x95, 0x08,                   //   Report Count (8)
+    0x81, 0x02,                   //   Input (Data, Variable, Absolute)
+    0xc0,                         // End Collection
+];
+
+/// Game controller report
+///
+/// Axes are centred on zero, negative values move left, backward, turn left, pitch forward and
+/// lean left.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "12", bit_numbering = "msb0")]
+pub struct GameControllerReport {
+    #[packed_field(bytes = "0..=1")]
+    pub move_right_left: i16,
+    #[packed_field(bytes = "2..=3")]
+    pub move_forward_backward: i16,
+    #[packed_field(bytes = "4..=5")]
+    pub turn_right_left: i16,
+    #[packed_field(bytes = "6..=7")]
+    pub pitch_forward_backward: i16,
+    #[packed_field(bytes = "8..=9")]
+    pub lean_right_left: i16,
+    #[packed_field(bits = "84..=87", ty = "enum")]
+    pub point_of_view: HatSwitch,
+    #[packed_field(bits = "83")]
+    pub trigger: bool,
+    #[packed_field(bits = "82")]
+    pub fire_jump: bool,
+    #[packed_field(bits = "81")]
+    pub flipper: bool,
+    #[packed_field(bits = "80")]
+    pub secondary_flipper: bool,
+    /// Button bitmap, button 1 in the least significant bit
+    #[packed_field(bytes = "11")]
+    pub buttons: u8,
+}
+
+pub struct GameController<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes16, OutNone, ReportSingle>,
+}
+
+impl<'a, B: UsbBus> GameController<'a, B> {
+    pub fn write_report(&mut self, report: &GameControllerReport) -> Result<(), UsbHidError> {
+        let data = report.pack().map_err(|_| {
+            error!("Error packing GameControllerReport");
+            UsbHidError::SerializationError
+        })?;
+        self.interface
+            .write_report(&data)
+            .map(|_| ())
+            .map_err(UsbHidError::from)
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for GameController<'a, B> {
+    type I = Interface<'a, B, InBytes16, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {}
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        Ok(())
+    }
+}
+
+pub struct GameControllerConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes16, OutNone, ReportSingle>,
+}
+
+impl<'a> Default for GameControllerConfig<'a> {
+    #[must_use]
+    fn default() -> Self {
+        Self::new(
+            unwrap!(
+                unwrap!(InterfaceBuilder::new(GAME_CONTROLLER_REPORT_DESCRIPTOR))
+                    .description("Game Controller")
+                    .in_endpoint(1.millis())
+            )
+            .without_out_endpoint()
+            .build(),
+        )
+    }
+}
+
+impl<'a> GameControllerConfig<'a> {
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes16, OutNone, ReportSingle>) -> Self {
+        Self { interface }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for GameControllerConfig<'a> {
+    type Allocated = GameController<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn game_controller_report() {
+        let report = GameControllerReport {
+            move_right_left: -1,
+            move_forward_backward: 0x1234,
+            turn_right_left: 0,
+            pitch_forward_backward: -32767,
+            lean_right_left: 1,
+            point_of_view: HatSwitch::SW,
+            trigger: true,
+            fire_jump: false,
+            flipper: false,
+            secondary_flipper: true,
+            buttons: 0x81,
+        };
+        assert_eq!(
+            report.pack().unwrap(),
+            [0xFF, 0xFF, 0x34, 0x12, 0x00, 0x00, 0x01, 0x80, 0x01, 0x00, 0x95, 0x81]
+        );
+    }
+
+    #[test]
+    fn default_report_is_centred() {
+        let report = GameControllerReport::default();
+        assert_eq!(report.pack().unwrap()[10], 0x08);
+    }
+}
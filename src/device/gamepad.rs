@@ -0,0 +1,386 @@
+//!HID gamepad with a configurable number of axes, buttons and hat switches
+use crate::descriptor::builder::{Collection, ItemFlags, ReportDescriptorBuilder};
+use crate::device::joystick::HatSwitch;
+use crate::page::{Desktop, UsagePage};
+use crate::usb_class::prelude::*;
+use core::default::Default;
+use fugit::ExtU32;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+
+/// Maximum number of axes supported by [`Gamepad`]
+pub const MAX_AXES: usize = 8;
+/// Maximum number of buttons supported by [`Gamepad`]
+pub const MAX_BUTTONS: usize = 128;
+/// Maximum number of hat switches supported by [`Gamepad`]
+pub const MAX_HATS: usize = 4;
+/// Largest possible packed [`GamepadReport`] in bytes
+pub const MAX_REPORT_SIZE: usize = 64;
+
+/// Capacity reserved for generated gamepad report descriptors
+pub const DESCRIPTOR_CAPACITY: usize = 128;
+
+/// Axes are assigned these usages in order
+const AXIS_USAGES: [Desktop; MAX_AXES] = [
+    Desktop::X,
+    Desktop::Y,
+    Desktop::Z,
+    Desktop::Rx,
+    Desktop::Ry,
+    Desktop::Rz,
+    Desktop::Slider,
+    Desktop::Dial,
+];
+
+const HAT_SWITCH_BITS: usize = 4;
+
+/// Gamepad axis resolution
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AxisResolution {
+    /// Axis values from 0 to 255
+    Bits8,
+    /// Axis values from 0 to 4095
+    Bits12,
+    /// Axis values from 0 to 65535
+    Bits16,
+}
+
+impl AxisResolution {
+    #[must_use]
+    pub const fn bits(self) -> usize {
+        match self {
+            Self::Bits8 => 8,
+            Self::Bits12 => 12,
+            Self::Bits16 => 16,
+        }
+    }
+
+    /// Largest axis value, axis values above this are clamped
+    #[must_use]
+    pub const fn logical_maximum(self) -> u16 {
+        match self {
+            Self::Bits8 => 0xFF,
+            Self::Bits12 => 0x0FFF,
+            Self::Bits16 => 0xFFFF,
+        }
+    }
+}
+
+/// Generate a gamepad report descriptor
+///
+/// The report consists of `axes` absolute axes, `hats` four bit hat switches and `buttons` single
+/// bit buttons, padded to a whole number of bytes. Axes use the Generic Desktop usages X, Y, Z, Rx,
+/// Ry, Rz, Slider and Dial in that order.
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_possible_wrap)]
+pub const fn gamepad_report_descriptor(
+    axes: usize,
+    buttons: usize,
+    hats: usize,
+    resolution: AxisResolution,
+) -> ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> {
+    core::assert!(axes <= MAX_AXES, "Too many gamepad axes");
+    core::assert!(buttons <= MAX_BUTTONS, "Too many gamepad buttons");
+    core::assert!(hats <= MAX_HATS, "Too many gamepad hat switches");
+
+    let mut descriptor = ReportDescriptorBuilder::new()
+        .usage_page(UsagePage::GenericDesktop as u16)
+        .usage(Desktop::GamePad as u16)
+        .collection(Collection::Application);
+
+    if axes > 0 {
+        let mut i = 0;
+        while i < axes {
+            descriptor = descriptor.usage(AXIS_USAGES[i] as u16);
+            i += 1;
+        }
+        descriptor = descriptor
+            .logical_minimum(0)
+            .logical_maximum(resolution.logical_maximum() as i32)
+            .report_size(resolution.bits() as u32)
+            .report_count(axes as u32)
+            .input(ItemFlags::DATA_VARIABLE_ABSOLUTE);
+    }
+
+    if hats > 0 {
+        let mut i = 0;
+        while i < hats {
+            descriptor = descriptor.usage(Desktop::HatSwitch as u16);
+            i += 1;
+        }
+        descriptor = descriptor
+            .logical_minimum(0)
+            .logical_maximum(7)
+            .physical_minimum(0)
+            .physical_maximum(315)
+            .unit(0x14) // English Rotation, degrees
+            .report_size(HAT_SWITCH_BITS as u32)
+            .report_count(hats as u32)
+            .input(ItemFlags::DATA_VARIABLE_ABSOLUTE.null_state())
+            .unit(0);
+    }
+
+    if buttons > 0 {
+        descriptor = descriptor
+            .usage_page(UsagePage::Button as u16)
+            .usage_minimum(1)
+            .usage_maximum(buttons as u16)
+            .logical_minimum(0)
+            .logical_maximum(1)
+            .report_size(1)
+            .report_count(buttons as u32)
+            .input(ItemFlags::DATA_VARIABLE_ABSOLUTE);
+    }
+
+    let bits = axes * resolution.bits() + hats * HAT_SWITCH_BITS + buttons;
+    descriptor
+        .input_padding(((8 - bits % 8) % 8) as u32)
+        .end_collection()
+}
+
+/// Write the `bits` least significant bits of `value` into `data` starting at bit `offset`
+fn write_bits(data: &mut [u8], offset: usize, value: u32, bits: usize) {
+    for i in 0..bits {
+        if value & (1 << i) != 0 {
+            let bit = offset + i;
+            data[bit / 8] |= 1 << (bit % 8);
+        }
+    }
+}
+
+/// Gamepad report with `AXES` axes, `BUTTONS` buttons and `HATS` hat switches
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GamepadReport<const AXES: usize, const BUTTONS: usize, const HATS: usize> {
+    /// Axis values, clamped to the [`AxisResolution`] of the device
+    pub axes: [u16; AXES],
+    pub hats: [HatSwitch; HATS],
+    pub buttons: [bool; BUTTONS],
+}
+
+impl<const AXES: usize, const BUTTONS: usize, const HATS: usize> Default
+    for GamepadReport<AXES, BUTTONS, HATS>
+{
+    fn default() -> Self {
+        Self {
+            axes: [0; AXES],
+            hats: [HatSwitch::Centered; HATS],
+            buttons: [false; BUTTONS],
+        }
+    }
+}
+
+impl<const AXES: usize, const BUTTONS: usize, const HATS: usize>
+    GamepadReport<AXES, BUTTONS, HATS>
+{
+    const DESCRIPTOR_8: ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> =
+        gamepad_report_descriptor(AXES, BUTTONS, HATS, AxisResolution::Bits8);
+    const DESCRIPTOR_12: ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> =
+        gamepad_report_descriptor(AXES, BUTTONS, HATS, AxisResolution::Bits12);
+    const DESCRIPTOR_16: ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> =
+        gamepad_report_descriptor(AXES, BUTTONS, HATS, AxisResolution::Bits16);
+
+    /// Report descriptor matching this report at the given resolution
+    #[must_use]
+    pub fn descriptor(resolution: AxisResolution) -> &'static [u8] {
+        let descriptor: &'static ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> = match resolution {
+            AxisResolution::Bits8 => &Self::DESCRIPTOR_8,
+            AxisResolution::Bits12 => &Self::DESCRIPTOR_12,
+            AxisResolution::Bits16 => &Self::DESCRIPTOR_16,
+        };
+        descriptor.as_bytes()
+    }
+
+    /// Size of the packed report in bytes
+    #[must_use]
+    pub const fn size(resolution: AxisResolution) -> usize {
+        (AXES * resolution.bits() + HATS * HAT_SWITCH_BITS + BUTTONS).div_ceil(8)
+    }
+
+    #[must_use]
+    pub fn button(&self, index: usize) -> bool {
+        self.buttons.get(index).copied().unwrap_or_default()
+    }
+
+    /// Set the state of the button at `index`, ignored if out of range
+    pub fn set_button(&mut self, index: usize, pressed: bool) {
+        if let Some(b) = self.buttons.get_mut(index) {
+            *b = pressed;
+        }
+    }
+
+    /// Pack the report into `data`, returning the number of bytes written
+    pub fn pack(&self, resolution: AxisResolution, data: &mut [u8]) -> Result<usize, UsbHidError> {
+        let size = Self::size(resolution);
+        if data.len() < size {
+            error!("Error packing GamepadReport, buffer too short");
+            return Err(UsbHidError::SerializationError);
+        }
+        data[..size].fill(0);
+
+        let mut offset = 0;
+        for &axis in &self.axes {
+            let value = axis.min(resolution.logical_maximum());
+            write_bits(data, offset, value.into(), resolution.bits());
+            offset += resolution.bits();
+        }
+        for &hat in &self.hats {
+            write_bits(data, offset, u8::from(hat).into(), HAT_SWITCH_BITS);
+            offset += HAT_SWITCH_BITS;
+        }
+        for &button in &self.buttons {
+            write_bits(data, offset, button.into(), 1);
+            offset += 1;
+        }
+
+        Ok(size)
+    }
+}
+
+/// Gamepad with `AXES` axes, `BUTTONS` buttons and `HATS` hat switches
+///
+/// At most [`MAX_AXES`] axes, [`MAX_BUTTONS`] buttons and [`MAX_HATS`] hat switches are supported,
+/// larger values fail to compile.
+pub struct Gamepad<'a, B: UsbBus, const AXES: usize, const BUTTONS: usize, const HATS: usize> {
+    interface: Interface<'a, B, InBytes64, OutNone, ReportSingle>,
+    resolution: AxisResolution,
+}
+
+impl<'a, B: UsbBus, const AXES: usize, const BUTTONS: usize, const HATS: usize>
+    Gamepad<'a, B, AXES, BUTTONS, HATS>
+{
+    pub fn write_report(
+        &mut self,
+        report: &GamepadReport<AXES, BUTTONS, HATS>,
+    ) -> Result<(), UsbHidError> {
+        let mut data = [0; MAX_REPORT_SIZE];
+        let size = report.pack(self.resolution, &mut data)?;
+        self.interface
+            .write_report(&data[..size])
+            .map(|_| ())
+            .map_err(UsbHidError::from)
+    }
+
+    #[must_use]
+    pub fn resolution(&self) -> AxisResolution {
+        self.resolution
+    }
+}
+
+impl<'a, B: UsbBus, const AXES: usize, const BUTTONS: usize, const HATS: usize> DeviceClass<'a>
+    for Gamepad<'a, B, AXES, BUTTONS, HATS>
+{
+    type I = Interface<'a, B, InBytes64, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {}
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        Ok(())
+    }
+}
+
+pub struct GamepadConfig<'a, const AXES: usize, const BUTTONS: usize, const HATS: usize> {
+    interface: InterfaceConfig<'a, InBytes64, OutNone, ReportSingle>,
+    resolution: AxisResolution,
+}
+
+impl<'a, const AXES: usize, const BUTTONS: usize, const HATS: usize> Default
+    for GamepadConfig<'a, AXES, BUTTONS, HATS>
+{
+    #[must_use]
+    fn default() -> Self {
+        Self::with_resolution(AxisResolution::Bits16)
+    }
+}
+
+impl<'a, const AXES: usize, const BUTTONS: usize, const HATS: usize>
+    GamepadConfig<'a, AXES, BUTTONS, HATS>
+{
+    /// `interface` must use the report descriptor from [`GamepadReport::descriptor`] for `resolution`
+    #[must_use]
+    pub fn new(
+        resolution: AxisResolution,
+        interface: InterfaceConfig<'a, InBytes64, OutNone, ReportSingle>,
+    ) -> Self {
+        Self {
+            interface,
+            resolution,
+        }
+    }
+
+    /// Default gamepad interface with the given axis resolution
+    #[must_use]
+    pub fn with_resolution(resolution: AxisResolution) -> Self {
+        Self::new(
+            resolution,
+            unwrap!(unwrap!(InterfaceBuilder::new(
+                GamepadReport::<AXES, BUTTONS, HATS>::descriptor(resolution)
+            ))
+            .description("Gamepad")
+            .in_endpoint(10.millis()))
+            .without_out_endpoint()
+            .build(),
+        )
+    }
+}
+
+impl<'a, B: UsbBus + 'a, const AXES: usize, const BUTTONS: usize, const HATS: usize>
+    UsbAllocatable<'a, B> for GamepadConfig<'a, AXES, BUTTONS, HATS>
+{
+    type Allocated = Gamepad<'a, B, AXES, BUTTONS, HATS>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+            resolution: self.resolution,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn pack_12_bit_axes() {
+        let mut report = GamepadReport::<2, 3, 1>::default();
+        report.axes = [0xABC, 0xFFFF];
+        report.hats = [HatSwitch::E];
+        report.set_button(0, true);
+        report.set_button(2, true);
+
+        let mut data = [0; MAX_REPORT_SIZE];
+        let size = report.pack(AxisResolution::Bits12, &mut data).unwrap();
+
+        assert_eq!(size, 4);
+        assert_eq!(data[..size], [0xBC, 0xFA, 0xFF, 0x52]);
+    }
+
+    #[test]
+    fn pack_centered_hat() {
+        let report = GamepadReport::<0, 0, 2>::default();
+
+        let mut data = [0; MAX_REPORT_SIZE];
+        let size = report.pack(AxisResolution::Bits8, &mut data).unwrap();
+
+        assert_eq!(data[..size], [0x88]);
+    }
+
+    #[test]
+    fn descriptor_padding() {
+        let descriptor = GamepadReport::<1, 3, 0>::descriptor(AxisResolution::Bits8);
+
+        // 8 bit axis and 3 buttons, padded with 5 constant bits
+        assert_eq!(
+            descriptor[descriptor.len() - 7..],
+            [0x75, 0x05, 0x95, 0x01, 0x81, 0x01, 0xC0]
+        );
+    }
+}
@@ -48,18 +48,85 @@ pub const JOYSTICK_DESCRIPTOR: &[u8] = &[
     // V 4 bits
     // repack to 2 bytes. {8buttons}, {4buttons, HatSwitch}
 
-    /* Hat Switch */
-	0x05, 0x01,							/*   USAGE_PAGE (Generic Desktop) */
-	0x09, 0x39,							/*   USAGE (Hat switch) */
-	0x15, 0x01,							/*   LOGICAL_MINIMUM (1) */
-	0x25, 0x08,							/*   LOGICAL_MAXIMUM (8) */
-	0x95, 0x01,							/*   REPORT_COUNT (1) */
-	0x75, 0x04,							/*   REPORT_SIZE (4) */
-	0x81, 0x02,							/*   INPUT (Data,Var,Abs) */
+    // Hat switch, null state (centered) is any value outside 0-7
+    0x05, 0x01, //   Usage Page (Generic Desktop)       5,   1
+    0x09, 0x39, //   Usage (Hat switch)                 9,   57
+    0x15, 0x00, //   Logical Minimum (0)                21,  0
+    0x25, 0x07, //   Logical Maximum (7)                37,  7
+    0x35, 0x00, //   Physical Minimum (0)               53,  0
+    0x46, 0x3b, 0x01, // Physical Maximum (315)         70,  59, 1
+    0x65, 0x14, //   Unit (English Rotation, Degrees)   101, 20
+    0x95, 0x01, //   Report Count (1)                   149, 1
+    0x75, 0x04, //   Report Size (4)                    117, 4
+    0x81, 0x42, //   Input (Data, Variable, Absolute, Null State) 129, 66
+    0x65, 0x00, //   Unit (None)                        101, 0
 
     0xc0,       // End Collection                       192
 ];
 
+/// Hat switch position
+///
+/// Hosts interpret a hat switch as an angle, reported clockwise from north in 45 degree steps.
+/// [`HatSwitch::Centered`] is reported as the null state.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Eq,
+    PartialEq,
+    Hash,
+    Default,
+    PrimitiveEnum,
+    num_enum::IntoPrimitive,
+    num_enum::FromPrimitive,
+)]
+#[repr(u8)]
+pub enum HatSwitch {
+    N = 0,
+    NE = 1,
+    E = 2,
+    SE = 3,
+    S = 4,
+    SW = 5,
+    W = 6,
+    NW = 7,
+    #[default]
+    Centered = 8,
+}
+
+impl HatSwitch {
+    /// Hat switch position from four directional inputs, e.g. a D-pad
+    ///
+    /// Simultaneous opposite directions cancel each other out.
+    #[must_use]
+    #[allow(clippy::fn_params_excessive_bools)]
+    pub fn from_directions(up: bool, down: bool, left: bool, right: bool) -> Self {
+        let vertical = (up && !down, down && !up);
+        let horizontal = (left && !right, right && !left);
+        match (vertical, horizontal) {
+            ((true, _), (false, false)) => Self::N,
+            ((true, _), (false, true)) => Self::NE,
+            ((true, _), (true, _)) => Self::NW,
+            ((_, true), (false, false)) => Self::S,
+            ((_, true), (false, true)) => Self::SE,
+            ((_, true), (true, _)) => Self::SW,
+            (_, (false, true)) => Self::E,
+            (_, (true, _)) => Self::W,
+            _ => Self::Centered,
+        }
+    }
+
+    /// Angle clockwise from north in degrees, `None` when centered
+    #[must_use]
+    pub fn angle(self) -> Option<u16> {
+        match self {
+            Self::Centered => None,
+            _ => Some(u16::from(u8::from(self)) * 45),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
 #[packed_struct(endian = "lsb", size_bytes = "8", bit_numbering = "msb0")]
 pub struct JoystickReport {
@@ -78,10 +145,10 @@ pub struct JoystickReport {
     #[packed_field]
     pub buttons1: u8,
 
-    // this 4 bits field pack to one byte,
-    // so reversed array order compared pre descriptor.
-    #[packed_field]
-    pub hat_switch: [bool; 4],
+    // the hat switch follows the 12 buttons in the descriptor, so with lsb
+    // first reports it occupies the high nibble of the last byte.
+    #[packed_field(size_bits = "4", ty = "enum")]
+    pub hat_switch: HatSwitch,
     #[packed_field]
     pub buttons2: [bool; 4],
 }
@@ -154,3 +221,44 @@ impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for JoystickConfig<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn hat_switch_from_directions() {
+        assert_eq!(
+            HatSwitch::from_directions(true, false, false, false),
+            HatSwitch::N
+        );
+        assert_eq!(
+            HatSwitch::from_directions(false, true, true, false),
+            HatSwitch::SW
+        );
+        assert_eq!(
+            HatSwitch::from_directions(true, true, false, true),
+            HatSwitch::E
+        );
+        assert_eq!(
+            HatSwitch::from_directions(true, true, true, true),
+            HatSwitch::Centered
+        );
+        assert_eq!(HatSwitch::NW.angle(), Some(315));
+        assert_eq!(HatSwitch::from(0xF), HatSwitch::Centered);
+    }
+
+    #[test]
+    fn joystick_report_hat_switch() {
+        let report = JoystickReport {
+            hat_switch: HatSwitch::SE,
+            buttons2: [false, false, false, true],
+            ..Default::default()
+        };
+
+        assert_eq!(report.pack().unwrap()[7], 0x31);
+        assert_eq!(JoystickReport::default().pack().unwrap()[7], 0x80);
+    }
+}
@@ -0,0 +1,773 @@
+//!Lamp array for host controlled RGB lighting
+//!
+//! See [HID Usage Tables for USB Version 1.3](<https://usb.org/sites/default/files/hut1_3_0.pdf>):
+//! Section 26 Lighting and Illumination Page (0x59).
+//!
+//! A [`LampArray`] describes its lamps to the host with the attribute feature reports and receives
+//! colours through the multi and range update feature reports, as used by Windows Dynamic
+//! Lighting. Updates are collected until the host marks an update complete, then copied to the
+//! framebuffer the firmware renders, see [`LampArray::frame_updated`].
+//!
+//! The lamp positions come from a static table. The lamp array is its own interface, so it can be
+//! added alongside a keyboard:
+//!
+//! ```
+//! use usbd_human_interface_device::device::keyboard::NKROBootKeyboardConfig;
+//! use usbd_human_interface_device::device::lamp_array::{
+//!     LampArray, LampArrayAttributes, LampArrayConfig, LampArrayKind, LampAttributes,
+//! };
+//! use usbd_human_interface_device::page::Keyboard;
+//! use usbd_human_interface_device::prelude::*;
+//! use usb_device::bus::{UsbBus, UsbBusAllocator};
+//!
+//! static LAMPS: [LampAttributes; 2] = [
+//!     LampAttributes::new(9_500, 9_500, 0).input_binding(Keyboard::Escape),
+//!     LampAttributes::new(28_500, 9_500, 0).input_binding(Keyboard::F1),
+//! ];
+//!
+//! const ATTRIBUTES: LampArrayAttributes = LampArrayAttributes {
+//!     bounding_box_width: 440_000,
+//!     bounding_box_height: 150_000,
+//!     bounding_box_depth: 35_000,
+//!     kind: LampArrayKind::Keyboard,
+//!     min_update_interval: 10_000,
+//! };
+//!
+//! fn build<B: UsbBus>(usb_alloc: &UsbBusAllocator<B>) {
+//!     let mut hid = UsbHidClassBuilder::new()
+//!         .add_device(NKROBootKeyboardConfig::default())
+//!         .add_device(LampArrayConfig::with_lamps(ATTRIBUTES, &LAMPS))
+//!         .build(usb_alloc);
+//!
+//!     let lamps = hid.device::<LampArray<'_, _, 2>, _>();
+//!     if lamps.frame_updated() {
+//!         for _colour in lamps.framebuffer() {
+//!             // Drive the LEDs
+//!         }
+//!     }
+//! }
+//! ```
+use crate::descriptor::builder::{Collection, ItemFlags, ReportDescriptorBuilder};
+use crate::descriptor::ReportType;
+use crate::page::{Keyboard, LightingAndIllumination as Lighting, UsagePage};
+use crate::usb_class::prelude::*;
+use core::default::Default;
+use fugit::ExtU32;
+use packed_struct::prelude::*;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+use usb_device::UsbError;
+
+/// Largest number of lamps in a multi update report
+pub const MAX_MULTI_UPDATE_LAMPS: usize = 8;
+
+/// Capacity reserved for the lamp array report descriptor
+pub const DESCRIPTOR_CAPACITY: usize = 320;
+
+const ATTRIBUTES_REPORT_ID: u8 = 0x01;
+const ATTRIBUTES_REQUEST_REPORT_ID: u8 = 0x02;
+const ATTRIBUTES_RESPONSE_REPORT_ID: u8 = 0x03;
+const MULTI_UPDATE_REPORT_ID: u8 = 0x04;
+const RANGE_UPDATE_REPORT_ID: u8 = 0x05;
+const CONTROL_REPORT_ID: u8 = 0x06;
+
+/// Lamp Update Flags - the host has finished updating the lamps
+const LAMP_UPDATE_COMPLETE: u8 = 0x01;
+
+/// Red, green, blue and intensity update channels
+const fn update_channels<const N: usize>(
+    builder: ReportDescriptorBuilder<N>,
+    count: u32,
+) -> ReportDescriptorBuilder<N> {
+    let mut builder = builder;
+    let mut i = 0;
+    while i < count {
+        builder = builder
+            .usage(Lighting::RedUpdateChannel as u16)
+            .usage(Lighting::GreenUpdateChannel as u16)
+            .usage(Lighting::BlueUpdateChannel as u16)
+            .usage(Lighting::IntensityUpdateChannel as u16);
+        i += 1;
+    }
+    builder
+        .logical_maximum(0xFF)
+        .report_size(8)
+        .report_count(count * 4)
+        .feature(ItemFlags::DATA_VARIABLE_ABSOLUTE)
+}
+
+/// Lamp array report descriptor
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+pub const LAMP_ARRAY_DESCRIPTOR: ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> = {
+    let cva = ItemFlags::CONSTANT_VARIABLE_ABSOLUTE;
+    let dva = ItemFlags::DATA_VARIABLE_ABSOLUTE;
+
+    let builder = ReportDescriptorBuilder::new()
+        .usage_page(UsagePage::LightingAndIllumination as u16)
+        .usage(Lighting::LampArray as u16)
+        .collection(Collection::Application)
+        // Lamp array attributes
+        .report_id(ATTRIBUTES_REPORT_ID)
+        .usage(Lighting::LampArrayAttributesReport as u16)
+        .collection(Collection::Logical)
+        .usage(Lighting::LampCount as u16)
+        .logical_minimum(0)
+        .logical_maximum(0xFFFF)
+        .report_size(16)
+        .report_count(1)
+        .feature(cva)
+        .usage(Lighting::BoundingBoxWidthInMicrometers as u16)
+        .usage(Lighting::BoundingBoxHeightInMicrometers as u16)
+        .usage(Lighting::BoundingBoxDepthInMicrometers as u16)
+        .usage(Lighting::LampArrayKind as u16)
+        .usage(Lighting::MinUpdateIntervalInMicroseconds as u16)
+        .logical_maximum(i32::MAX)
+        .report_size(32)
+        .report_count(5)
+        .feature(cva)
+        .end_collection()
+        // Lamp attributes request
+        .report_id(ATTRIBUTES_REQUEST_REPORT_ID)
+        .usage(Lighting::LampAttributesRequestReport as u16)
+        .collection(Collection::Logical)
+        .usage(Lighting::LampId as u16)
+        .logical_maximum(0xFFFF)
+        .report_size(16)
+        .report_count(1)
+        .feature(dva)
+        .end_collection()
+        // Lamp attributes response
+        .report_id(ATTRIBUTES_RESPONSE_REPORT_ID)
+        .usage(Lighting::LampAttributesResponseReport as u16)
+        .collection(Collection::Logical)
+        .usage(Lighting::LampId as u16)
+        .feature(dva)
+        .usage(Lighting::PositionXInMicrometers as u16)
+        .usage(Lighting::PositionYInMicrometers as u16)
+        .usage(Lighting::PositionZInMicrometers as u16)
+        .usage(Lighting::UpdateLatencyInMicroseconds as u16)
+        .usage(Lighting::LampPurposes as u16)
+        .logical_maximum(i32::MAX)
+        .report_size(32)
+        .report_count(5)
+        .feature(dva)
+        .usage(Lighting::RedLevelCount as u16)
+        .usage(Lighting::GreenLevelCount as u16)
+        .usage(Lighting::BlueLevelCount as u16)
+        .usage(Lighting::IntensityLevelCount as u16)
+        .usage(Lighting::IsProgrammable as u16)
+        .usage(Lighting::InputBinding as u16)
+        .logical_maximum(0xFF)
+        .report_size(8)
+        .report_count(6)
+        .feature(dva)
+        .end_collection()
+        // Lamp multi update
+        .report_id(MULTI_UPDATE_REPORT_ID)
+        .usage(Lighting::LampMultiUpdateReport as u16)
+        .collection(Collection::Logical)
+        .usage(Lighting::LampCount as u16)
+        .usage(Lighting::LampUpdateFlags as u16)
+        .logical_maximum(MAX_MULTI_UPDATE_LAMPS as i32)
+        .report_size(8)
+        .report_count(2)
+        .feature(dva)
+        .usage(Lighting::LampId as u16)
+        .logical_maximum(0xFFFF)
+        .report_size(16)
+        .report_count(MAX_MULTI_UPDATE_LAMPS as u32)
+        .feature(dva);
+    let builder = update_channels(builder, MAX_MULTI_UPDATE_LAMPS as u32)
+        .end_collection()
+        // Lamp range update
+        .report_id(RANGE_UPDATE_REPORT_ID)
+        .usage(Lighting::LampRangeUpdateReport as u16)
+        .collection(Collection::Logical)
+        .usage(Lighting::LampUpdateFlags as u16)
+        .logical_maximum(MAX_MULTI_UPDATE_LAMPS as i32)
+        .report_size(8)
+        .report_count(1)
+        .feature(dva)
+        .usage(Lighting::LampIdStart as u16)
+        .usage(Lighting::LampIdEnd as u16)
+        .logical_maximum(0xFFFF)
+        .report_size(16)
+        .report_count(2)
+        .feature(dva);
+    update_channels(builder, 1)
+        .end_collection()
+        // Lamp array control
+        .report_id(CONTROL_REPORT_ID)
+        .usage(Lighting::LampArrayControlReport as u16)
+        .collection(Collection::Logical)
+        .usage(Lighting::AutonomousMode as u16)
+        .logical_maximum(1)
+        .report_size(8)
+        .report_count(1)
+        .feature(dva)
+        .end_collection()
+        .end_collection()
+};
+
+/// Kind of device the lamp array is part of
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[repr(u8)]
+pub enum LampArrayKind {
+    Keyboard = 0x01,
+    Mouse = 0x02,
+    GameController = 0x03,
+    Peripheral = 0x04,
+    Scene = 0x05,
+    Notification = 0x06,
+    Chassis = 0x07,
+    Wearable = 0x08,
+    Furniture = 0x09,
+    Art = 0x0A,
+}
+
+/// Lamp Purposes flags
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct LampPurposes(u32);
+
+impl LampPurposes {
+    pub const CONTROL: Self = Self(0x01);
+    pub const ACCENT: Self = Self(0x02);
+    pub const BRANDING: Self = Self(0x04);
+    pub const STATUS: Self = Self(0x08);
+    pub const ILLUMINATION: Self = Self(0x10);
+    pub const PRESENTATION: Self = Self(0x20);
+
+    #[must_use]
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    #[must_use]
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+}
+
+/// Attributes of the whole lamp array
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LampArrayAttributes {
+    /// Width of the bounding box of the lamps in micrometers
+    pub bounding_box_width: u32,
+    /// Height of the bounding box of the lamps in micrometers
+    pub bounding_box_height: u32,
+    /// Depth of the bounding box of the lamps in micrometers
+    pub bounding_box_depth: u32,
+    pub kind: LampArrayKind,
+    /// Shortest interval between updates the device supports, in microseconds
+    pub min_update_interval: u32,
+}
+
+/// Attributes of a single lamp
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LampAttributes {
+    /// Position in micrometers from the top left front corner of the bounding box
+    pub position: [u32; 3],
+    /// Time from receiving an update to the lamp changing, in microseconds
+    pub update_latency: u32,
+    pub purposes: LampPurposes,
+    pub red_level_count: u8,
+    pub green_level_count: u8,
+    pub blue_level_count: u8,
+    pub intensity_level_count: u8,
+    pub is_programmable: bool,
+    /// Key the lamp illuminates, [`Keyboard::NoEventIndicated`] for none
+    pub input_binding: Keyboard,
+}
+
+impl LampAttributes {
+    /// A programmable 8 bit RGB control lamp at `x`, `y`, `z` micrometers
+    #[must_use]
+    pub const fn new(x: u32, y: u32, z: u32) -> Self {
+        Self {
+            position: [x, y, z],
+            update_latency: 1000,
+            purposes: LampPurposes::CONTROL,
+            red_level_count: 0xFF,
+            green_level_count: 0xFF,
+            blue_level_count: 0xFF,
+            intensity_level_count: 1,
+            is_programmable: true,
+            input_binding: Keyboard::NoEventIndicated,
+        }
+    }
+
+    /// Bind the lamp to the key it illuminates
+    #[must_use]
+    pub const fn input_binding(mut self, key: Keyboard) -> Self {
+        self.input_binding = key;
+        self
+    }
+
+    #[must_use]
+    pub const fn purposes(mut self, purposes: LampPurposes) -> Self {
+        self.purposes = purposes;
+        self
+    }
+}
+
+/// Lamp colour and intensity
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "4")]
+pub struct LampColor {
+    #[packed_field]
+    pub red: u8,
+    #[packed_field]
+    pub green: u8,
+    #[packed_field]
+    pub blue: u8,
+    #[packed_field]
+    pub intensity: u8,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "23")]
+struct LampArrayAttributesReport {
+    #[packed_field]
+    report_id: u8,
+    #[packed_field]
+    lamp_count: u16,
+    #[packed_field]
+    bounding_box_width: u32,
+    #[packed_field]
+    bounding_box_height: u32,
+    #[packed_field]
+    bounding_box_depth: u32,
+    #[packed_field]
+    kind: u32,
+    #[packed_field]
+    min_update_interval: u32,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "3")]
+struct LampAttributesRequestReport {
+    #[packed_field]
+    report_id: u8,
+    #[packed_field]
+    lamp_id: u16,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "29")]
+struct LampAttributesResponseReport {
+    #[packed_field]
+    report_id: u8,
+    #[packed_field]
+    lamp_id: u16,
+    #[packed_field(element_size_bytes = "4")]
+    position: [u32; 3],
+    #[packed_field]
+    update_latency: u32,
+    #[packed_field]
+    purposes: u32,
+    #[packed_field]
+    red_level_count: u8,
+    #[packed_field]
+    green_level_count: u8,
+    #[packed_field]
+    blue_level_count: u8,
+    #[packed_field]
+    intensity_level_count: u8,
+    #[packed_field]
+    is_programmable: u8,
+    #[packed_field]
+    input_binding: u8,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "51")]
+struct LampMultiUpdateReport {
+    #[packed_field]
+    report_id: u8,
+    #[packed_field]
+    lamp_count: u8,
+    #[packed_field]
+    flags: u8,
+    #[packed_field(element_size_bytes = "2")]
+    lamp_ids: [u16; 8],
+    #[packed_field(element_size_bytes = "4")]
+    colors: [LampColor; 8],
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "10")]
+struct LampRangeUpdateReport {
+    #[packed_field]
+    report_id: u8,
+    #[packed_field]
+    flags: u8,
+    #[packed_field]
+    lamp_id_start: u16,
+    #[packed_field]
+    lamp_id_end: u16,
+    #[packed_field(size_bytes = "4")]
+    color: LampColor,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "2")]
+struct LampArrayControlReport {
+    #[packed_field]
+    report_id: u8,
+    #[packed_field]
+    autonomous_mode: u8,
+}
+
+fn unpack<T: PackedStructSlice>(data: &[u8]) -> usb_device::Result<T> {
+    T::unpack_from_slice(data).map_err(|_| {
+        error!("Error unpacking lamp array report");
+        UsbError::ParseError
+    })
+}
+
+fn pack_into<T: PackedStructSlice>(report: &T, data: &mut [u8]) -> usb_device::Result<usize> {
+    let size = T::packed_bytes_size(None).map_err(|_| UsbError::ParseError)?;
+    let Some(data) = data.get_mut(..size) else {
+        error!("GetReport failed, buffer too short");
+        return Err(UsbError::BufferOverflow);
+    };
+    report.pack_to_slice(data).map_err(|_| {
+        error!("Error packing lamp array report");
+        UsbError::ParseError
+    })?;
+    Ok(size)
+}
+
+/// Host controlled lamp array with `N` lamps
+pub struct LampArray<'a, B: UsbBus, const N: usize> {
+    interface: Interface<'a, B, InBytes8, OutNone, ReportSingle>,
+    attributes: LampArrayAttributes,
+    lamps: &'a [LampAttributes; N],
+    /// Lamp ID of the next attributes response
+    lamp_id: u16,
+    autonomous_mode: bool,
+    /// Colours received since the last complete update
+    pending: [LampColor; N],
+    framebuffer: [LampColor; N],
+    updated: bool,
+}
+
+impl<'a, B: UsbBus, const N: usize> LampArray<'a, B, N> {
+    #[must_use]
+    pub fn lamps(&self) -> &'a [LampAttributes; N] {
+        self.lamps
+    }
+
+    /// True while the firmware controls the lamps, set by the host before it sends updates
+    #[must_use]
+    pub fn autonomous_mode(&self) -> bool {
+        self.autonomous_mode
+    }
+
+    /// Lamp colours from the last complete host update, indexed by lamp ID
+    #[must_use]
+    pub fn framebuffer(&self) -> &[LampColor; N] {
+        &self.framebuffer
+    }
+
+    /// Returns true, once, after the host completes an update of the framebuffer
+    pub fn frame_updated(&mut self) -> bool {
+        core::mem::take(&mut self.updated)
+    }
+
+    fn lamp_index(lamp_id: u16) -> usb_device::Result<usize> {
+        let index = usize::from(lamp_id);
+        if index < N {
+            Ok(index)
+        } else {
+            warn!("Invalid lamp ID {}", lamp_id);
+            Err(UsbError::ParseError)
+        }
+    }
+
+    fn complete_update(&mut self, flags: u8) {
+        if flags & LAMP_UPDATE_COMPLETE != 0 {
+            self.framebuffer = self.pending;
+            self.updated = true;
+        }
+    }
+
+    fn multi_update(&mut self, report: &LampMultiUpdateReport) -> usb_device::Result<()> {
+        let count = usize::from(report.lamp_count);
+        if count > MAX_MULTI_UPDATE_LAMPS {
+            warn!("Invalid lamp count {}", report.lamp_count);
+            return Err(UsbError::ParseError);
+        }
+        for (&lamp_id, &color) in report.lamp_ids.iter().zip(report.colors.iter()).take(count) {
+            self.pending[Self::lamp_index(lamp_id)?] = color;
+        }
+        self.complete_update(report.flags);
+        Ok(())
+    }
+
+    fn range_update(&mut self, report: &LampRangeUpdateReport) -> usb_device::Result<()> {
+        let start = Self::lamp_index(report.lamp_id_start)?;
+        let end = Self::lamp_index(report.lamp_id_end)?;
+        if start > end {
+            warn!("Invalid lamp range {}-{}", start, end);
+            return Err(UsbError::ParseError);
+        }
+        self.pending[start..=end].fill(report.color);
+        self.complete_update(report.flags);
+        Ok(())
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn attributes_report(&self) -> LampArrayAttributesReport {
+        LampArrayAttributesReport {
+            report_id: ATTRIBUTES_REPORT_ID,
+            lamp_count: N as u16,
+            bounding_box_width: self.attributes.bounding_box_width,
+            bounding_box_height: self.attributes.bounding_box_height,
+            bounding_box_depth: self.attributes.bounding_box_depth,
+            kind: self.attributes.kind as u32,
+            min_update_interval: self.attributes.min_update_interval,
+        }
+    }
+
+    fn attributes_response(&self) -> LampAttributesResponseReport {
+        let lamp = &self.lamps[usize::from(self.lamp_id)];
+        LampAttributesResponseReport {
+            report_id: ATTRIBUTES_RESPONSE_REPORT_ID,
+            lamp_id: self.lamp_id,
+            position: lamp.position,
+            update_latency: lamp.update_latency,
+            purposes: lamp.purposes.bits(),
+            red_level_count: lamp.red_level_count,
+            green_level_count: lamp.green_level_count,
+            blue_level_count: lamp.blue_level_count,
+            intensity_level_count: lamp.intensity_level_count,
+            is_programmable: lamp.is_programmable.into(),
+            input_binding: lamp.input_binding.into(),
+        }
+    }
+}
+
+impl<'a, B: UsbBus, const N: usize> DeviceClass<'a> for LampArray<'a, B, N> {
+    type I = Interface<'a, B, InBytes8, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {
+        self.lamp_id = 0;
+        self.autonomous_mode = true;
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        Ok(())
+    }
+
+    fn set_report(
+        &mut self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &[u8],
+    ) -> usb_device::Result<()> {
+        if report_type != ReportType::Feature {
+            warn!(
+                "Unsupported lamp array SET_REPORT report ID {:X}",
+                report_id
+            );
+            return Err(UsbError::Unsupported);
+        }
+        match report_id {
+            ATTRIBUTES_REQUEST_REPORT_ID => {
+                let report: LampAttributesRequestReport = unpack(data)?;
+                Self::lamp_index(report.lamp_id)?;
+                self.lamp_id = report.lamp_id;
+                Ok(())
+            }
+            MULTI_UPDATE_REPORT_ID => self.multi_update(&unpack(data)?),
+            RANGE_UPDATE_REPORT_ID => self.range_update(&unpack(data)?),
+            CONTROL_REPORT_ID => {
+                let report: LampArrayControlReport = unpack(data)?;
+                self.autonomous_mode = report.autonomous_mode != 0;
+                Ok(())
+            }
+            _ => {
+                warn!(
+                    "Unsupported lamp array SET_REPORT report ID {:X}",
+                    report_id
+                );
+                Err(UsbError::Unsupported)
+            }
+        }
+    }
+
+    fn get_report(
+        &mut self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &mut [u8],
+    ) -> usb_device::Result<usize> {
+        if report_type != ReportType::Feature {
+            return Err(UsbError::Unsupported);
+        }
+        match report_id {
+            ATTRIBUTES_REPORT_ID => pack_into(&self.attributes_report(), data),
+            ATTRIBUTES_RESPONSE_REPORT_ID => pack_into(&self.attributes_response(), data),
+            CONTROL_REPORT_ID => pack_into(
+                &LampArrayControlReport {
+                    report_id,
+                    autonomous_mode: self.autonomous_mode.into(),
+                },
+                data,
+            ),
+            _ => {
+                warn!(
+                    "Unsupported lamp array GET_REPORT report ID {:X}",
+                    report_id
+                );
+                Err(UsbError::Unsupported)
+            }
+        }
+    }
+
+    /// Each attributes response moves on to the next lamp
+    fn get_report_ack(&mut self, report_type: ReportType, report_id: u8) -> usb_device::Result<()> {
+        if report_type == ReportType::Feature && report_id == ATTRIBUTES_RESPONSE_REPORT_ID {
+            self.lamp_id = if usize::from(self.lamp_id) + 1 < N {
+                self.lamp_id + 1
+            } else {
+                0
+            };
+        }
+        Ok(())
+    }
+}
+
+pub struct LampArrayConfig<'a, const N: usize> {
+    interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>,
+    attributes: LampArrayAttributes,
+    lamps: &'a [LampAttributes; N],
+}
+
+impl<'a, const N: usize> LampArrayConfig<'a, N> {
+    /// `lamps` is indexed by lamp ID
+    #[must_use]
+    pub fn new(
+        attributes: LampArrayAttributes,
+        lamps: &'a [LampAttributes; N],
+        interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>,
+    ) -> Self {
+        assert!(N > 0 && u16::try_from(N).is_ok(), "Invalid lamp count");
+        Self {
+            interface,
+            attributes,
+            lamps,
+        }
+    }
+
+    /// Config for `lamps`, indexed by lamp ID, with the default interface
+    #[must_use]
+    pub fn with_lamps(attributes: LampArrayAttributes, lamps: &'a [LampAttributes; N]) -> Self {
+        let descriptor: &'static ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> =
+            &LAMP_ARRAY_DESCRIPTOR;
+        Self::new(
+            attributes,
+            lamps,
+            unwrap!(unwrap!(InterfaceBuilder::new(descriptor.as_bytes()))
+                .description("Lamp Array")
+                .in_endpoint(100.millis()))
+            .without_out_endpoint()
+            .build(),
+        )
+    }
+}
+
+impl<'a, B: UsbBus + 'a, const N: usize> UsbAllocatable<'a, B> for LampArrayConfig<'a, N> {
+    type Allocated = LampArray<'a, B, N>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+            attributes: self.attributes,
+            lamps: self.lamps,
+            lamp_id: 0,
+            autonomous_mode: true,
+            pending: [LampColor::default(); N],
+            framebuffer: [LampColor::default(); N],
+            updated: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::test_descriptor::{report_bits, report_sizes};
+
+    #[test]
+    fn attributes_response_report() {
+        let report = LampAttributesResponseReport {
+            report_id: ATTRIBUTES_RESPONSE_REPORT_ID,
+            lamp_id: 0x0102,
+            position: [1, 2, 3],
+            update_latency: 1000,
+            purposes: LampPurposes::CONTROL.union(LampPurposes::STATUS).bits(),
+            red_level_count: 0xFF,
+            green_level_count: 0xFF,
+            blue_level_count: 0xFF,
+            intensity_level_count: 1,
+            is_programmable: 1,
+            input_binding: Keyboard::A.into(),
+        };
+        let packed = report.pack().unwrap();
+        assert_eq!(packed[..7], [0x03, 0x02, 0x01, 1, 0, 0, 0]);
+        assert_eq!(packed[15..23], [0xE8, 0x03, 0, 0, 0x09, 0, 0, 0]);
+        assert_eq!(packed[23..], [0xFF, 0xFF, 0xFF, 1, 1, 0x04]);
+    }
+
+    #[test]
+    fn multi_update_report() {
+        let mut data = [0_u8; 51];
+        data[..7].copy_from_slice(&[MULTI_UPDATE_REPORT_ID, 2, 0x01, 0x05, 0x00, 0x01, 0x01]);
+        data[19..27].copy_from_slice(&[10, 20, 30, 40, 50, 60, 70, 80]);
+
+        let report: LampMultiUpdateReport = unpack(&data).unwrap();
+        assert_eq!(report.lamp_count, 2);
+        assert_eq!(report.flags, LAMP_UPDATE_COMPLETE);
+        assert_eq!(report.lamp_ids[..2], [5, 0x0101]);
+        assert_eq!(
+            report.colors[1],
+            LampColor {
+                red: 50,
+                green: 60,
+                blue: 70,
+                intensity: 80
+            }
+        );
+    }
+
+    #[test]
+    fn descriptor_report_sizes() {
+        let [_, _, sizes] = report_sizes::<7>(LAMP_ARRAY_DESCRIPTOR.as_bytes());
+
+        assert_eq!(
+            sizes,
+            [
+                0,
+                report_bits(LampArrayAttributesReport::packed_bytes_size(None).unwrap()),
+                report_bits(LampAttributesRequestReport::packed_bytes_size(None).unwrap()),
+                report_bits(LampAttributesResponseReport::packed_bytes_size(None).unwrap()),
+                report_bits(LampMultiUpdateReport::packed_bytes_size(None).unwrap()),
+                report_bits(LampRangeUpdateReport::packed_bytes_size(None).unwrap()),
+                report_bits(LampArrayControlReport::packed_bytes_size(None).unwrap()),
+            ]
+        );
+    }
+}
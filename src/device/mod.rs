@@ -1,16 +1,31 @@
 //! Concrete implementation of Human Interface Devices
 
+use crate::descriptor::ReportType;
 use crate::interface::InterfaceClass;
 use crate::UsbHidError;
 use frunk::{HCons, HNil, ToMut};
 #[allow(clippy::wildcard_imports)]
 use usb_device::class_prelude::*;
 
+pub mod battery;
 pub mod consumer;
 pub mod fido;
+pub mod force_feedback;
+pub mod game;
+pub mod gamepad;
 pub mod joystick;
 pub mod keyboard;
+pub mod lamp_array;
 pub mod mouse;
+pub mod multi_axis;
+pub mod power;
+pub mod radial_controller;
+pub mod raw;
+pub mod sensor;
+pub mod simulation;
+pub mod telephony;
+pub mod usage_array;
+pub mod usage_bitmap;
 
 pub trait DeviceClass<'a> {
     type I: InterfaceClass<'a>;
@@ -19,6 +34,44 @@ pub trait DeviceClass<'a> {
     fn reset(&mut self);
     /// Called every 1ms
     fn tick(&mut self) -> Result<(), UsbHidError>;
+    /// Called on a `SET_REPORT` request, `data` includes the report ID if the report has one
+    ///
+    /// By default the report is buffered by the interface for [`Interface::read_report`](crate::interface::Interface::read_report)
+    ///
+    /// Returning [`UsbError::Unsupported`] or [`UsbError::ParseError`] stalls the request. Other
+    /// errors, e.g. [`UsbError::WouldBlock`] while the previous report is unread, drop the report.
+    fn set_report(
+        &mut self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &[u8],
+    ) -> usb_device::Result<()> {
+        let _ = (report_type, report_id);
+        self.interface().set_report(data)
+    }
+    /// Called on a `GET_REPORT` request, returning the number of bytes written to `data`
+    ///
+    /// By default the last report written to the interface is returned
+    fn get_report(
+        &mut self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &mut [u8],
+    ) -> usb_device::Result<usize> {
+        let _ = (report_type, report_id);
+        self.interface().get_report(data)
+    }
+    /// Called once the report from [`DeviceClass::get_report`] has been sent to the host
+    fn get_report_ack(&mut self, report_type: ReportType, report_id: u8) -> usb_device::Result<()> {
+        let _ = (report_type, report_id);
+        self.interface().get_report_ack()
+    }
+    /// Called on a `GET_DESCRIPTOR` request for a string descriptor
+    ///
+    /// By default only the interface description is returned
+    fn get_string(&mut self, index: StringIndex, lang_id: u16) -> Option<&'a str> {
+        self.interface().get_string(index, lang_id)
+    }
 }
 
 pub trait DeviceHList<'a>: ToMut<'a> {
@@ -27,6 +80,26 @@ pub trait DeviceHList<'a>: ToMut<'a> {
     fn write_descriptors(&mut self, writer: &mut DescriptorWriter) -> usb_device::Result<()>;
     fn get_string(&mut self, index: StringIndex, lang_id: u16) -> Option<&'a str>;
     fn tick(&mut self) -> Result<(), UsbHidError>;
+    fn set_report(
+        &mut self,
+        id: u8,
+        report_type: ReportType,
+        report_id: u8,
+        data: &[u8],
+    ) -> Option<usb_device::Result<()>>;
+    fn get_report(
+        &mut self,
+        id: u8,
+        report_type: ReportType,
+        report_id: u8,
+        data: &mut [u8],
+    ) -> Option<usb_device::Result<usize>>;
+    fn get_report_ack(
+        &mut self,
+        id: u8,
+        report_type: ReportType,
+        report_id: u8,
+    ) -> Option<usb_device::Result<()>>;
 }
 
 impl<'a> DeviceHList<'a> for HNil {
@@ -47,6 +120,30 @@ impl<'a> DeviceHList<'a> for HNil {
     fn tick(&mut self) -> Result<(), UsbHidError> {
         Ok(())
     }
+
+    fn set_report(
+        &mut self,
+        _: u8,
+        _: ReportType,
+        _: u8,
+        _: &[u8],
+    ) -> Option<usb_device::Result<()>> {
+        None
+    }
+
+    fn get_report(
+        &mut self,
+        _: u8,
+        _: ReportType,
+        _: u8,
+        _: &mut [u8],
+    ) -> Option<usb_device::Result<usize>> {
+        None
+    }
+
+    fn get_report_ack(&mut self, _: u8, _: ReportType, _: u8) -> Option<usb_device::Result<()>> {
+        None
+    }
 }
 
 impl<'a, Head: DeviceClass<'a> + 'a, Tail: DeviceHList<'a>> DeviceHList<'a> for HCons<Head, Tail> {
@@ -70,7 +167,7 @@ impl<'a, Head: DeviceClass<'a> + 'a, Tail: DeviceHList<'a>> DeviceHList<'a> for
     }
 
     fn get_string(&mut self, index: StringIndex, lang_id: u16) -> Option<&'a str> {
-        let s = self.head.interface().get_string(index, lang_id);
+        let s = self.head.get_string(index, lang_id);
         if s.is_some() {
             s
         } else {
@@ -82,4 +179,45 @@ impl<'a, Head: DeviceClass<'a> + 'a, Tail: DeviceHList<'a>> DeviceHList<'a> for
         self.head.tick()?;
         self.tail.tick()
     }
+
+    fn set_report(
+        &mut self,
+        id: u8,
+        report_type: ReportType,
+        report_id: u8,
+        data: &[u8],
+    ) -> Option<usb_device::Result<()>> {
+        if id == u8::from(self.head.interface().id()) {
+            Some(self.head.set_report(report_type, report_id, data))
+        } else {
+            self.tail.set_report(id, report_type, report_id, data)
+        }
+    }
+
+    fn get_report(
+        &mut self,
+        id: u8,
+        report_type: ReportType,
+        report_id: u8,
+        data: &mut [u8],
+    ) -> Option<usb_device::Result<usize>> {
+        if id == u8::from(self.head.interface().id()) {
+            Some(self.head.get_report(report_type, report_id, data))
+        } else {
+            self.tail.get_report(id, report_type, report_id, data)
+        }
+    }
+
+    fn get_report_ack(
+        &mut self,
+        id: u8,
+        report_type: ReportType,
+        report_id: u8,
+    ) -> Option<usb_device::Result<()>> {
+        if id == u8::from(self.head.interface().id()) {
+            Some(self.head.get_report_ack(report_type, report_id))
+        } else {
+            self.tail.get_report_ack(id, report_type, report_id)
+        }
+    }
 }
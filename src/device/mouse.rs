@@ -1,10 +1,14 @@
 //!HID mice
+use crate::descriptor::ReportType;
+use crate::page::Button;
 use crate::usb_class::prelude::*;
 use core::default::Default;
 use fugit::ExtU32;
+use heapless::Deque;
 use packed_struct::prelude::*;
 use usb_device::bus::UsbBus;
 use usb_device::class_prelude::UsbBusAllocator;
+use usb_device::UsbError;
 
 /// HID Mouse report descriptor conforming to the Boot specification
 ///
@@ -172,6 +176,421 @@ pub struct AbsoluteWheelMouseReport {
     pub wheel: i8,
 }
 
+/// Wheel mouse with eight buttons and high resolution wheel and pan
+///
+/// The wheel and pan are each in a logical collection with a Resolution Multiplier, in feature
+/// report 2. Until the host sets a multiplier, the wheel and pan are reported in detents, after
+/// that in `1/120`ths of a detent.
+///
+/// Reference: <https://learn.microsoft.com/en-us/windows-hardware/design/component-guidelines/enhanced-wheel-support>
+#[rustfmt::skip]
+pub const HIGH_RESOLUTION_WHEEL_MOUSE_REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01,        // Usage Page (Generic Desktop),
+    0x09, 0x02,        // Usage (Mouse),
+    0xA1, 0x01,        // Collection (Application),
+    0x09, 0x01,        //   Usage (Pointer),
+    0xA1, 0x00,        //   Collection (Physical),
+    0x85, 0x01,        //     Report ID (1),
+    0x95, 0x08,        //     Report Count (8),
+    0x75, 0x01,        //     Report Size (1),
+    0x05, 0x09,        //     Usage Page (Buttons),
+    0x19, 0x01,        //     Usage Minimum (1),
+    0x29, 0x08,        //     Usage Maximum (8),
+    0x15, 0x00,        //     Logical Minimum (0),
+    0x25, 0x01,        //     Logical Maximum (1),
+    0x81, 0x02,        //     Input (Data, Variable, Absolute),
+
+    0x75, 0x08,        //     Report Size (8),
+    0x95, 0x02,        //     Report Count (2),
+    0x05, 0x01,        //     Usage Page (Generic Desktop),
+    0x09, 0x30,        //     Usage (X),
+    0x09, 0x31,        //     Usage (Y),
+    0x15, 0x81,        //     Logical Minimum (-127),
+    0x25, 0x7F,        //     Logical Maximum (127),
+    0x81, 0x06,        //     Input (Data, Variable, Relative),
+
+    0xA1, 0x02,        //     Collection (Logical),
+    0x85, 0x02,        //       Report ID (2),
+    0x09, 0x48,        //       Usage (Resolution Multiplier),
+    0x15, 0x00,        //       Logical Minimum (0),
+    0x25, 0x01,        //       Logical Maximum (1),
+    0x35, 0x01,        //       Physical Minimum (1),
+    0x45, 0x78,        //       Physical Maximum (120),
+    0x75, 0x02,        //       Report Size (2),
+    0x95, 0x01,        //       Report Count (1),
+    0xA4,              //       Push,
+    0xB1, 0x02,        //       Feature (Data, Variable, Absolute),
+    0x85, 0x01,        //       Report ID (1),
+    0x09, 0x38,        //       Usage (Wheel),
+    0x16, 0x01, 0x80,  //       Logical Minimum (-32767),
+    0x26, 0xFF, 0x7F,  //       Logical Maximum (32767),
+    0x35, 0x00,        //       Physical Minimum (0),
+    0x45, 0x00,        //       Physical Maximum (0),
+    0x75, 0x10,        //       Report Size (16),
+    0x81, 0x06,        //       Input (Data, Variable, Relative),
+    0xC0,              //     End Collection,
+
+    0xA1, 0x02,        //     Collection (Logical),
+    0x85, 0x02,        //       Report ID (2),
+    0x09, 0x48,        //       Usage (Resolution Multiplier),
+    0xB4,              //       Pop,
+    0xB1, 0x02,        //       Feature (Data, Variable, Absolute),
+    0x35, 0x00,        //       Physical Minimum (0),
+    0x45, 0x00,        //       Physical Maximum (0),
+    0x75, 0x04,        //       Report Size (4),
+    0xB1, 0x01,        //       Feature (Constant),
+    0x85, 0x01,        //       Report ID (1),
+    0x05, 0x0C,        //       Usage Page (Consumer),
+    0x0A, 0x38, 0x02,  //       Usage (AC Pan),
+    0x16, 0x01, 0x80,  //       Logical Minimum (-32767),
+    0x26, 0xFF, 0x7F,  //       Logical Maximum (32767),
+    0x75, 0x10,        //       Report Size (16),
+    0x81, 0x06,        //       Input (Data, Variable, Relative),
+    0xC0,              //     End Collection,
+    0xC0,              //   End Collection,
+    0xC0,              // End Collection
+];
+
+/// Wheel movement of one detent in high resolution units
+pub const HIGH_RESOLUTION_WHEEL_DETENT: i32 = 120;
+
+const HIGH_RESOLUTION_INPUT_REPORT_ID: u8 = 0x01;
+const RESOLUTION_MULTIPLIER_REPORT_ID: u8 = 0x02;
+
+/// High resolution wheel mouse report
+///
+/// The wheel and pan are in `1/120`ths of a detent, see [`HIGH_RESOLUTION_WHEEL_DETENT`], and are
+/// reduced to whole detents until the host enables the resolution multiplier.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub struct HighResolutionWheelMouseReport {
+    pub buttons: u8,
+    pub x: i8,
+    pub y: i8,
+    pub vertical_wheel: i16,
+    pub horizontal_wheel: i16,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "8")]
+struct HighResolutionInputReport {
+    #[packed_field]
+    report_id: u8,
+    #[packed_field]
+    buttons: u8,
+    #[packed_field]
+    x: i8,
+    #[packed_field]
+    y: i8,
+    #[packed_field]
+    vertical_wheel: i16,
+    #[packed_field]
+    horizontal_wheel: i16,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "2", bit_numbering = "msb0")]
+struct ResolutionMultiplierReport {
+    #[packed_field(bytes = "0")]
+    report_id: u8,
+    #[packed_field(bits = "15")]
+    vertical: bool,
+    #[packed_field(bits = "13")]
+    horizontal: bool,
+}
+
+/// Wheel movement not yet reported, in high resolution units
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+struct HighResolutionWheel {
+    high_resolution: bool,
+    remainder: i32,
+}
+
+impl HighResolutionWheel {
+    /// Add `movement` and return the value to report, in high resolution units or detents
+    #[allow(clippy::cast_possible_truncation)]
+    fn report(&mut self, movement: i16) -> i16 {
+        const MAX: i32 = i16::MAX as i32;
+        let total = self.remainder + i32::from(movement);
+        let (value, reported) = if self.high_resolution {
+            let value = total.clamp(-MAX, MAX);
+            (value, value)
+        } else {
+            let detents = (total / HIGH_RESOLUTION_WHEEL_DETENT).clamp(-MAX, MAX);
+            (detents, detents * HIGH_RESOLUTION_WHEEL_DETENT)
+        };
+        self.remainder = total - reported;
+        value as i16
+    }
+
+    fn set_high_resolution(&mut self, high_resolution: bool) {
+        if high_resolution != self.high_resolution {
+            self.high_resolution = high_resolution;
+            self.remainder = 0;
+        }
+    }
+}
+
+/// Boot compatible mouse with sixteen buttons, 16-bit movement, wheel and pan
+///
+/// In boot protocol a [`BootMouseReport`] is sent instead
+#[rustfmt::skip]
+pub const EXTENDED_MOUSE_REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01,        // Usage Page (Generic Desktop),
+    0x09, 0x02,        // Usage (Mouse),
+    0xA1, 0x01,        // Collection (Application),
+    0x09, 0x01,        //   Usage (Pointer),
+    0xA1, 0x00,        //   Collection (Physical),
+    0x95, 0x10,        //     Report Count (16),
+    0x75, 0x01,        //     Report Size (1),
+    0x05, 0x09,        //     Usage Page (Buttons),
+    0x19, 0x01,        //     Usage Minimum (1),
+    0x29, 0x10,        //     Usage Maximum (16),
+    0x15, 0x00,        //     Logical Minimum (0),
+    0x25, 0x01,        //     Logical Maximum (1),
+    0x81, 0x02,        //     Input (Data, Variable, Absolute),
+
+    0x75, 0x10,        //     Report Size (16),
+    0x95, 0x02,        //     Report Count (2),
+    0x05, 0x01,        //     Usage Page (Generic Desktop),
+    0x09, 0x30,        //     Usage (X),
+    0x09, 0x31,        //     Usage (Y),
+    0x16, 0x01, 0x80,  //     Logical Minimum (-32767),
+    0x26, 0xFF, 0x7F,  //     Logical Maximum (32767),
+    0x81, 0x06,        //     Input (Data, Variable, Relative),
+
+    0x15, 0x81,        //     Logical Minimum (-127),
+    0x25, 0x7F,        //     Logical Maximum (127),
+    0x09, 0x38,        //     Usage (Wheel),
+    0x75, 0x08,        //     Report Size (8),
+    0x95, 0x01,        //     Report Count (1),
+    0x81, 0x06,        //     Input (Data, Variable, Relative),
+    0x05, 0x0C,        //     Usage Page (Consumer),
+    0x0A, 0x38, 0x02,  //     Usage (AC Pan),
+    0x81, 0x06,        //     Input (Data, Variable, Relative),
+    0xC0,              //   End Collection,
+    0xC0,              // End Collection
+];
+
+/// Pressed mouse buttons, up to sixteen
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub struct MouseButtons(u16);
+
+impl MouseButtons {
+    pub const NONE: Self = Self(0);
+    pub const LEFT: Self = Self::button(Button::Primary);
+    pub const RIGHT: Self = Self::button(Button::Secondary);
+    pub const MIDDLE: Self = Self::button(Button::Tertiary);
+    pub const BACK: Self = Self::button(Button::Back);
+    pub const FORWARD: Self = Self::button(Button::Forward);
+
+    /// A single button, [`Button::NoButtonPressed`] is no buttons
+    #[must_use]
+    pub const fn button(button: Button) -> Self {
+        match button as u8 {
+            0 => Self::NONE,
+            b => Self(1 << (b - 1)),
+        }
+    }
+
+    #[must_use]
+    pub const fn from_bits(bits: u16) -> Self {
+        Self(bits)
+    }
+
+    #[must_use]
+    pub const fn bits(self) -> u16 {
+        self.0
+    }
+
+    #[must_use]
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    #[must_use]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Press or release `buttons`
+    pub fn set(&mut self, buttons: Self, pressed: bool) {
+        if pressed {
+            self.0 |= buttons.0;
+        } else {
+            self.0 &= !buttons.0;
+        }
+    }
+}
+
+impl From<Button> for MouseButtons {
+    fn from(button: Button) -> Self {
+        Self::button(button)
+    }
+}
+
+impl core::ops::BitOr for MouseButtons {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        self.union(rhs)
+    }
+}
+
+/// Extended mouse report
+///
+/// The minimum values, `i16::MIN` and `i8::MIN`, are outside the logical range of the report
+/// descriptor and are sent as -32767 and -127.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub struct ExtendedMouseReport {
+    pub buttons: MouseButtons,
+    pub x: i16,
+    pub y: i16,
+    pub vertical_wheel: i8,
+    pub horizontal_wheel: i8,
+}
+
+impl ExtendedMouseReport {
+    /// The boot protocol report, with the first eight buttons and the movement saturated to
+    /// -127 to 127
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn to_boot_report(&self) -> BootMouseReport {
+        let saturate = |v: i16| v.clamp(-127, 127) as i8;
+        BootMouseReport {
+            buttons: self.buttons.bits() as u8,
+            x: saturate(self.x),
+            y: saturate(self.y),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "8")]
+struct ExtendedMouseInputReport {
+    #[packed_field]
+    buttons: u16,
+    #[packed_field]
+    x: i16,
+    #[packed_field]
+    y: i16,
+    #[packed_field]
+    vertical_wheel: i8,
+    #[packed_field]
+    horizontal_wheel: i8,
+}
+
+/// Button states that can be queued between reports by a [`RelativePointer`]
+pub const BUTTON_QUEUE_SIZE: usize = 8;
+
+/// Relative pointer report produced by a [`RelativePointer`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub struct RelativePointerReport {
+    pub buttons: u16,
+    pub x: i32,
+    pub y: i32,
+    pub vertical_wheel: i32,
+    pub horizontal_wheel: i32,
+}
+
+/// Accumulates relative pointer motion and button changes between reports
+///
+/// Motion and wheel movement is added at any rate and sent clamped to the range of the report,
+/// with the remainder carried over to the following reports. Each change of the buttons is sent
+/// in its own report, so a press and release between two reports is not lost. Changes beyond
+/// [`BUTTON_QUEUE_SIZE`] unsent changes are rejected with [`UsbHidError::WouldBlock`].
+#[derive(Clone, Debug, Default)]
+pub struct RelativePointer {
+    x: i32,
+    y: i32,
+    vertical_wheel: i32,
+    horizontal_wheel: i32,
+    /// Button states not yet sent, oldest first
+    queued_buttons: Deque<u16, BUTTON_QUEUE_SIZE>,
+    buttons: u16,
+}
+
+impl RelativePointer {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn move_by(&mut self, x: i32, y: i32) {
+        self.x = self.x.saturating_add(x);
+        self.y = self.y.saturating_add(y);
+    }
+
+    pub fn scroll(&mut self, vertical: i32, horizontal: i32) {
+        self.vertical_wheel = self.vertical_wheel.saturating_add(vertical);
+        self.horizontal_wheel = self.horizontal_wheel.saturating_add(horizontal);
+    }
+
+    /// Set the pressed buttons, button 1 in the least significant bit
+    ///
+    /// Returns [`UsbHidError::WouldBlock`], leaving the buttons unchanged, if
+    /// [`BUTTON_QUEUE_SIZE`] changes are waiting to be sent.
+    pub fn set_buttons(&mut self, buttons: u16) -> Result<(), UsbHidError> {
+        if buttons == self.buttons {
+            return Ok(());
+        }
+        if self.queued_buttons.push_back(buttons).is_err() {
+            warn!("Button queue full, button state {:X} not queued", buttons);
+            return Err(UsbHidError::WouldBlock);
+        }
+        self.buttons = buttons;
+        Ok(())
+    }
+
+    pub fn press(&mut self, buttons: u16) -> Result<(), UsbHidError> {
+        self.set_buttons(self.buttons | buttons)
+    }
+
+    pub fn release(&mut self, buttons: u16) -> Result<(), UsbHidError> {
+        self.set_buttons(self.buttons & !buttons)
+    }
+
+    /// The most recently set buttons
+    #[must_use]
+    pub fn buttons(&self) -> u16 {
+        self.buttons
+    }
+
+    /// The next report to send, with motion within `-max_motion..=max_motion` and wheel
+    /// movement within `-max_wheel..=max_wheel`, or `None` if there is nothing to send
+    ///
+    /// Call [`RelativePointer::commit`] once the report has been sent.
+    #[must_use]
+    pub fn next_report(&self, max_motion: i32, max_wheel: i32) -> Option<RelativePointerReport> {
+        let report = RelativePointerReport {
+            buttons: self.queued_buttons.front().copied().unwrap_or(self.buttons),
+            x: self.x.clamp(-max_motion, max_motion),
+            y: self.y.clamp(-max_motion, max_motion),
+            vertical_wheel: self.vertical_wheel.clamp(-max_wheel, max_wheel),
+            horizontal_wheel: self.horizontal_wheel.clamp(-max_wheel, max_wheel),
+        };
+        if self.queued_buttons.is_empty()
+            && report.x == 0
+            && report.y == 0
+            && report.vertical_wheel == 0
+            && report.horizontal_wheel == 0
+        {
+            None
+        } else {
+            Some(report)
+        }
+    }
+
+    /// Remove a report returned by [`RelativePointer::next_report`] that has been sent
+    pub fn commit(&mut self, report: &RelativePointerReport) {
+        self.x -= report.x;
+        self.y -= report.y;
+        self.vertical_wheel -= report.vertical_wheel;
+        self.horizontal_wheel -= report.horizontal_wheel;
+        self.queued_buttons.pop_front();
+    }
+}
+
 pub struct BootMouse<'a, B: UsbBus> {
     interface: Interface<'a, B, InBytes8, OutNone, ReportSingle>,
 }
@@ -240,6 +659,7 @@ impl<'a, B: UsbBus> DeviceClass<'a> for BootMouse<'a, B> {
 
 pub struct WheelMouse<'a, B: UsbBus> {
     interface: Interface<'a, B, InBytes8, OutNone, ReportSingle>,
+    pointer: RelativePointer,
 }
 
 impl<'a, B: UsbBus> WheelMouse<'a, B> {
@@ -253,6 +673,32 @@ impl<'a, B: UsbBus> WheelMouse<'a, B> {
             .map(|_| ())
             .map_err(UsbHidError::from)
     }
+
+    /// Motion and buttons sent by [`WheelMouse::write_pending`]
+    pub fn pointer(&mut self) -> &mut RelativePointer {
+        &mut self.pointer
+    }
+
+    /// Send the next report from [`WheelMouse::pointer`]
+    ///
+    /// Called by [`tick`](DeviceClass::tick), returns [`UsbHidError::Duplicate`] if there is
+    /// nothing to send.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn write_pending(&mut self) -> Result<(), UsbHidError> {
+        let max = i32::from(i8::MAX);
+        let Some(pending) = self.pointer.next_report(max, max) else {
+            return Err(UsbHidError::Duplicate);
+        };
+        self.write_report(&WheelMouseReport {
+            buttons: pending.buttons as u8,
+            x: pending.x as i8,
+            y: pending.y as i8,
+            vertical_wheel: pending.vertical_wheel as i8,
+            horizontal_wheel: pending.horizontal_wheel as i8,
+        })?;
+        self.pointer.commit(&pending);
+        Ok(())
+    }
 }
 pub struct WheelMouseConfig<'a> {
     interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>,
@@ -287,6 +733,7 @@ impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for WheelMouseConfig<'a> {
     fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
         WheelMouse {
             interface: self.interface.allocate(usb_alloc),
+            pointer: RelativePointer::default(),
         }
     }
 }
@@ -298,10 +745,15 @@ impl<'a, B: UsbBus> DeviceClass<'a> for WheelMouse<'a, B> {
         &mut self.interface
     }
 
-    fn reset(&mut self) {}
+    fn reset(&mut self) {
+        self.pointer = RelativePointer::default();
+    }
 
     fn tick(&mut self) -> Result<(), UsbHidError> {
-        Ok(())
+        match self.write_pending() {
+            Err(UsbHidError::WouldBlock | UsbHidError::Duplicate) => Ok(()),
+            result => result,
+        }
     }
 }
 
@@ -371,3 +823,454 @@ impl<'a, B: UsbBus> DeviceClass<'a> for AbsoluteWheelMouse<'a, B> {
         Ok(())
     }
 }
+
+pub struct HighResolutionWheelMouse<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes8, OutNone, ReportSingle>,
+    vertical_wheel: HighResolutionWheel,
+    horizontal_wheel: HighResolutionWheel,
+}
+
+impl<'a, B: UsbBus> HighResolutionWheelMouse<'a, B> {
+    /// Write a report, with the wheel and pan in `1/120`ths of a detent
+    ///
+    /// Wheel and pan movement too small to report in detents is carried over to the following
+    /// reports.
+    pub fn write_report(
+        &mut self,
+        report: &HighResolutionWheelMouseReport,
+    ) -> Result<(), UsbHidError> {
+        let mut vertical_wheel = self.vertical_wheel;
+        let mut horizontal_wheel = self.horizontal_wheel;
+        let data = HighResolutionInputReport {
+            report_id: HIGH_RESOLUTION_INPUT_REPORT_ID,
+            buttons: report.buttons,
+            x: report.x,
+            y: report.y,
+            vertical_wheel: vertical_wheel.report(report.vertical_wheel),
+            horizontal_wheel: horizontal_wheel.report(report.horizontal_wheel),
+        }
+        .pack()
+        .map_err(|_| {
+            error!("Error packing HighResolutionInputReport");
+            UsbHidError::SerializationError
+        })?;
+        self.interface.write_report(&data)?;
+        self.vertical_wheel = vertical_wheel;
+        self.horizontal_wheel = horizontal_wheel;
+        Ok(())
+    }
+
+    /// True once the host has enabled the vertical wheel resolution multiplier
+    #[must_use]
+    pub fn vertical_high_resolution(&self) -> bool {
+        self.vertical_wheel.high_resolution
+    }
+
+    /// True once the host has enabled the horizontal wheel resolution multiplier
+    #[must_use]
+    pub fn horizontal_high_resolution(&self) -> bool {
+        self.horizontal_wheel.high_resolution
+    }
+}
+
+pub struct HighResolutionWheelMouseConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>,
+}
+
+impl<'a> HighResolutionWheelMouseConfig<'a> {
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>) -> Self {
+        Self { interface }
+    }
+}
+
+impl<'a> Default for HighResolutionWheelMouseConfig<'a> {
+    #[must_use]
+    fn default() -> Self {
+        HighResolutionWheelMouseConfig::new(
+            unwrap!(unwrap!(InterfaceBuilder::new(
+                HIGH_RESOLUTION_WHEEL_MOUSE_REPORT_DESCRIPTOR
+            ))
+            .description("High Resolution Wheel Mouse")
+            .in_endpoint(1.millis()))
+            .without_out_endpoint()
+            .build(),
+        )
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for HighResolutionWheelMouseConfig<'a> {
+    type Allocated = HighResolutionWheelMouse<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        HighResolutionWheelMouse {
+            interface: self.interface.allocate(usb_alloc),
+            vertical_wheel: HighResolutionWheel::default(),
+            horizontal_wheel: HighResolutionWheel::default(),
+        }
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for HighResolutionWheelMouse<'a, B> {
+    type I = Interface<'a, B, InBytes8, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {
+        self.vertical_wheel = HighResolutionWheel::default();
+        self.horizontal_wheel = HighResolutionWheel::default();
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        Ok(())
+    }
+
+    fn set_report(
+        &mut self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &[u8],
+    ) -> usb_device::Result<()> {
+        if report_type != ReportType::Feature || report_id != RESOLUTION_MULTIPLIER_REPORT_ID {
+            warn!("Unsupported mouse SET_REPORT report ID {:X}", report_id);
+            return Err(UsbError::Unsupported);
+        }
+        let report = ResolutionMultiplierReport::unpack_from_slice(data).map_err(|_| {
+            error!("Error unpacking ResolutionMultiplierReport");
+            UsbError::ParseError
+        })?;
+        info!(
+            "Resolution multiplier vertical: {}, horizontal: {}",
+            report.vertical, report.horizontal
+        );
+        self.vertical_wheel.set_high_resolution(report.vertical);
+        self.horizontal_wheel.set_high_resolution(report.horizontal);
+        Ok(())
+    }
+
+    fn get_report(
+        &mut self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &mut [u8],
+    ) -> usb_device::Result<usize> {
+        if report_type != ReportType::Feature || report_id != RESOLUTION_MULTIPLIER_REPORT_ID {
+            return Err(UsbError::Unsupported);
+        }
+        let report = ResolutionMultiplierReport {
+            report_id,
+            vertical: self.vertical_wheel.high_resolution,
+            horizontal: self.horizontal_wheel.high_resolution,
+        }
+        .pack()
+        .map_err(|_| {
+            error!("Error packing ResolutionMultiplierReport");
+            UsbError::ParseError
+        })?;
+        let Some(data) = data.get_mut(..report.len()) else {
+            error!("GetReport failed, buffer too short");
+            return Err(UsbError::BufferOverflow);
+        };
+        data.copy_from_slice(&report);
+        Ok(report.len())
+    }
+
+    fn get_report_ack(&mut self, _: ReportType, _: u8) -> usb_device::Result<()> {
+        Ok(())
+    }
+}
+
+pub struct ExtendedMouse<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes8, OutNone, ReportSingle>,
+    pointer: RelativePointer,
+}
+
+impl<'a, B: UsbBus> ExtendedMouse<'a, B> {
+    /// Write a report, as a [`BootMouseReport`] if the host selected the boot protocol
+    pub fn write_report(&mut self, report: &ExtendedMouseReport) -> Result<(), UsbHidError> {
+        if self.interface.protocol() == HidProtocol::Boot {
+            let data = report.to_boot_report().pack().map_err(|_| {
+                error!("Error packing BootMouseReport");
+                UsbHidError::SerializationError
+            })?;
+            return self
+                .interface
+                .write_report(&data)
+                .map(|_| ())
+                .map_err(UsbHidError::from);
+        }
+        let data = ExtendedMouseInputReport {
+            buttons: report.buttons.bits(),
+            x: report.x.max(-i16::MAX),
+            y: report.y.max(-i16::MAX),
+            vertical_wheel: report.vertical_wheel.max(-i8::MAX),
+            horizontal_wheel: report.horizontal_wheel.max(-i8::MAX),
+        }
+        .pack()
+        .map_err(|_| {
+            error!("Error packing ExtendedMouseInputReport");
+            UsbHidError::SerializationError
+        })?;
+        self.interface
+            .write_report(&data)
+            .map(|_| ())
+            .map_err(UsbHidError::from)
+    }
+
+    /// Motion and buttons sent by [`ExtendedMouse::write_pending`]
+    pub fn pointer(&mut self) -> &mut RelativePointer {
+        &mut self.pointer
+    }
+
+    /// Send the next report from [`ExtendedMouse::pointer`]
+    ///
+    /// Motion is limited to the range of the boot report in boot protocol, and wheel movement,
+    /// which the boot report cannot carry, is held until the host selects the report protocol.
+    /// Called by [`tick`](DeviceClass::tick), returns [`UsbHidError::Duplicate`] if there is
+    /// nothing to send.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn write_pending(&mut self) -> Result<(), UsbHidError> {
+        let (max_motion, max_wheel) = if self.interface.protocol() == HidProtocol::Boot {
+            (i32::from(i8::MAX), 0)
+        } else {
+            (i32::from(i16::MAX), i32::from(i8::MAX))
+        };
+        let Some(pending) = self.pointer.next_report(max_motion, max_wheel) else {
+            return Err(UsbHidError::Duplicate);
+        };
+        self.write_report(&ExtendedMouseReport {
+            buttons: MouseButtons::from_bits(pending.buttons),
+            x: pending.x as i16,
+            y: pending.y as i16,
+            vertical_wheel: pending.vertical_wheel as i8,
+            horizontal_wheel: pending.horizontal_wheel as i8,
+        })?;
+        self.pointer.commit(&pending);
+        Ok(())
+    }
+}
+
+pub struct ExtendedMouseConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>,
+}
+
+impl<'a> ExtendedMouseConfig<'a> {
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>) -> Self {
+        Self { interface }
+    }
+}
+
+impl<'a> Default for ExtendedMouseConfig<'a> {
+    #[must_use]
+    fn default() -> Self {
+        ExtendedMouseConfig::new(
+            unwrap!(
+                unwrap!(InterfaceBuilder::new(EXTENDED_MOUSE_REPORT_DESCRIPTOR))
+                    .boot_device(InterfaceProtocol::Mouse)
+                    .description("Mouse")
+                    .in_endpoint(1.millis())
+            )
+            .without_out_endpoint()
+            .build(),
+        )
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for ExtendedMouseConfig<'a> {
+    type Allocated = ExtendedMouse<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        ExtendedMouse {
+            interface: self.interface.allocate(usb_alloc),
+            pointer: RelativePointer::default(),
+        }
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for ExtendedMouse<'a, B> {
+    type I = Interface<'a, B, InBytes8, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {
+        self.pointer = RelativePointer::default();
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        match self.write_pending() {
+            Err(UsbHidError::WouldBlock | UsbHidError::Duplicate) => Ok(()),
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::interface::InterfaceClass;
+    use crate::test_bus::{build_device, TestBusState, TestUsbBus};
+
+    #[test]
+    fn resolution_multiplier_report() {
+        let report = ResolutionMultiplierReport::unpack(&[0x02, 0x05]).unwrap();
+        assert!(report.vertical);
+        assert!(report.horizontal);
+        let report = ResolutionMultiplierReport::unpack(&[0x02, 0x01]).unwrap();
+        assert!(report.vertical);
+        assert!(!report.horizontal);
+    }
+
+    #[test]
+    fn wheel_detents_until_high_resolution() {
+        let mut wheel = HighResolutionWheel::default();
+        assert_eq!(wheel.report(60), 0);
+        assert_eq!(wheel.report(-30), 0);
+        assert_eq!(wheel.report(100), 1);
+        assert_eq!(wheel.report(-40), 0);
+        assert_eq!(wheel.report(-100), -1);
+
+        wheel.set_high_resolution(true);
+        assert_eq!(wheel.report(15), 15);
+        assert_eq!(wheel.report(-1), -1);
+    }
+
+    #[test]
+    fn mouse_buttons() {
+        let mut buttons = MouseButtons::LEFT | MouseButtons::BACK;
+        assert_eq!(buttons.bits(), 0x0009);
+        assert!(buttons.contains(MouseButtons::LEFT));
+        assert!(!buttons.contains(MouseButtons::LEFT | MouseButtons::RIGHT));
+        buttons.set(Button::Button16.into(), true);
+        buttons.set(MouseButtons::LEFT, false);
+        assert_eq!(buttons.bits(), 0x8008);
+        assert_eq!(
+            MouseButtons::from(Button::NoButtonPressed),
+            MouseButtons::NONE
+        );
+    }
+
+    #[test]
+    fn extended_mouse_boot_report() {
+        let report = ExtendedMouseReport {
+            buttons: MouseButtons::RIGHT | Button::Button12.into(),
+            x: 1000,
+            y: -5,
+            ..Default::default()
+        };
+        assert_eq!(report.to_boot_report().pack().unwrap(), [0x02, 0x7F, 0xFB]);
+    }
+
+    #[test]
+    fn relative_pointer_carries_remainder() {
+        let mut pointer = RelativePointer::new();
+        assert_eq!(pointer.next_report(127, 127), None);
+
+        pointer.move_by(300, -10);
+        pointer.scroll(1, 0);
+        let report = pointer.next_report(127, 127).unwrap();
+        assert_eq!((report.x, report.y, report.vertical_wheel), (127, -10, 1));
+        pointer.commit(&report);
+
+        pointer.move_by(0, 5);
+        let report = pointer.next_report(127, 127).unwrap();
+        assert_eq!((report.x, report.y, report.vertical_wheel), (127, 5, 0));
+        pointer.commit(&report);
+
+        let report = pointer.next_report(127, 127).unwrap();
+        assert_eq!((report.x, report.y), (46, 0));
+        pointer.commit(&report);
+        assert_eq!(pointer.next_report(127, 127), None);
+    }
+
+    #[test]
+    fn relative_pointer_sends_every_button_change() {
+        let mut pointer = RelativePointer::new();
+        pointer.press(0x01).unwrap();
+        pointer.release(0x01).unwrap();
+        pointer.press(0x01).unwrap();
+        pointer.press(0x01).unwrap();
+        assert_eq!(pointer.buttons(), 0x01);
+
+        for buttons in [0x01, 0x00, 0x01] {
+            let report = pointer.next_report(127, 127).unwrap();
+            assert_eq!(report.buttons, buttons);
+            pointer.commit(&report);
+        }
+        assert_eq!(pointer.next_report(127, 127), None);
+
+        pointer.move_by(1, 0);
+        assert_eq!(pointer.next_report(127, 127).unwrap().buttons, 0x01);
+    }
+
+    #[test]
+    fn relative_pointer_button_queue_full() {
+        let mut pointer = RelativePointer::new();
+        for i in 0..BUTTON_QUEUE_SIZE {
+            pointer.set_buttons(u16::from(i % 2 == 0)).unwrap();
+        }
+        assert!(matches!(pointer.press(0x01), Err(UsbHidError::WouldBlock)));
+        assert_eq!(pointer.buttons(), 0x00);
+
+        let report = pointer.next_report(127, 127).unwrap();
+        pointer.commit(&report);
+        pointer.press(0x01).unwrap();
+
+        let mut sent = std::vec::Vec::new();
+        while let Some(report) = pointer.next_report(127, 127) {
+            sent.push(report.buttons);
+            pointer.commit(&report);
+        }
+        assert_eq!(sent, [0, 1, 0, 1, 0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn boot_protocol_keeps_wheel_movement() {
+        let state = TestBusState::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&state));
+        let mut mouse = ExtendedMouseConfig::default().allocate(&usb_alloc);
+        let _usb_dev = build_device(&usb_alloc);
+
+        mouse.interface().set_protocol(HidProtocol::Boot);
+        mouse.pointer().move_by(1, 0);
+        mouse.pointer().scroll(2, 0);
+        mouse.write_pending().unwrap();
+        assert_eq!(state.take_written(), [[0x00, 0x01, 0x00]]);
+        assert!(matches!(mouse.write_pending(), Err(UsbHidError::Duplicate)));
+
+        mouse.interface().set_protocol(HidProtocol::Report);
+        mouse.write_pending().unwrap();
+        let report = ExtendedMouseInputReport::unpack_from_slice(&state.take_written()[0]).unwrap();
+        assert_eq!(report.x, 0);
+        assert_eq!(report.vertical_wheel, 2);
+    }
+
+    #[test]
+    fn extended_report_clamped_to_logical_range() {
+        let state = TestBusState::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&state));
+        let mut mouse = ExtendedMouseConfig::default().allocate(&usb_alloc);
+        let _usb_dev = build_device(&usb_alloc);
+
+        mouse
+            .write_report(&ExtendedMouseReport {
+                buttons: MouseButtons::default(),
+                x: i16::MIN,
+                y: i16::MAX,
+                vertical_wheel: i8::MIN,
+                horizontal_wheel: i8::MAX,
+            })
+            .unwrap();
+        let report = ExtendedMouseInputReport::unpack_from_slice(&state.take_written()[0]).unwrap();
+        assert_eq!(report.x, -32767);
+        assert_eq!(report.y, 32767);
+        assert_eq!(report.vertical_wheel, -127);
+        assert_eq!(report.horizontal_wheel, 127);
+    }
+}
@@ -0,0 +1,293 @@
+//!Six degrees of freedom multi-axis controller
+//!
+//! A Multi-axis Controller with X, Y and Z translation, Rx, Ry and Rz rotation and 32 buttons, in
+//! separate reports the way 3D mice present them, so that Linux `spacenavd` and other 3D mouse
+//! software recognise it.
+//!
+//! Axes are relative, reporting the displacement of the cap from its rest position, and are sent
+//! for as long as they are non-zero.
+use crate::usb_class::prelude::*;
+use core::default::Default;
+use fugit::ExtU32;
+use packed_struct::prelude::*;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+
+const TRANSLATION_REPORT_ID: u8 = 0x01;
+const ROTATION_REPORT_ID: u8 = 0x02;
+const BUTTONS_REPORT_ID: u8 = 0x03;
+
+/// Multi-axis controller report descriptor
+#[rustfmt::skip]
+pub const MULTI_AXIS_CONTROLLER_REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01,       // Usage Page (Generic Desktop),
+    0x09, 0x08,       // Usage (Multi-axis Controller),
+    0xA1, 0x01,       // Collection (Application),
+    0xA1, 0x00,       //   Collection (Physical),
+    0x85, 0x01,       //     Report ID (1),
+    0x16, 0x00, 0x80, //     Logical Minimum (-32768),
+    0x26, 0xFF, 0x7F, //     Logical Maximum (32767),
+    0x75, 0x10,       //     Report Size (16),
+    0x95, 0x03,       //     Report Count (3),
+    0x09, 0x30,       //     Usage (X),
+    0x09, 0x31,       //     Usage (Y),
+    0x09, 0x32,       //     Usage (Z),
+    0x81, 0x06,       //     Input (Data, Variable, Relative),
+    0xC0,             //   End Collection,
+    0xA1, 0x00,       //   Collection (Physical),
+    0x85, 0x02,       //     Report ID (2),
+    0x09, 0x33,       //     Usage (Rx),
+    0x09, 0x34,       //     Usage (Ry),
+    0x09, 0x35,       //     Usage (Rz),
+    0x81, 0x06,       //     Input (Data, Variable, Relative),
+    0xC0,             //   End Collection,
+    0x85, 0x03,       //   Report ID (3),
+    0x05, 0x09,       //   Usage Page (Button),
+    0x19, 0x01,       //   Usage Minimum (1),
+    0x29, 0x20,       //   Usage Maximum (32),
+    0x15, 0x00,       //   Logical Minimum (0),
+    0x25, 0x01,       //   Logical Maximum (1),
+    0x75, 0x01,       //   Report Size (1),
+    0x95, 0x20,       //   Report Count (32),
+    0x81, 0x02,       //   Input (Data, Variable, Absolute),
+    0xC0,             // End Collection
+];
+
+/// State of a multi-axis controller
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub struct MultiAxisReport {
+    pub x: i16,
+    pub y: i16,
+    pub z: i16,
+    pub rx: i16,
+    pub ry: i16,
+    pub rz: i16,
+    /// Button bitmap, button 1 in the least significant bit
+    pub buttons: u32,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "7")]
+struct AxesReport {
+    #[packed_field]
+    report_id: u8,
+    #[packed_field(element_size_bytes = "2")]
+    axes: [i16; 3],
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "5")]
+struct ButtonsReport {
+    #[packed_field]
+    report_id: u8,
+    #[packed_field]
+    buttons: u32,
+}
+
+/// Report pending transmission, sent when it changes and, for the axes, while it is non-zero
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+struct Pending<T> {
+    value: T,
+    sent: T,
+    dirty: bool,
+}
+
+impl<T: Copy + PartialEq + Default> Pending<T> {
+    fn update(&mut self, value: T, repeat: bool) {
+        self.dirty |= value != self.sent || (repeat && value != T::default());
+        self.value = value;
+    }
+
+    /// Record that `value` has been sent
+    fn mark_sent(&mut self) {
+        self.sent = self.value;
+        self.dirty = false;
+    }
+}
+
+pub struct MultiAxisController<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes8, OutNone, ReportSingle>,
+    translation: Pending<[i16; 3]>,
+    rotation: Pending<[i16; 3]>,
+    buttons: Pending<u32>,
+}
+
+impl<'a, B: UsbBus> MultiAxisController<'a, B> {
+    /// Send the reports for the parts of `report` that need sending
+    ///
+    /// Translation and rotation are sent if they changed or are non-zero, buttons if they
+    /// changed. Returns [`UsbHidError::Duplicate`] if nothing needs sending and
+    /// [`UsbHidError::WouldBlock`] if some reports will be sent on a later
+    /// [`tick`](DeviceClass::tick).
+    pub fn write_report(&mut self, report: &MultiAxisReport) -> Result<(), UsbHidError> {
+        self.translation
+            .update([report.x, report.y, report.z], true);
+        self.rotation
+            .update([report.rx, report.ry, report.rz], true);
+        self.buttons.update(report.buttons, false);
+        if !(self.translation.dirty || self.rotation.dirty || self.buttons.dirty) {
+            return Err(UsbHidError::Duplicate);
+        }
+        self.write_pending()
+    }
+
+    fn write_axes(
+        interface: &mut Interface<'a, B, InBytes8, OutNone, ReportSingle>,
+        report_id: u8,
+        pending: &mut Pending<[i16; 3]>,
+    ) -> Result<(), UsbHidError> {
+        if pending.dirty {
+            let data = AxesReport {
+                report_id,
+                axes: pending.value,
+            }
+            .pack()
+            .map_err(|_| {
+                error!("Error packing AxesReport");
+                UsbHidError::SerializationError
+            })?;
+            interface.write_report(&data)?;
+            pending.mark_sent();
+        }
+        Ok(())
+    }
+
+    fn write_pending(&mut self) -> Result<(), UsbHidError> {
+        Self::write_axes(
+            &mut self.interface,
+            TRANSLATION_REPORT_ID,
+            &mut self.translation,
+        )?;
+        Self::write_axes(&mut self.interface, ROTATION_REPORT_ID, &mut self.rotation)?;
+        if self.buttons.dirty {
+            let data = ButtonsReport {
+                report_id: BUTTONS_REPORT_ID,
+                buttons: self.buttons.value,
+            }
+            .pack()
+            .map_err(|_| {
+                error!("Error packing ButtonsReport");
+                UsbHidError::SerializationError
+            })?;
+            self.interface.write_report(&data)?;
+            self.buttons.mark_sent();
+        }
+        Ok(())
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for MultiAxisController<'a, B> {
+    type I = Interface<'a, B, InBytes8, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {
+        self.translation = Pending::default();
+        self.rotation = Pending::default();
+        self.buttons = Pending::default();
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        match self.write_pending() {
+            Err(UsbHidError::WouldBlock) => Ok(()),
+            result => result,
+        }
+    }
+}
+
+pub struct MultiAxisControllerConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>,
+}
+
+impl<'a> MultiAxisControllerConfig<'a> {
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>) -> Self {
+        Self { interface }
+    }
+}
+
+impl<'a> Default for MultiAxisControllerConfig<'a> {
+    #[must_use]
+    fn default() -> Self {
+        Self::new(
+            unwrap!(unwrap!(InterfaceBuilder::new(
+                MULTI_AXIS_CONTROLLER_REPORT_DESCRIPTOR
+            ))
+            .description("Multi-axis Controller")
+            .in_endpoint(1.millis()))
+            .without_out_endpoint()
+            .build(),
+        )
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for MultiAxisControllerConfig<'a> {
+    type Allocated = MultiAxisController<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        MultiAxisController {
+            interface: Interface::new(usb_alloc, self.interface),
+            translation: Pending::default(),
+            rotation: Pending::default(),
+            buttons: Pending::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn axes_report() {
+        let report = AxesReport {
+            report_id: ROTATION_REPORT_ID,
+            axes: [1, -1, 0x1234],
+        };
+        assert_eq!(
+            report.pack().unwrap(),
+            [0x02, 0x01, 0x00, 0xFF, 0xFF, 0x34, 0x12]
+        );
+    }
+
+    #[test]
+    fn pending_axes_repeat_while_non_zero() {
+        let mut pending = Pending::<[i16; 3]>::default();
+        pending.update([0, 0, 0], true);
+        assert!(!pending.dirty);
+
+        pending.update([5, 0, 0], true);
+        assert!(pending.dirty);
+        pending.mark_sent();
+
+        pending.update([5, 0, 0], true);
+        assert!(pending.dirty);
+        pending.mark_sent();
+
+        pending.update([0, 0, 0], true);
+        assert!(pending.dirty);
+        pending.mark_sent();
+
+        pending.update([0, 0, 0], true);
+        assert!(!pending.dirty);
+    }
+
+    #[test]
+    fn pending_buttons_sent_on_change() {
+        let mut pending = Pending::<u32>::default();
+        pending.update(0x01, false);
+        assert!(pending.dirty);
+        pending.mark_sent();
+        assert_eq!(pending.sent, 0x01);
+        assert!(!pending.dirty);
+
+        pending.update(0x01, false);
+        assert!(!pending.dirty);
+        pending.update(0x00, false);
+        assert!(pending.dirty);
+    }
+}
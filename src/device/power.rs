@@ -0,0 +1,927 @@
+//!HID Power Device for UPS and battery monitoring
+//!
+//! See [Universal Serial Bus Usage Tables for HID Power Devices Release 1.0](<https://www.usb.org/sites/default/files/pdcv10.pdf>).
+//!
+//! [`PowerDevice`] describes a UPS with a single Power Summary collection, laid out so that the
+//! Network UPS Tools `usbhid-ups` driver and the Windows HID battery driver recognise it. Most
+//! values are feature reports read by the host with `GET_REPORT`, [`PresentStatus`] and the
+//! remaining capacity and run time are also sent as input reports whenever they change.
+//!
+//! Capacities are in percent. The host can set the remaining and warning capacity limits and
+//! request a delayed shutdown or startup, see [`PowerDevice::shutdown_requested`].
+use crate::descriptor::builder::{Collection, ItemFlags, ReportDescriptorBuilder};
+use crate::page::{BatterySystem, PowerDevice as Power, UsagePage};
+use crate::usb_class::prelude::*;
+use core::default::Default;
+use fugit::ExtU32;
+use packed_struct::prelude::*;
+use usb_device::bus::{StringIndex, UsbBus};
+use usb_device::class_prelude::UsbBusAllocator;
+use usb_device::UsbError;
+
+/// Capacity Mode - capacities are reported in percent
+const CAPACITY_MODE_PERCENT: u8 = 2;
+/// Delay value meaning no shutdown or startup is pending
+const DELAY_NONE: i16 = -1;
+const MS_PER_SECOND: u16 = 1000;
+const MAX_PERCENT: i32 = 100;
+
+const CAPACITY_REPORT_ID: u8 = 0x01;
+const INFORMATION_REPORT_ID: u8 = 0x02;
+const DELAY_REPORT_ID: u8 = 0x03;
+const PRESENT_STATUS_REPORT_ID: u8 = 0x04;
+const REMAINING_REPORT_ID: u8 = 0x05;
+const VOLTAGE_REPORT_ID: u8 = 0x06;
+
+/// Present Status usages, in the order of the [`PresentStatus`] bits
+const fn present_status_usages<const N: usize>(
+    builder: ReportDescriptorBuilder<N>,
+) -> ReportDescriptorBuilder<N> {
+    builder
+        .usage_page(UsagePage::BatterySystem as u16)
+        .usage(BatterySystem::Charging as u16)
+        .usage(BatterySystem::Discharging as u16)
+        .usage(BatterySystem::AcPresent as u16)
+        .usage(BatterySystem::BatteryPresent as u16)
+        .usage(BatterySystem::BelowRemainingCapacityLimit as u16)
+        .usage(BatterySystem::FullyCharged as u16)
+        .usage(BatterySystem::NeedReplacement as u16)
+        .logical_minimum(0)
+        .logical_maximum(1)
+        .report_size(1)
+        .report_count(7)
+}
+
+const fn power_status_usages<const N: usize>(
+    builder: ReportDescriptorBuilder<N>,
+) -> ReportDescriptorBuilder<N> {
+    builder
+        .usage_page(UsagePage::PowerDevice as u16)
+        .usage(Power::ShutdownImminent as u16)
+        .usage(Power::Overload as u16)
+        .usage(Power::OverTemperature as u16)
+        .usage(Power::InternalFailure as u16)
+        .report_count(4)
+}
+
+/// Remaining Capacity in percent
+const fn remaining_capacity<const N: usize>(
+    builder: ReportDescriptorBuilder<N>,
+) -> ReportDescriptorBuilder<N> {
+    builder
+        .usage_page(UsagePage::BatterySystem as u16)
+        .usage(BatterySystem::RemainingCapacity as u16)
+        .logical_minimum(0)
+        .logical_maximum(MAX_PERCENT)
+        .unit(0)
+        .report_size(8)
+        .report_count(1)
+}
+
+/// Run Time To Empty in seconds
+const fn run_time_to_empty<const N: usize>(
+    builder: ReportDescriptorBuilder<N>,
+) -> ReportDescriptorBuilder<N> {
+    builder
+        .usage(BatterySystem::RunTimeToEmpty as u16)
+        .logical_maximum(0xFFFF)
+        .unit(0x1001)
+        .unit_exponent(0)
+        .report_size(16)
+}
+
+/// Capacity reserved for the power device report descriptor
+pub const DESCRIPTOR_CAPACITY: usize = 384;
+
+/// UPS report descriptor
+///
+/// A Power Summary collection with the capacity, information, delay, present status, remaining
+/// capacity and voltage reports
+pub const POWER_DEVICE_DESCRIPTOR: ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> = {
+    let dva = ItemFlags::DATA_VARIABLE_ABSOLUTE;
+    let cva = ItemFlags::CONSTANT_VARIABLE_ABSOLUTE;
+
+    let builder = ReportDescriptorBuilder::new()
+        .usage_page(UsagePage::PowerDevice as u16)
+        .usage(Power::Ups as u16)
+        .collection(Collection::Application)
+        .usage(Power::PowerSummary as u16)
+        .collection(Collection::Physical)
+        // Capacity
+        .report_id(CAPACITY_REPORT_ID)
+        .usage_page(UsagePage::BatterySystem as u16)
+        .usage(BatterySystem::CapacityMode as u16)
+        .usage(BatterySystem::DesignCapacity as u16)
+        .usage(BatterySystem::FullChargeCapacity as u16)
+        .usage(BatterySystem::CapacityGranularity1 as u16)
+        .usage(BatterySystem::CapacityGranularity2 as u16)
+        .logical_minimum(0)
+        .logical_maximum(MAX_PERCENT)
+        .report_size(8)
+        .report_count(5)
+        .feature(cva)
+        .usage(BatterySystem::WarningCapacityLimit as u16)
+        .usage(BatterySystem::RemainingCapacityLimit as u16)
+        .report_count(2)
+        .feature(dva)
+        // Information
+        .report_id(INFORMATION_REPORT_ID)
+        .usage(BatterySystem::Rechargeable as u16)
+        .logical_maximum(1)
+        .report_size(1)
+        .report_count(1)
+        .feature(cva)
+        .feature_padding(7)
+        .usage_page(UsagePage::PowerDevice as u16)
+        .usage(Power::IManufacturer as u16)
+        .usage(Power::IProduct as u16)
+        .usage(Power::ISerialNumber as u16)
+        .usage_page(UsagePage::BatterySystem as u16)
+        .usage(BatterySystem::IDeviceChemistry as u16)
+        .usage(BatterySystem::IOemInformation as u16)
+        .logical_maximum(0xFF)
+        .report_size(8)
+        .report_count(5)
+        .feature(cva)
+        .usage(BatterySystem::ManufactureDate as u16)
+        .logical_maximum(0xFFFF)
+        .report_size(16)
+        .report_count(1)
+        .feature(cva)
+        // Delays
+        .report_id(DELAY_REPORT_ID)
+        .usage_page(UsagePage::PowerDevice as u16)
+        .usage(Power::DelayBeforeShutdown as u16)
+        .usage(Power::DelayBeforeStartup as u16)
+        .logical_minimum(DELAY_NONE as i32)
+        .logical_maximum(i16::MAX as i32)
+        .unit(0x1001)
+        .report_count(2)
+        .feature(dva)
+        .unit(0)
+        // Present Status
+        .report_id(PRESENT_STATUS_REPORT_ID)
+        .usage(Power::PresentStatus as u16)
+        .collection(Collection::Logical);
+    let builder = power_status_usages(present_status_usages(builder).input(dva))
+        .input(dva)
+        .input_padding(5);
+    let builder = power_status_usages(present_status_usages(builder).feature(dva))
+        .feature(dva)
+        .feature_padding(5)
+        .end_collection()
+        // Remaining
+        .report_id(REMAINING_REPORT_ID);
+    let builder = run_time_to_empty(remaining_capacity(builder).input(dva)).input(dva);
+    let builder = run_time_to_empty(remaining_capacity(builder).feature(dva)).feature(dva);
+    builder
+        // Voltage in centivolts
+        .report_id(VOLTAGE_REPORT_ID)
+        .usage_page(UsagePage::PowerDevice as u16)
+        .usage(Power::Voltage as u16)
+        .usage(Power::ConfigVoltage as u16)
+        .unit(0x00F0_D121)
+        .unit_exponent(5)
+        .report_count(2)
+        .feature(cva)
+        .unit(0)
+        .unit_exponent(0)
+        .end_collection()
+        .end_collection()
+};
+
+/// Present Status flags
+///
+/// Below Remaining Capacity Limit is set by [`PowerDevice`] from the remaining capacity and the
+/// remaining capacity limit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "2", bit_numbering = "msb0")]
+pub struct PresentStatus {
+    #[packed_field(bits = "7")]
+    pub charging: bool,
+    #[packed_field(bits = "6")]
+    pub discharging: bool,
+    #[packed_field(bits = "5")]
+    pub ac_present: bool,
+    #[packed_field(bits = "4")]
+    pub battery_present: bool,
+    #[packed_field(bits = "3")]
+    pub below_remaining_capacity_limit: bool,
+    #[packed_field(bits = "2")]
+    pub fully_charged: bool,
+    #[packed_field(bits = "1")]
+    pub need_replacement: bool,
+    #[packed_field(bits = "0")]
+    pub shutdown_imminent: bool,
+    #[packed_field(bits = "15")]
+    pub overload: bool,
+    #[packed_field(bits = "14")]
+    pub over_temperature: bool,
+    #[packed_field(bits = "13")]
+    pub internal_failure: bool,
+}
+
+/// Dynamic UPS state, updated by the application
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub struct PowerStatus {
+    pub present_status: PresentStatus,
+    /// Remaining capacity in percent
+    pub remaining_capacity: u8,
+    /// Estimated run time on battery in seconds
+    pub run_time_to_empty: u16,
+    /// Battery voltage in centivolts
+    pub voltage: u16,
+}
+
+/// Battery manufacture date
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ManufactureDate {
+    /// Year from 1980
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl ManufactureDate {
+    /// Smart Battery Data date encoding - `(year - 1980) * 512 + month * 32 + day`
+    #[must_use]
+    pub fn encode(self) -> u16 {
+        (self.year.saturating_sub(1980) << 9)
+            | (u16::from(self.month & 0x0F) << 5)
+            | u16::from(self.day & 0x1F)
+    }
+}
+
+/// Static UPS information
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PowerDeviceInfo<'a> {
+    pub manufacturer: Option<&'a str>,
+    pub product: Option<&'a str>,
+    pub serial_number: Option<&'a str>,
+    /// Battery chemistry, e.g. `PbAc` or `LION`
+    pub chemistry: &'a str,
+    pub oem_information: Option<&'a str>,
+    pub manufacture_date: Option<ManufactureDate>,
+    pub rechargeable: bool,
+    /// Full charge capacity as a percentage of the design capacity
+    pub full_charge_capacity: u8,
+    /// Nominal battery voltage in centivolts
+    pub config_voltage: u16,
+}
+
+impl<'a> Default for PowerDeviceInfo<'a> {
+    fn default() -> Self {
+        Self {
+            manufacturer: None,
+            product: None,
+            serial_number: None,
+            chemistry: "PbAc",
+            oem_information: None,
+            manufacture_date: None,
+            rechargeable: true,
+            full_charge_capacity: 100,
+            config_voltage: 1200,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "8")]
+struct CapacityReport {
+    #[packed_field]
+    report_id: u8,
+    #[packed_field]
+    capacity_mode: u8,
+    #[packed_field]
+    design_capacity: u8,
+    #[packed_field]
+    full_charge_capacity: u8,
+    #[packed_field]
+    capacity_granularity_1: u8,
+    #[packed_field]
+    capacity_granularity_2: u8,
+    #[packed_field]
+    warning_capacity_limit: u8,
+    #[packed_field]
+    remaining_capacity_limit: u8,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "9", bit_numbering = "msb0")]
+struct InformationReport {
+    #[packed_field(bytes = "0")]
+    report_id: u8,
+    #[packed_field(bits = "15")]
+    rechargeable: bool,
+    #[packed_field(bytes = "2")]
+    manufacturer: u8,
+    #[packed_field(bytes = "3")]
+    product: u8,
+    #[packed_field(bytes = "4")]
+    serial_number: u8,
+    #[packed_field(bytes = "5")]
+    chemistry: u8,
+    #[packed_field(bytes = "6")]
+    oem_information: u8,
+    #[packed_field(bytes = "7..=8")]
+    manufacture_date: u16,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "5")]
+struct DelayReport {
+    #[packed_field]
+    report_id: u8,
+    #[packed_field]
+    delay_before_shutdown: i16,
+    #[packed_field]
+    delay_before_startup: i16,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "3")]
+struct PresentStatusReport {
+    #[packed_field]
+    report_id: u8,
+    #[packed_field(size_bytes = "2")]
+    present_status: PresentStatus,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "4")]
+struct RemainingReport {
+    #[packed_field]
+    report_id: u8,
+    #[packed_field]
+    remaining_capacity: u8,
+    #[packed_field]
+    run_time_to_empty: u16,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "5")]
+struct VoltageReport {
+    #[packed_field]
+    report_id: u8,
+    #[packed_field]
+    voltage: u16,
+    #[packed_field]
+    config_voltage: u16,
+}
+
+fn pack_into<P: PackedStruct>(report: &P, data: &mut [u8]) -> usb_device::Result<usize>
+where
+    P::ByteArray: AsRef<[u8]>,
+{
+    let packed = report.pack().map_err(|_| {
+        error!("Error packing power device report");
+        UsbError::ParseError
+    })?;
+    let packed = packed.as_ref();
+    let Some(data) = data.get_mut(..packed.len()) else {
+        error!("GetReport failed, buffer too short");
+        return Err(UsbError::BufferOverflow);
+    };
+    data.copy_from_slice(packed);
+    Ok(packed.len())
+}
+
+/// Host requested shutdown or startup delay, counted down every second
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Delay {
+    seconds: i16,
+    ms: u16,
+}
+
+impl Default for Delay {
+    fn default() -> Self {
+        Self {
+            seconds: DELAY_NONE,
+            ms: 0,
+        }
+    }
+}
+
+impl Delay {
+    fn set(&mut self, seconds: i16) {
+        self.seconds = seconds.max(DELAY_NONE);
+        self.ms = 0;
+    }
+
+    /// Returns true when the delay expires
+    fn tick(&mut self) -> bool {
+        if self.seconds < 0 {
+            return false;
+        }
+        if self.seconds == 0 {
+            self.seconds = DELAY_NONE;
+            return true;
+        }
+        self.ms += 1;
+        if self.ms >= MS_PER_SECOND {
+            self.ms = 0;
+            self.seconds -= 1;
+        }
+        false
+    }
+
+    fn remaining(self) -> Option<u16> {
+        u16::try_from(self.seconds).ok()
+    }
+}
+
+pub struct PowerDevice<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes8, OutNone, ReportSingle>,
+    info: PowerDeviceInfo<'a>,
+    manufacturer_index: Option<StringIndex>,
+    product_index: Option<StringIndex>,
+    serial_number_index: Option<StringIndex>,
+    chemistry_index: StringIndex,
+    oem_information_index: Option<StringIndex>,
+    status: PowerStatus,
+    warning_capacity_limit: u8,
+    remaining_capacity_limit: u8,
+    shutdown: Delay,
+    startup: Delay,
+    shutdown_requested: bool,
+    startup_requested: bool,
+    present_status_pending: bool,
+    remaining_pending: bool,
+}
+
+impl<'a, B: UsbBus> PowerDevice<'a, B> {
+    /// Update the UPS state
+    ///
+    /// Input reports are sent for the values that changed, returns [`UsbHidError::Duplicate`] if
+    /// nothing changed and [`UsbHidError::WouldBlock`] if the reports will be sent on a later
+    /// [`tick`](DeviceClass::tick).
+    pub fn update(&mut self, status: PowerStatus) -> Result<(), UsbHidError> {
+        let mut status = status;
+        status.remaining_capacity = status.remaining_capacity.min(100);
+        status.present_status.below_remaining_capacity_limit =
+            status.remaining_capacity < self.remaining_capacity_limit;
+
+        if status == self.status {
+            return Err(UsbHidError::Duplicate);
+        }
+        self.present_status_pending |= status.present_status != self.status.present_status;
+        self.remaining_pending |= status.remaining_capacity != self.status.remaining_capacity
+            || status.run_time_to_empty != self.status.run_time_to_empty;
+        self.status = status;
+        self.write_pending()
+    }
+
+    #[must_use]
+    pub fn status(&self) -> PowerStatus {
+        self.status
+    }
+
+    /// Remaining capacity in percent below which the host should shut down
+    #[must_use]
+    pub fn remaining_capacity_limit(&self) -> u8 {
+        self.remaining_capacity_limit
+    }
+
+    /// Remaining capacity in percent below which the host should warn the user
+    #[must_use]
+    pub fn warning_capacity_limit(&self) -> u8 {
+        self.warning_capacity_limit
+    }
+
+    /// Seconds until the output is shut down, if the host requested a shutdown
+    #[must_use]
+    pub fn delay_before_shutdown(&self) -> Option<u16> {
+        self.shutdown.remaining()
+    }
+
+    /// Seconds until the output is switched on, if the host requested a startup
+    #[must_use]
+    pub fn delay_before_startup(&self) -> Option<u16> {
+        self.startup.remaining()
+    }
+
+    /// Returns true, once, when the shutdown delay requested by the host has expired
+    pub fn shutdown_requested(&mut self) -> bool {
+        core::mem::take(&mut self.shutdown_requested)
+    }
+
+    /// Returns true, once, when the startup delay requested by the host has expired
+    pub fn startup_requested(&mut self) -> bool {
+        core::mem::take(&mut self.startup_requested)
+    }
+
+    fn present_status_report(&self) -> PresentStatusReport {
+        PresentStatusReport {
+            report_id: PRESENT_STATUS_REPORT_ID,
+            present_status: self.status.present_status,
+        }
+    }
+
+    fn remaining_report(&self) -> RemainingReport {
+        RemainingReport {
+            report_id: REMAINING_REPORT_ID,
+            remaining_capacity: self.status.remaining_capacity,
+            run_time_to_empty: self.status.run_time_to_empty,
+        }
+    }
+
+    fn write_pending(&mut self) -> Result<(), UsbHidError> {
+        if self.present_status_pending {
+            let data = self.present_status_report().pack().map_err(|_| {
+                error!("Error packing PresentStatusReport");
+                UsbHidError::SerializationError
+            })?;
+            self.interface.write_report(&data)?;
+            self.present_status_pending = false;
+        }
+        if self.remaining_pending {
+            let data = self.remaining_report().pack().map_err(|_| {
+                error!("Error packing RemainingReport");
+                UsbHidError::SerializationError
+            })?;
+            self.interface.write_report(&data)?;
+            self.remaining_pending = false;
+        }
+        Ok(())
+    }
+
+    fn get_feature_report(&self, report_id: u8, data: &mut [u8]) -> usb_device::Result<usize> {
+        match report_id {
+            CAPACITY_REPORT_ID => pack_into(
+                &CapacityReport {
+                    report_id,
+                    capacity_mode: CAPACITY_MODE_PERCENT,
+                    design_capacity: 100,
+                    full_charge_capacity: self.info.full_charge_capacity,
+                    capacity_granularity_1: 1,
+                    capacity_granularity_2: 1,
+                    warning_capacity_limit: self.warning_capacity_limit,
+                    remaining_capacity_limit: self.remaining_capacity_limit,
+                },
+                data,
+            ),
+            INFORMATION_REPORT_ID => pack_into(
+                &InformationReport {
+                    report_id,
+                    rechargeable: self.info.rechargeable,
+                    manufacturer: self.manufacturer_index.map_or(0, u8::from),
+                    product: self.product_index.map_or(0, u8::from),
+                    serial_number: self.serial_number_index.map_or(0, u8::from),
+                    chemistry: self.chemistry_index.into(),
+                    oem_information: self.oem_information_index.map_or(0, u8::from),
+                    manufacture_date: self
+                        .info
+                        .manufacture_date
+                        .map_or(0, ManufactureDate::encode),
+                },
+                data,
+            ),
+            DELAY_REPORT_ID => pack_into(
+                &DelayReport {
+                    report_id,
+                    delay_before_shutdown: self.shutdown.seconds,
+                    delay_before_startup: self.startup.seconds,
+                },
+                data,
+            ),
+            PRESENT_STATUS_REPORT_ID => pack_into(&self.present_status_report(), data),
+            REMAINING_REPORT_ID => pack_into(&self.remaining_report(), data),
+            VOLTAGE_REPORT_ID => pack_into(
+                &VoltageReport {
+                    report_id,
+                    voltage: self.status.voltage,
+                    config_voltage: self.info.config_voltage,
+                },
+                data,
+            ),
+            _ => {
+                warn!("Unsupported GET_REPORT feature report ID {:X}", report_id);
+                Err(UsbError::Unsupported)
+            }
+        }
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for PowerDevice<'a, B> {
+    type I = Interface<'a, B, InBytes8, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {
+        self.present_status_pending = true;
+        self.remaining_pending = true;
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        self.shutdown_requested |= self.shutdown.tick();
+        self.startup_requested |= self.startup.tick();
+        match self.write_pending() {
+            Err(UsbHidError::WouldBlock) => Ok(()),
+            result => result,
+        }
+    }
+
+    fn set_report(
+        &mut self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &[u8],
+    ) -> usb_device::Result<()> {
+        match (report_type, report_id) {
+            (ReportType::Feature, CAPACITY_REPORT_ID) => {
+                let report: CapacityReport = data
+                    .try_into()
+                    .ok()
+                    .and_then(|d: [u8; 8]| CapacityReport::unpack(&d).ok())
+                    .ok_or(UsbError::ParseError)?;
+                self.warning_capacity_limit = report.warning_capacity_limit.min(100);
+                self.remaining_capacity_limit = report.remaining_capacity_limit.min(100);
+            }
+            (ReportType::Feature, DELAY_REPORT_ID) => {
+                let report: DelayReport = data
+                    .try_into()
+                    .ok()
+                    .and_then(|d: [u8; 5]| DelayReport::unpack(&d).ok())
+                    .ok_or(UsbError::ParseError)?;
+                self.shutdown.set(report.delay_before_shutdown);
+                self.startup.set(report.delay_before_startup);
+            }
+            _ => {
+                warn!("Unsupported SET_REPORT report ID {:X}", report_id);
+                return Err(UsbError::Unsupported);
+            }
+        }
+        Ok(())
+    }
+
+    fn get_report(
+        &mut self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &mut [u8],
+    ) -> usb_device::Result<usize> {
+        match (report_type, report_id) {
+            (ReportType::Feature, _) => self.get_feature_report(report_id, data),
+            (ReportType::Input, PRESENT_STATUS_REPORT_ID) => {
+                pack_into(&self.present_status_report(), data)
+            }
+            (ReportType::Input, REMAINING_REPORT_ID) => pack_into(&self.remaining_report(), data),
+            _ => Err(UsbError::Unsupported),
+        }
+    }
+
+    fn get_report_ack(&mut self, _: ReportType, _: u8) -> usb_device::Result<()> {
+        Ok(())
+    }
+
+    fn get_string(&mut self, index: StringIndex, lang_id: u16) -> Option<&'a str> {
+        let strings = [
+            (self.manufacturer_index, self.info.manufacturer),
+            (self.product_index, self.info.product),
+            (self.serial_number_index, self.info.serial_number),
+            (Some(self.chemistry_index), Some(self.info.chemistry)),
+            (self.oem_information_index, self.info.oem_information),
+        ];
+        match strings.iter().find(|(i, _)| *i == Some(index)) {
+            Some((_, s)) => *s,
+            None => self.interface.get_string(index, lang_id),
+        }
+    }
+}
+
+pub struct PowerDeviceConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>,
+    info: PowerDeviceInfo<'a>,
+}
+
+impl<'a> Default for PowerDeviceConfig<'a> {
+    #[must_use]
+    fn default() -> Self {
+        let descriptor: &'static ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> =
+            &POWER_DEVICE_DESCRIPTOR;
+        Self::new(
+            PowerDeviceInfo::default(),
+            unwrap!(unwrap!(InterfaceBuilder::new(descriptor.as_bytes()))
+                .description("UPS")
+                .in_endpoint(100.millis()))
+            .without_out_endpoint()
+            .build(),
+        )
+    }
+}
+
+impl<'a> PowerDeviceConfig<'a> {
+    #[must_use]
+    pub fn new(
+        info: PowerDeviceInfo<'a>,
+        interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>,
+    ) -> Self {
+        Self { interface, info }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for PowerDeviceConfig<'a> {
+    type Allocated = PowerDevice<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+            info: self.info,
+            manufacturer_index: self.info.manufacturer.map(|_| usb_alloc.string()),
+            product_index: self.info.product.map(|_| usb_alloc.string()),
+            serial_number_index: self.info.serial_number.map(|_| usb_alloc.string()),
+            chemistry_index: usb_alloc.string(),
+            oem_information_index: self.info.oem_information.map(|_| usb_alloc.string()),
+            status: PowerStatus::default(),
+            warning_capacity_limit: 20,
+            remaining_capacity_limit: 10,
+            shutdown: Delay::default(),
+            startup: Delay::default(),
+            shutdown_requested: false,
+            startup_requested: false,
+            present_status_pending: true,
+            remaining_pending: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::test_bus::{TestBusState, TestUsbBus};
+    use crate::test_descriptor::{items, report_bits, report_sizes};
+
+    #[test]
+    fn present_status_report() {
+        let report = PresentStatusReport {
+            report_id: PRESENT_STATUS_REPORT_ID,
+            present_status: PresentStatus {
+                charging: true,
+                ac_present: true,
+                shutdown_imminent: true,
+                internal_failure: true,
+                ..Default::default()
+            },
+        };
+        assert_eq!(report.pack().unwrap(), [0x04, 0x85, 0x04]);
+    }
+
+    #[test]
+    fn information_report() {
+        let report = InformationReport {
+            report_id: INFORMATION_REPORT_ID,
+            rechargeable: true,
+            manufacturer: 1,
+            product: 2,
+            serial_number: 3,
+            chemistry: 4,
+            oem_information: 0,
+            manufacture_date: ManufactureDate {
+                year: 2024,
+                month: 6,
+                day: 15,
+            }
+            .encode(),
+        };
+        assert_eq!(
+            report.pack().unwrap(),
+            [0x02, 0x01, 1, 2, 3, 4, 0, 0xCF, 0x58]
+        );
+    }
+
+    #[test]
+    fn delay_countdown() {
+        let mut delay = Delay::default();
+        assert!(!delay.tick());
+        delay.set(1);
+        assert_eq!(delay.remaining(), Some(1));
+        for _ in 0..MS_PER_SECOND {
+            assert!(!delay.tick());
+        }
+        assert_eq!(delay.remaining(), Some(0));
+        assert!(delay.tick());
+        assert_eq!(delay.remaining(), None);
+    }
+
+    #[test]
+    fn descriptor_report_sizes() {
+        let sizes = report_sizes::<7>(POWER_DEVICE_DESCRIPTOR.as_bytes());
+
+        assert_eq!(sizes[0], [0, 0, 0, 0, 16, 24, 0]);
+        assert_eq!(sizes[1], [0; 7]);
+        assert_eq!(
+            sizes[2],
+            [
+                0,
+                report_bits(CapacityReport::packed_bytes_size(None).unwrap()),
+                report_bits(InformationReport::packed_bytes_size(None).unwrap()),
+                report_bits(DelayReport::packed_bytes_size(None).unwrap()),
+                report_bits(PresentStatusReport::packed_bytes_size(None).unwrap()),
+                report_bits(RemainingReport::packed_bytes_size(None).unwrap()),
+                report_bits(VoltageReport::packed_bytes_size(None).unwrap()),
+            ]
+        );
+    }
+
+    #[test]
+    fn descriptor_units() {
+        // Decode the unit of each usage of each main item
+        let (mut page, mut unit) = (0, 0);
+        let mut usages = std::vec::Vec::new();
+        let mut units = std::vec::Vec::new();
+        for (prefix, value) in items(POWER_DEVICE_DESCRIPTOR.as_bytes()) {
+            match prefix {
+                0x04 => page = value,
+                0x64 => unit = value,
+                0x08 => usages.push((page, value)),
+                0x80 | 0x90 | 0xB0 => units.extend(usages.drain(..).map(|u| (u, unit))),
+                0xA0 => usages.clear(),
+                _ => {}
+            }
+        }
+
+        let battery = |usage: BatterySystem| (UsagePage::BatterySystem as u32, usage as u32);
+        let power = |usage: Power| (UsagePage::PowerDevice as u32, usage as u32);
+        let seconds = [
+            battery(BatterySystem::RunTimeToEmpty),
+            power(Power::DelayBeforeShutdown),
+            power(Power::DelayBeforeStartup),
+        ];
+        let volts = [power(Power::Voltage), power(Power::ConfigVoltage)];
+        for (usage, unit) in units.iter().copied() {
+            let expected = if seconds.contains(&usage) {
+                0x1001
+            } else if volts.contains(&usage) {
+                0x00F0_D121
+            } else {
+                0
+            };
+            assert_eq!(unit, expected, "unit of usage {usage:X?}");
+        }
+        assert_eq!(
+            units
+                .iter()
+                .filter(|(usage, _)| *usage == battery(BatterySystem::RemainingCapacity))
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn information_string_indices() {
+        let state = TestBusState::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&state));
+        let mut ups = PowerDeviceConfig::new(
+            PowerDeviceInfo {
+                manufacturer: Some("ACME"),
+                product: Some("UPS 1000"),
+                ..Default::default()
+            },
+            PowerDeviceConfig::default().interface,
+        )
+        .allocate(&usb_alloc);
+        let device_string = usb_alloc.string();
+
+        let mut data = [0; 9];
+        ups.get_report(ReportType::Feature, INFORMATION_REPORT_ID, &mut data)
+            .unwrap();
+        let report = InformationReport::unpack(&data).unwrap();
+
+        // usb-device reserves indices 1 to 3 for its own manufacturer, product and serial number
+        assert!(report.manufacturer > 3);
+        assert!(report.product > report.manufacturer);
+        assert_eq!(report.serial_number, 0);
+        assert!(report.chemistry > report.product);
+        assert_eq!(report.oem_information, 0);
+        assert!(u8::from(device_string) > report.chemistry);
+    }
+
+    #[test]
+    fn unsupported_set_report_rejected() {
+        let state = TestBusState::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&state));
+        let mut ups = PowerDeviceConfig::default().allocate(&usb_alloc);
+
+        assert!(matches!(
+            ups.set_report(
+                ReportType::Feature,
+                INFORMATION_REPORT_ID,
+                &[INFORMATION_REPORT_ID]
+            ),
+            Err(UsbError::Unsupported)
+        ));
+        assert!(matches!(
+            ups.set_report(ReportType::Output, DELAY_REPORT_ID, &[DELAY_REPORT_ID]),
+            Err(UsbError::Unsupported)
+        ));
+        assert!(matches!(
+            ups.set_report(ReportType::Feature, DELAY_REPORT_ID, &[DELAY_REPORT_ID]),
+            Err(UsbError::ParseError)
+        ));
+    }
+}
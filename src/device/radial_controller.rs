@@ -0,0 +1,303 @@
+//!Radial controller, a Surface Dial style rotary knob
+//!
+//! A System Multi-Axis Controller with a button and a dial reporting rotation in tenths of a
+//! degree, optionally with the on-screen position of the controller.
+//!
+//! Rotation is accumulated by [`RadialController::rotate`] and sent on the following
+//! [`tick`](DeviceClass::tick)s, at most 360 degrees per report, so encoder steps are not lost
+//! when the host polls less often than the encoder moves.
+//!
+//! Reference: <https://learn.microsoft.com/en-us/windows-hardware/design/component-guidelines/radial-controller-sample-report-descriptors>
+use crate::usb_class::prelude::*;
+use core::default::Default;
+use fugit::ExtU32;
+use packed_struct::prelude::*;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+
+/// Largest rotation in a single report, in tenths of a degree
+pub const MAX_ROTATION: i16 = 3600;
+/// Largest on-screen position
+pub const MAX_POSITION: u16 = 0x7FFF;
+
+/// Contact width reported by the on-screen radial controller, in thousandths of an inch
+const CONTACT_WIDTH: u16 = 3000;
+/// Size of the report without the on-screen position
+const REPORT_SIZE: usize = 3;
+
+/// Radial controller report descriptor
+#[rustfmt::skip]
+pub const RADIAL_CONTROLLER_REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01,       // Usage Page (Generic Desktop),
+    0x09, 0x0E,       // Usage (System Multi-Axis Controller),
+    0xA1, 0x01,       // Collection (Application),
+    0x05, 0x0D,       //   Usage Page (Digitizers),
+    0x09, 0x21,       //   Usage (Puck),
+    0xA1, 0x00,       //   Collection (Physical),
+    0x05, 0x09,       //     Usage Page (Button),
+    0x09, 0x01,       //     Usage (Button 1),
+    0x15, 0x00,       //     Logical Minimum (0),
+    0x25, 0x01,       //     Logical Maximum (1),
+    0x75, 0x01,       //     Report Size (1),
+    0x95, 0x01,       //     Report Count (1),
+    0x81, 0x02,       //     Input (Data, Variable, Absolute),
+    0x75, 0x07,       //     Report Size (7),
+    0x81, 0x01,       //     Input (Constant),
+    0x05, 0x01,       //     Usage Page (Generic Desktop),
+    0x09, 0x37,       //     Usage (Dial),
+    0x55, 0x0F,       //     Unit Exponent (-1),
+    0x65, 0x14,       //     Unit (Degrees),
+    0x36, 0xF0, 0xF1, //     Physical Minimum (-3600),
+    0x46, 0x10, 0x0E, //     Physical Maximum (3600),
+    0x16, 0xF0, 0xF1, //     Logical Minimum (-3600),
+    0x26, 0x10, 0x0E, //     Logical Maximum (3600),
+    0x75, 0x10,       //     Report Size (16),
+    0x81, 0x06,       //     Input (Data, Variable, Relative),
+    0xC0,             //   End Collection,
+    0xC0,             // End Collection
+];
+
+/// Radial controller with on-screen position report descriptor
+#[rustfmt::skip]
+pub const ON_SCREEN_RADIAL_CONTROLLER_REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01,       // Usage Page (Generic Desktop),
+    0x09, 0x0E,       // Usage (System Multi-Axis Controller),
+    0xA1, 0x01,       // Collection (Application),
+    0x05, 0x0D,       //   Usage Page (Digitizers),
+    0x09, 0x21,       //   Usage (Puck),
+    0xA1, 0x00,       //   Collection (Physical),
+    0x05, 0x09,       //     Usage Page (Button),
+    0x09, 0x01,       //     Usage (Button 1),
+    0x15, 0x00,       //     Logical Minimum (0),
+    0x25, 0x01,       //     Logical Maximum (1),
+    0x75, 0x01,       //     Report Size (1),
+    0x95, 0x01,       //     Report Count (1),
+    0x81, 0x02,       //     Input (Data, Variable, Absolute),
+    0x75, 0x07,       //     Report Size (7),
+    0x81, 0x01,       //     Input (Constant),
+    0x05, 0x01,       //     Usage Page (Generic Desktop),
+    0x09, 0x37,       //     Usage (Dial),
+    0x55, 0x0F,       //     Unit Exponent (-1),
+    0x65, 0x14,       //     Unit (Degrees),
+    0x36, 0xF0, 0xF1, //     Physical Minimum (-3600),
+    0x46, 0x10, 0x0E, //     Physical Maximum (3600),
+    0x16, 0xF0, 0xF1, //     Logical Minimum (-3600),
+    0x26, 0x10, 0x0E, //     Logical Maximum (3600),
+    0x75, 0x10,       //     Report Size (16),
+    0x81, 0x06,       //     Input (Data, Variable, Relative),
+    0x09, 0x30,       //     Usage (X),
+    0x55, 0x0D,       //     Unit Exponent (-3),
+    0x65, 0x13,       //     Unit (Inch),
+    0x35, 0x00,       //     Physical Minimum (0),
+    0x46, 0xC0, 0x5D, //     Physical Maximum (24000),
+    0x15, 0x00,       //     Logical Minimum (0),
+    0x26, 0xFF, 0x7F, //     Logical Maximum (32767),
+    0x81, 0x02,       //     Input (Data, Variable, Absolute),
+    0x09, 0x31,       //     Usage (Y),
+    0x46, 0xB0, 0x36, //     Physical Maximum (14000),
+    0x81, 0x02,       //     Input (Data, Variable, Absolute),
+    0x05, 0x0D,       //     Usage Page (Digitizers),
+    0x09, 0x48,       //     Usage (Width),
+    0x36, 0xB8, 0x0B, //     Physical Minimum (3000),
+    0x46, 0xB8, 0x0B, //     Physical Maximum (3000),
+    0x16, 0xB8, 0x0B, //     Logical Minimum (3000),
+    0x26, 0xB8, 0x0B, //     Logical Maximum (3000),
+    0x81, 0x03,       //     Input (Constant, Variable, Absolute),
+    0xC0,             //   End Collection,
+    0xC0,             // End Collection
+];
+
+/// Radial controller report
+///
+/// Only the first 3 bytes, without the on-screen position, are sent by a radial controller that
+/// is not on-screen.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "9", bit_numbering = "msb0")]
+pub struct RadialControllerReport {
+    #[packed_field(bits = "7")]
+    pub button: bool,
+    /// Rotation in tenths of a degree, clockwise positive
+    #[packed_field(bytes = "1..=2")]
+    pub dial: i16,
+    /// On-screen position from 0 to [`MAX_POSITION`]
+    #[packed_field(bytes = "3..=4")]
+    pub x: u16,
+    /// On-screen position from 0 to [`MAX_POSITION`]
+    #[packed_field(bytes = "5..=6")]
+    pub y: u16,
+    #[packed_field(bytes = "7..=8")]
+    width: u16,
+}
+
+pub struct RadialController<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes16, OutNone, ReportSingle>,
+    on_screen: bool,
+    /// Rotation not yet sent, in tenths of a degree
+    rotation: i32,
+    /// Button and position, dial unused
+    state: RadialControllerReport,
+    /// Button and position last sent
+    sent: RadialControllerReport,
+}
+
+impl<'a, B: UsbBus> RadialController<'a, B> {
+    /// Add `tenths` of a degree of rotation, clockwise positive
+    pub fn rotate(&mut self, tenths: i32) {
+        self.rotation = self.rotation.saturating_add(tenths);
+    }
+
+    pub fn set_button(&mut self, pressed: bool) {
+        self.state.button = pressed;
+    }
+
+    /// Set the on-screen position, ignored if the controller is not on-screen
+    pub fn set_position(&mut self, x: u16, y: u16) {
+        self.state.x = x.min(MAX_POSITION);
+        self.state.y = y.min(MAX_POSITION);
+    }
+
+    /// Rotation not yet sent to the host, in tenths of a degree
+    #[must_use]
+    pub fn pending_rotation(&self) -> i32 {
+        self.rotation
+    }
+
+    /// Send a report if there is rotation or the button or position changed
+    ///
+    /// Called by [`tick`](DeviceClass::tick), returns [`UsbHidError::Duplicate`] if there is
+    /// nothing to send.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn write_pending(&mut self) -> Result<(), UsbHidError> {
+        if self.rotation == 0 && self.state == self.sent {
+            return Err(UsbHidError::Duplicate);
+        }
+        let dial = self
+            .rotation
+            .clamp(i32::from(-MAX_ROTATION), i32::from(MAX_ROTATION)) as i16;
+        let report = RadialControllerReport {
+            dial,
+            width: CONTACT_WIDTH,
+            ..self.state
+        };
+        let data = report.pack().map_err(|_| {
+            error!("Error packing RadialControllerReport");
+            UsbHidError::SerializationError
+        })?;
+        let size = if self.on_screen {
+            data.len()
+        } else {
+            REPORT_SIZE
+        };
+        self.interface.write_report(&data[..size])?;
+        self.rotation -= i32::from(dial);
+        self.sent = self.state;
+        Ok(())
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for RadialController<'a, B> {
+    type I = Interface<'a, B, InBytes16, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {
+        self.rotation = 0;
+        self.sent = RadialControllerReport::default();
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        match self.write_pending() {
+            Err(UsbHidError::WouldBlock | UsbHidError::Duplicate) => Ok(()),
+            result => result,
+        }
+    }
+}
+
+pub struct RadialControllerConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes16, OutNone, ReportSingle>,
+    on_screen: bool,
+}
+
+impl<'a> RadialControllerConfig<'a> {
+    /// `on_screen` must match the report descriptor of `interface`, see
+    /// [`ON_SCREEN_RADIAL_CONTROLLER_REPORT_DESCRIPTOR`]
+    #[must_use]
+    pub fn new(
+        interface: InterfaceConfig<'a, InBytes16, OutNone, ReportSingle>,
+        on_screen: bool,
+    ) -> Self {
+        Self {
+            interface,
+            on_screen,
+        }
+    }
+
+    /// Radial controller reporting its on-screen position
+    #[must_use]
+    pub fn on_screen() -> Self {
+        Self::new(
+            unwrap!(unwrap!(InterfaceBuilder::new(
+                ON_SCREEN_RADIAL_CONTROLLER_REPORT_DESCRIPTOR
+            ))
+            .description("Radial Controller")
+            .in_endpoint(10.millis()))
+            .without_out_endpoint()
+            .build(),
+            true,
+        )
+    }
+}
+
+impl<'a> Default for RadialControllerConfig<'a> {
+    #[must_use]
+    fn default() -> Self {
+        Self::new(
+            unwrap!(
+                unwrap!(InterfaceBuilder::new(RADIAL_CONTROLLER_REPORT_DESCRIPTOR))
+                    .description("Radial Controller")
+                    .in_endpoint(10.millis())
+            )
+            .without_out_endpoint()
+            .build(),
+            false,
+        )
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for RadialControllerConfig<'a> {
+    type Allocated = RadialController<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        RadialController {
+            interface: Interface::new(usb_alloc, self.interface),
+            on_screen: self.on_screen,
+            rotation: 0,
+            state: RadialControllerReport::default(),
+            sent: RadialControllerReport::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn report() {
+        let report = RadialControllerReport {
+            button: true,
+            dial: -10,
+            x: 0x1234,
+            y: 0x7FFF,
+            width: CONTACT_WIDTH,
+        };
+        assert_eq!(
+            report.pack().unwrap(),
+            [0x01, 0xF6, 0xFF, 0x34, 0x12, 0xFF, 0x7F, 0xB8, 0x0B]
+        );
+    }
+}
@@ -0,0 +1,362 @@
+//!Raw HID with a vendor defined usage page
+//!
+//! A bidirectional device exchanging fixed size reports of opaque bytes, as used by desktop
+//! configuration tools talking to devices through `hidraw` or `WebHID`.
+//!
+//! ```
+//! use usbd_human_interface_device::device::raw::RawHidConfig;
+//!
+//! // 32 byte reports on usage page 0xFF60, usage 0x61, without report IDs
+//! let config = RawHidConfig::with_usage::<0xFF60, 0x61, 32, 0>();
+//! ```
+use crate::descriptor::builder::{Collection, ItemFlags, ReportDescriptorBuilder};
+use crate::usb_class::prelude::*;
+use core::default::Default;
+use fugit::ExtU32;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+use usb_device::UsbError;
+
+/// Largest report, including the report ID if used
+pub const MAX_REPORT_SIZE: usize = 64;
+
+/// Capacity reserved for generated raw HID report descriptors
+pub const DESCRIPTOR_CAPACITY: usize = 48;
+
+/// Usage of the input report data
+const DATA_IN_USAGE: u16 = 0x01;
+/// Usage of the output report data
+const DATA_OUT_USAGE: u16 = 0x02;
+
+/// Generate a raw HID report descriptor
+///
+/// Declares an input and an output report of `report_size` bytes in an application collection
+/// with the vendor defined `usage_page` and `usage`. The reports are prefixed with `report_id`
+/// when it is `Some`.
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub const fn raw_hid_report_descriptor(
+    usage_page: u16,
+    usage: u16,
+    report_size: usize,
+    report_id: Option<u8>,
+) -> ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> {
+    core::assert!(
+        usage_page >= 0xFF00,
+        "Raw HID usage page must be vendor defined"
+    );
+    core::assert!(report_size > 0, "Raw HID report size must not be zero");
+
+    let mut descriptor = ReportDescriptorBuilder::new()
+        .usage_page(usage_page)
+        .usage(usage)
+        .collection(Collection::Application);
+
+    if let Some(id) = report_id {
+        core::assert!(id != 0, "Report ID 0 is reserved");
+        core::assert!(
+            report_size < MAX_REPORT_SIZE,
+            "Raw HID report too large for report ID"
+        );
+        descriptor = descriptor.report_id(id);
+    } else {
+        core::assert!(report_size <= MAX_REPORT_SIZE, "Raw HID report too large");
+    }
+
+    descriptor
+        .logical_minimum(0)
+        .logical_maximum(0xFF)
+        .report_size(8)
+        .report_count(report_size as u32)
+        .usage(DATA_IN_USAGE)
+        .input(ItemFlags::DATA_VARIABLE_ABSOLUTE)
+        .usage(DATA_OUT_USAGE)
+        .output(ItemFlags::DATA_VARIABLE_ABSOLUTE)
+        .end_collection()
+}
+
+/// Raw HID report descriptors, `REPORT_ID` 0 meaning no report ID
+struct RawHidDescriptor<
+    const USAGE_PAGE: u16,
+    const USAGE: u16,
+    const SIZE: usize,
+    const REPORT_ID: u8,
+>;
+
+impl<const USAGE_PAGE: u16, const USAGE: u16, const SIZE: usize, const REPORT_ID: u8>
+    RawHidDescriptor<USAGE_PAGE, USAGE, SIZE, REPORT_ID>
+{
+    const DESCRIPTOR: ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> = raw_hid_report_descriptor(
+        USAGE_PAGE,
+        USAGE,
+        SIZE,
+        if REPORT_ID == 0 {
+            None
+        } else {
+            Some(REPORT_ID)
+        },
+    );
+}
+
+pub struct RawHid<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes64, OutBytes64, ReportSingle>,
+    report_size: usize,
+    report_id: Option<u8>,
+}
+
+impl<'a, B: UsbBus> RawHid<'a, B> {
+    /// Write an input report
+    ///
+    /// `data` shorter than the report size is padded with zeros, longer `data` is rejected. The
+    /// report ID is added if configured.
+    pub fn write_report(&mut self, data: &[u8]) -> Result<(), UsbHidError> {
+        if data.len() > self.report_size {
+            error!("Error writing raw HID report, data too long");
+            return Err(UsbHidError::SerializationError);
+        }
+
+        let mut report = [0; MAX_REPORT_SIZE];
+        let offset = usize::from(self.report_id.is_some());
+        if let Some(id) = self.report_id {
+            report[0] = id;
+        }
+        report[offset..offset + data.len()].copy_from_slice(data);
+
+        self.interface
+            .write_report(&report[..offset + self.report_size])
+            .map(|_| ())
+            .map_err(UsbHidError::from)
+    }
+
+    /// Read an output report into `data`, returning the number of bytes read
+    ///
+    /// The report ID, if configured, is removed. Reports with a different report ID are rejected
+    /// with [`UsbError::ParseError`].
+    pub fn read_report(&mut self, data: &mut [u8]) -> usb_device::Result<usize> {
+        let mut report = [0; MAX_REPORT_SIZE];
+        let len = self.interface.read_report(&mut report)?;
+
+        let payload = match self.report_id {
+            None => &report[..len],
+            Some(id) if len > 0 && report[0] == id => &report[1..len],
+            Some(_) => {
+                warn!("Unexpected raw HID output report ID");
+                return Err(UsbError::ParseError);
+            }
+        };
+
+        if data.len() < payload.len() {
+            return Err(UsbError::BufferOverflow);
+        }
+        data[..payload.len()].copy_from_slice(payload);
+        Ok(payload.len())
+    }
+
+    /// Report size in bytes, excluding the report ID
+    #[must_use]
+    pub fn report_size(&self) -> usize {
+        self.report_size
+    }
+
+    #[must_use]
+    pub fn report_id(&self) -> Option<u8> {
+        self.report_id
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for RawHid<'a, B> {
+    type I = Interface<'a, B, InBytes64, OutBytes64, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {}
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        Ok(())
+    }
+}
+
+pub struct RawHidConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes64, OutBytes64, ReportSingle>,
+    report_size: usize,
+    report_id: Option<u8>,
+}
+
+impl<'a> Default for RawHidConfig<'a> {
+    #[must_use]
+    fn default() -> Self {
+        Self::with_usage::<0xFF00, 0x01, 64, 0>()
+    }
+}
+
+impl<'a> RawHidConfig<'a> {
+    /// `interface` must use a report descriptor from [`raw_hid_report_descriptor`] for
+    /// `report_size` and `report_id`
+    ///
+    /// Panics if `report_size` is zero or the report, including the report ID, is larger than
+    /// [`MAX_REPORT_SIZE`].
+    #[must_use]
+    pub fn new(
+        report_size: usize,
+        report_id: Option<u8>,
+        interface: InterfaceConfig<'a, InBytes64, OutBytes64, ReportSingle>,
+    ) -> Self {
+        assert!(report_size > 0, "Raw HID report size must not be zero");
+        assert!(
+            report_size + usize::from(report_id.is_some()) <= MAX_REPORT_SIZE,
+            "Raw HID report too large"
+        );
+        Self {
+            interface,
+            report_size,
+            report_id,
+        }
+    }
+
+    /// Default raw HID interface with reports of `SIZE` bytes on the vendor defined `USAGE_PAGE`
+    /// and `USAGE`
+    ///
+    /// A `REPORT_ID` of 0 omits the report ID. Invalid parameters fail to compile.
+    #[must_use]
+    pub fn with_usage<
+        const USAGE_PAGE: u16,
+        const USAGE: u16,
+        const SIZE: usize,
+        const REPORT_ID: u8,
+    >() -> Self {
+        let descriptor: &'static ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> =
+            &RawHidDescriptor::<USAGE_PAGE, USAGE, SIZE, REPORT_ID>::DESCRIPTOR;
+        Self::new(
+            SIZE,
+            (REPORT_ID != 0).then_some(REPORT_ID),
+            unwrap!(
+                unwrap!(unwrap!(InterfaceBuilder::new(descriptor.as_bytes()))
+                    .description("Raw HID")
+                    .in_endpoint(1.millis()))
+                .with_out_endpoint(1.millis())
+            )
+            .build(),
+        )
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for RawHidConfig<'a> {
+    type Allocated = RawHid<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+            report_size: self.report_size,
+            report_id: self.report_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::test_bus::{build_device, TestBusState, TestUsbBus};
+
+    #[test]
+    fn descriptor_without_report_id() {
+        let descriptor = raw_hid_report_descriptor(0xFF60, 0x61, 32, None);
+
+        assert_eq!(
+            descriptor.as_bytes(),
+            [
+                0x06, 0x60, 0xFF, // Usage Page (0xFF60)
+                0x09, 0x61, // Usage (0x61)
+                0xA1, 0x01, // Collection (Application)
+                0x15, 0x00, // Logical Minimum (0)
+                0x26, 0xFF, 0x00, // Logical Maximum (255)
+                0x75, 0x08, // Report Size (8)
+                0x95, 0x20, // Report Count (32)
+                0x09, 0x01, // Usage (Data In)
+                0x81, 0x02, // Input (Data, Variable, Absolute)
+                0x09, 0x02, // Usage (Data Out)
+                0x91, 0x02, // Output (Data, Variable, Absolute)
+                0xC0, // End Collection
+            ]
+        );
+    }
+
+    #[test]
+    fn descriptor_with_report_id() {
+        let descriptor = raw_hid_report_descriptor(0xFFAB, 0x200, 63, Some(3));
+
+        assert_eq!(
+            descriptor.as_bytes()[..9],
+            [0x06, 0xAB, 0xFF, 0x0A, 0x00, 0x02, 0xA1, 0x01, 0x85]
+        );
+        assert_eq!(descriptor.as_bytes()[9], 3);
+        assert_eq!(descriptor.as_bytes()[17..19], [0x95, 63]);
+    }
+
+    #[test]
+    fn write_report_with_report_id() {
+        let state = TestBusState::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&state));
+        let mut raw = RawHidConfig::with_usage::<0xFF60, 0x61, 8, 2>().allocate(&usb_alloc);
+        let _usb_dev = build_device(&usb_alloc);
+
+        raw.write_report(&[1, 2, 3]).unwrap();
+        assert_eq!(state.take_written(), [[2, 1, 2, 3, 0, 0, 0, 0, 0]]);
+
+        assert!(matches!(
+            raw.write_report(&[0; 9]),
+            Err(UsbHidError::SerializationError)
+        ));
+        assert!(state.take_written().is_empty());
+    }
+
+    #[test]
+    fn read_report_removes_report_id() {
+        let state = TestBusState::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&state));
+        let mut raw = RawHidConfig::with_usage::<0xFF60, 0x61, 8, 2>().allocate(&usb_alloc);
+        let _usb_dev = build_device(&usb_alloc);
+        let mut data = [0; 8];
+
+        state.host_write(&[2, 0xAA, 0xBB]);
+        assert!(matches!(raw.read_report(&mut data), Ok(2)));
+        assert_eq!(data[..2], [0xAA, 0xBB]);
+
+        state.host_write(&[3, 0xAA]);
+        assert!(matches!(
+            raw.read_report(&mut data),
+            Err(UsbError::ParseError)
+        ));
+
+        assert!(matches!(
+            raw.read_report(&mut data),
+            Err(UsbError::WouldBlock)
+        ));
+    }
+
+    #[test]
+    fn report_without_report_id() {
+        let state = TestBusState::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&state));
+        let mut raw = RawHidConfig::with_usage::<0xFF60, 0x61, 64, 0>().allocate(&usb_alloc);
+        let _usb_dev = build_device(&usb_alloc);
+
+        raw.write_report(&[0xFF; 64]).unwrap();
+        assert_eq!(state.take_written(), [[0xFF; 64]]);
+
+        state.host_write(&[7; 64]);
+        let mut data = [0; 64];
+        assert!(matches!(raw.read_report(&mut data), Ok(64)));
+        assert_eq!(data, [7; 64]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Raw HID report too large")]
+    fn report_size_with_report_id_too_large() {
+        let interface = RawHidConfig::default().interface;
+        let _ = RawHidConfig::new(MAX_REPORT_SIZE, Some(1), interface);
+    }
+}
@@ -0,0 +1,801 @@
+//!HID sensors - accelerometer, ambient light and temperature
+//!
+//! See [HID Sensor Usages](<https://www.usb.org/sites/default/files/hutrr39b_0.pdf>).
+//!
+//! Each [`SensorDevice`] is a single sensor application collection with the properties and data
+//! fields expected by the Windows sensor class driver and the Linux `hid-sensor-hub`. Combine
+//! several sensors by adding a device for each one.
+//!
+//! The host selects the report interval, change sensitivity, reporting state and power state
+//! through a feature report. The application sets the sensor data with
+//! [`SensorDevice::set_data`] and [`tick`](DeviceClass::tick) sends a data event every report
+//! interval, or in the threshold reporting states only once the data has changed by at least the
+//! change sensitivity.
+//!
+//! Additional sensor types can be added by implementing [`SensorData`] with a report descriptor
+//! starting with [`sensor_collection`].
+//!
+//! ```
+//! use usbd_human_interface_device::device::sensor::{Accelerometer3dConfig, ConnectionType};
+//! use fugit::ExtU32;
+//!
+//! let config = Accelerometer3dConfig::default()
+//!     .connection_type(ConnectionType::Attached)
+//!     .report_interval(50.millis());
+//! ```
+use crate::descriptor::builder::{Collection, ItemFlags, ReportDescriptorBuilder};
+use crate::descriptor::ReportType;
+use crate::page::{Sensors, UsagePage};
+use crate::usb_class::prelude::*;
+use core::default::Default;
+use core::marker::PhantomData;
+use fugit::{ExtU32, MillisDurationU32};
+use packed_struct::prelude::*;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+use usb_device::UsbError;
+
+/// Report ID of the sensor feature and input reports
+const REPORT_ID: u8 = 0x01;
+/// Bytes before the data fields of the input report - report ID, sensor state and sensor event
+const INPUT_HEADER_SIZE: usize = 3;
+/// Largest input report
+const MAX_INPUT_REPORT_SIZE: usize = 16;
+
+/// Capacity reserved for sensor report descriptors
+pub const DESCRIPTOR_CAPACITY: usize = 288;
+
+/// Named array of selectors - an `item_type` main item with a logical collection of the `count`
+/// selector usages starting at `first`
+#[allow(clippy::cast_possible_truncation)]
+const fn selector<const N: usize>(
+    builder: ReportDescriptorBuilder<N>,
+    usage: Sensors,
+    first: Sensors,
+    count: u16,
+    item_type: ReportType,
+) -> ReportDescriptorBuilder<N> {
+    let mut builder = builder
+        .usage(usage as u16)
+        .logical_minimum(0)
+        .logical_maximum(count as i32 - 1)
+        .report_size(8)
+        .report_count(1)
+        .collection(Collection::Logical);
+    let mut i = 0;
+    while i < count {
+        builder = builder.usage(first as u16 + i);
+        i += 1;
+    }
+    let builder = match item_type {
+        ReportType::Input => builder.input(ItemFlags::DATA_ARRAY_ABSOLUTE),
+        ReportType::Output => builder.output(ItemFlags::DATA_ARRAY_ABSOLUTE),
+        ReportType::Feature => builder.feature(ItemFlags::DATA_ARRAY_ABSOLUTE),
+    };
+    builder.end_collection()
+}
+
+/// Start of a sensor report descriptor
+///
+/// Opens an application collection for `sensor` and adds the [`FeatureReport`] properties and the
+/// sensor state and event of the input report. The change sensitivity applies to `data_field`, in
+/// its units with `unit_exponent`. The data fields of the input report and the End Collection
+/// follow.
+#[must_use]
+pub const fn sensor_collection<const N: usize>(
+    sensor: Sensors,
+    data_field: Sensors,
+    unit_exponent: i8,
+) -> ReportDescriptorBuilder<N> {
+    let builder = ReportDescriptorBuilder::new()
+        .usage_page(UsagePage::Sensors as u16)
+        .usage(sensor as u16)
+        .collection(Collection::Application)
+        .report_id(REPORT_ID)
+        .unit(0)
+        .unit_exponent(0);
+    let builder = selector(
+        builder,
+        Sensors::PropertySensorConnectionType,
+        Sensors::ConnectionTypePcIntegrated,
+        3,
+        ReportType::Feature,
+    );
+    let builder = selector(
+        builder,
+        Sensors::PropertyReportingState,
+        Sensors::ReportingStateReportNoEvents,
+        6,
+        ReportType::Feature,
+    );
+    let builder = selector(
+        builder,
+        Sensors::PropertyPowerState,
+        Sensors::PowerStateUndefined,
+        6,
+        ReportType::Feature,
+    );
+    let builder = selector(
+        builder,
+        Sensors::EventSensorState,
+        Sensors::SensorStateUndefined,
+        7,
+        ReportType::Feature,
+    )
+    // Report interval in milliseconds
+    .usage(Sensors::PropertyReportInterval as u16)
+    .logical_minimum(0)
+    .logical_maximum(i32::MAX)
+    .report_size(32)
+    .report_count(1)
+    .feature(ItemFlags::DATA_VARIABLE_ABSOLUTE)
+    .usage(data_field as u16 | Sensors::ChangeSensitivityAbsolute as u16)
+    .logical_maximum(0xFFFF)
+    .report_size(16)
+    .unit_exponent(unit_exponent)
+    .feature(ItemFlags::DATA_VARIABLE_ABSOLUTE)
+    .unit_exponent(0);
+    let builder = selector(
+        builder,
+        Sensors::EventSensorState,
+        Sensors::SensorStateUndefined,
+        7,
+        ReportType::Input,
+    );
+    selector(
+        builder,
+        Sensors::EventSensorEvent,
+        Sensors::SensorEventUnknown,
+        6,
+        ReportType::Input,
+    )
+}
+
+/// Accelerometer 3D report descriptor
+pub const ACCELEROMETER_3D_DESCRIPTOR: ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> =
+    sensor_collection(
+        Sensors::MotionAccelerometer3D,
+        Sensors::DataFieldAcceleration,
+        -2,
+    )
+    .usage(Sensors::DataFieldAccelerationAxisX as u16)
+    .usage(Sensors::DataFieldAccelerationAxisY as u16)
+    .usage(Sensors::DataFieldAccelerationAxisZ as u16)
+    .logical_minimum(i16::MIN as i32)
+    .logical_maximum(i16::MAX as i32)
+    .report_size(16)
+    .report_count(3)
+    .unit_exponent(-2)
+    .input(ItemFlags::DATA_VARIABLE_ABSOLUTE)
+    .end_collection();
+
+/// Ambient light report descriptor
+pub const AMBIENT_LIGHT_DESCRIPTOR: ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> =
+    sensor_collection(
+        Sensors::LightAmbientLight,
+        Sensors::DataFieldIlluminance,
+        -2,
+    )
+    .usage(Sensors::DataFieldIlluminance as u16)
+    .logical_minimum(0)
+    .logical_maximum(i32::MAX)
+    .report_size(32)
+    .report_count(1)
+    .unit_exponent(-2)
+    .input(ItemFlags::DATA_VARIABLE_ABSOLUTE)
+    .end_collection();
+
+/// Temperature report descriptor
+pub const TEMPERATURE_DESCRIPTOR: ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> = sensor_collection(
+    Sensors::EnvironmentalTemperature,
+    Sensors::DataFieldTemperature,
+    -2,
+)
+.usage(Sensors::DataFieldTemperature as u16)
+.logical_minimum(i16::MIN as i32)
+.logical_maximum(i16::MAX as i32)
+.report_size(16)
+.report_count(1)
+.unit_exponent(-2)
+.input(ItemFlags::DATA_VARIABLE_ABSOLUTE)
+.end_collection();
+
+/// Sensor connection type, in the order of the selectors in the report descriptor
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Default, PrimitiveEnum)]
+#[repr(u8)]
+pub enum ConnectionType {
+    #[default]
+    Integrated = 0,
+    Attached = 1,
+    External = 2,
+}
+
+/// Sensor reporting state, in the order of the selectors in the report descriptor
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Default, PrimitiveEnum)]
+#[repr(u8)]
+pub enum ReportingState {
+    NoEvents = 0,
+    #[default]
+    AllEvents = 1,
+    ThresholdEvents = 2,
+    WakeOnNoEvents = 3,
+    WakeOnAllEvents = 4,
+    WakeOnThresholdEvents = 5,
+}
+
+/// Sensor power state, in the order of the selectors in the report descriptor
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Default, PrimitiveEnum)]
+#[repr(u8)]
+pub enum PowerState {
+    Undefined = 0,
+    #[default]
+    D0FullPower = 1,
+    D1LowPower = 2,
+    D2StandbyWithWake = 3,
+    D3SleepWithWake = 4,
+    D4PowerOff = 5,
+}
+
+/// Sensor state, in the order of the selectors in the report descriptor
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Default, PrimitiveEnum)]
+#[repr(u8)]
+pub enum SensorState {
+    Undefined = 0,
+    Ready = 1,
+    NotAvailable = 2,
+    #[default]
+    NoData = 3,
+    Initializing = 4,
+    AccessDenied = 5,
+    Error = 6,
+}
+
+/// Sensor event, in the order of the selectors in the report descriptor
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Default, PrimitiveEnum)]
+#[repr(u8)]
+pub enum SensorEvent {
+    #[default]
+    Unknown = 0,
+    StateChanged = 1,
+    PropertyChanged = 2,
+    DataUpdated = 3,
+    PollResponse = 4,
+    ChangeSensitivity = 5,
+}
+
+/// Sensor properties feature report
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "11")]
+pub struct FeatureReport {
+    #[packed_field]
+    pub report_id: u8,
+    #[packed_field(size_bytes = "1", ty = "enum")]
+    pub connection_type: ConnectionType,
+    #[packed_field(size_bytes = "1", ty = "enum")]
+    pub reporting_state: ReportingState,
+    #[packed_field(size_bytes = "1", ty = "enum")]
+    pub power_state: PowerState,
+    #[packed_field(size_bytes = "1", ty = "enum")]
+    pub sensor_state: SensorState,
+    /// Report interval in milliseconds, 0 for the default interval
+    #[packed_field]
+    pub report_interval: u32,
+    /// Change sensitivity in the units of the data fields
+    #[packed_field]
+    pub change_sensitivity: u16,
+}
+
+/// Data fields of a sensor input report
+pub trait SensorData: PackedStructSlice + Copy + Default + PartialEq {
+    /// Interface description
+    const DESCRIPTION: &'static str;
+    /// Change sensitivity used until the host sets one
+    const DEFAULT_SENSITIVITY: u16;
+
+    /// Report descriptor, starting with [`sensor_collection`]
+    fn report_descriptor() -> &'static [u8];
+
+    /// True if any data field differs from `previous` by at least `sensitivity`
+    fn exceeds_sensitivity(&self, previous: &Self, sensitivity: u16) -> bool;
+}
+
+fn exceeds(value: i32, previous: i32, sensitivity: u16) -> bool {
+    let change = value.abs_diff(previous);
+    change > 0 && change >= u32::from(sensitivity)
+}
+
+/// Accelerometer 3D data in hundredths of G
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "6")]
+pub struct Acceleration3d {
+    #[packed_field]
+    pub x: i16,
+    #[packed_field]
+    pub y: i16,
+    #[packed_field]
+    pub z: i16,
+}
+
+impl SensorData for Acceleration3d {
+    const DESCRIPTION: &'static str = "Accelerometer";
+    const DEFAULT_SENSITIVITY: u16 = 2;
+
+    fn report_descriptor() -> &'static [u8] {
+        let descriptor: &'static ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> =
+            &ACCELEROMETER_3D_DESCRIPTOR;
+        descriptor.as_bytes()
+    }
+
+    fn exceeds_sensitivity(&self, previous: &Self, sensitivity: u16) -> bool {
+        exceeds(self.x.into(), previous.x.into(), sensitivity)
+            || exceeds(self.y.into(), previous.y.into(), sensitivity)
+            || exceeds(self.z.into(), previous.z.into(), sensitivity)
+    }
+}
+
+/// Ambient light illuminance in hundredths of lux
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "4")]
+pub struct Illuminance {
+    #[packed_field]
+    pub illuminance: u32,
+}
+
+impl SensorData for Illuminance {
+    const DESCRIPTION: &'static str = "Ambient Light";
+    const DEFAULT_SENSITIVITY: u16 = 100;
+
+    fn report_descriptor() -> &'static [u8] {
+        let descriptor: &'static ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> =
+            &AMBIENT_LIGHT_DESCRIPTOR;
+        descriptor.as_bytes()
+    }
+
+    fn exceeds_sensitivity(&self, previous: &Self, sensitivity: u16) -> bool {
+        let change = self.illuminance.abs_diff(previous.illuminance);
+        change > 0 && change >= u32::from(sensitivity)
+    }
+}
+
+/// Temperature in hundredths of a degree Celsius
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "2")]
+pub struct Temperature {
+    #[packed_field]
+    pub temperature: i16,
+}
+
+impl SensorData for Temperature {
+    const DESCRIPTION: &'static str = "Temperature";
+    const DEFAULT_SENSITIVITY: u16 = 10;
+
+    fn report_descriptor() -> &'static [u8] {
+        let descriptor: &'static ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> =
+            &TEMPERATURE_DESCRIPTOR;
+        descriptor.as_bytes()
+    }
+
+    fn exceeds_sensitivity(&self, previous: &Self, sensitivity: u16) -> bool {
+        exceeds(
+            self.temperature.into(),
+            previous.temperature.into(),
+            sensitivity,
+        )
+    }
+}
+
+/// Reporting state, power state, interval and sensitivity, independent of the sensor data
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Properties {
+    connection_type: ConnectionType,
+    reporting_state: ReportingState,
+    power_state: PowerState,
+    sensor_state: SensorState,
+    default_report_interval: u32,
+    report_interval: u32,
+    change_sensitivity: u16,
+}
+
+impl Properties {
+    fn feature_report(&self) -> FeatureReport {
+        FeatureReport {
+            report_id: REPORT_ID,
+            connection_type: self.connection_type,
+            reporting_state: self.reporting_state,
+            power_state: self.power_state,
+            sensor_state: self.sensor_state,
+            report_interval: self.report_interval,
+            change_sensitivity: self.change_sensitivity,
+        }
+    }
+
+    /// Apply the host writable properties of `report`
+    fn set(&mut self, report: &FeatureReport) {
+        self.reporting_state = report.reporting_state;
+        self.power_state = report.power_state;
+        self.report_interval = report.report_interval;
+        self.change_sensitivity = report.change_sensitivity;
+    }
+
+    /// Report interval in milliseconds, the default interval if the host has not selected one
+    fn interval(&self) -> u32 {
+        if self.report_interval == 0 {
+            self.default_report_interval
+        } else {
+            self.report_interval
+        }
+    }
+
+    fn powered(&self) -> bool {
+        matches!(
+            self.power_state,
+            PowerState::Undefined | PowerState::D0FullPower | PowerState::D1LowPower
+        )
+    }
+
+    /// The data event to send, if any, given whether the data changed by the change sensitivity
+    fn data_event(&self, threshold_exceeded: bool) -> Option<SensorEvent> {
+        if !self.powered() || self.sensor_state != SensorState::Ready {
+            return None;
+        }
+        match self.reporting_state {
+            ReportingState::AllEvents | ReportingState::WakeOnAllEvents => {
+                Some(SensorEvent::DataUpdated)
+            }
+            ReportingState::ThresholdEvents | ReportingState::WakeOnThresholdEvents
+                if threshold_exceeded =>
+            {
+                Some(SensorEvent::ChangeSensitivity)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A single HID sensor, reporting `S`
+pub struct SensorDevice<'a, B: UsbBus, S> {
+    interface: Interface<'a, B, InBytes16, OutNone, ReportSingle>,
+    properties: Properties,
+    data: S,
+    /// Data of the last input report, `None` if no data has been sent
+    sent: Option<S>,
+    state_changed: bool,
+    elapsed_ms: u32,
+}
+
+/// Accelerometer 3D sensor
+pub type Accelerometer3d<'a, B> = SensorDevice<'a, B, Acceleration3d>;
+/// Ambient light sensor
+pub type AmbientLight<'a, B> = SensorDevice<'a, B, Illuminance>;
+/// Temperature sensor
+pub type TemperatureSensor<'a, B> = SensorDevice<'a, B, Temperature>;
+
+impl<'a, B: UsbBus, S: SensorData> SensorDevice<'a, B, S> {
+    #[must_use]
+    pub fn data(&self) -> S {
+        self.data
+    }
+
+    /// Set the sensor data, sent on the following [`tick`](DeviceClass::tick)s
+    ///
+    /// The sensor state becomes [`SensorState::Ready`] with the first data.
+    pub fn set_data(&mut self, data: S) {
+        self.data = data;
+        if self.properties.sensor_state == SensorState::NoData {
+            self.set_sensor_state(SensorState::Ready);
+        }
+    }
+
+    #[must_use]
+    pub fn sensor_state(&self) -> SensorState {
+        self.properties.sensor_state
+    }
+
+    /// Set the sensor state, sending a state changed event if it changes
+    pub fn set_sensor_state(&mut self, state: SensorState) {
+        if state != self.properties.sensor_state {
+            self.properties.sensor_state = state;
+            self.state_changed = true;
+        }
+    }
+
+    #[must_use]
+    pub fn reporting_state(&self) -> ReportingState {
+        self.properties.reporting_state
+    }
+
+    #[must_use]
+    pub fn power_state(&self) -> PowerState {
+        self.properties.power_state
+    }
+
+    /// Report interval selected by the host, or the default interval
+    #[must_use]
+    pub fn report_interval(&self) -> MillisDurationU32 {
+        self.properties.interval().millis()
+    }
+
+    /// Change sensitivity selected by the host, in the units of the data fields
+    #[must_use]
+    pub fn change_sensitivity(&self) -> u16 {
+        self.properties.change_sensitivity
+    }
+
+    fn input_report(&self, event: SensorEvent, data: &mut [u8]) -> usb_device::Result<usize> {
+        let size =
+            INPUT_HEADER_SIZE + S::packed_bytes_size(None).map_err(|_| UsbError::ParseError)?;
+        let Some(data) = data.get_mut(..size) else {
+            error!("Sensor input report buffer too short");
+            return Err(UsbError::BufferOverflow);
+        };
+        data[0] = REPORT_ID;
+        data[1] = self.properties.sensor_state.to_primitive();
+        data[2] = event.to_primitive();
+        self.data
+            .pack_to_slice(&mut data[INPUT_HEADER_SIZE..])
+            .map_err(|_| {
+                error!("Error packing sensor data");
+                UsbError::ParseError
+            })?;
+        Ok(size)
+    }
+
+    fn write_event(&mut self, event: SensorEvent) -> Result<(), UsbHidError> {
+        let mut report = [0; MAX_INPUT_REPORT_SIZE];
+        let size = self
+            .input_report(event, &mut report)
+            .map_err(|_| UsbHidError::SerializationError)?;
+        self.interface.write_report(&report[..size])?;
+        self.sent = Some(self.data);
+        self.elapsed_ms = 0;
+        Ok(())
+    }
+}
+
+impl<'a, B: UsbBus, S: SensorData> DeviceClass<'a> for SensorDevice<'a, B, S> {
+    type I = Interface<'a, B, InBytes16, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {
+        self.sent = None;
+        self.elapsed_ms = 0;
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        self.elapsed_ms = self.elapsed_ms.saturating_add(1);
+
+        let event = if self.state_changed && self.properties.powered() {
+            Some(SensorEvent::StateChanged)
+        } else if self.elapsed_ms >= self.properties.interval() {
+            let exceeded = self.sent.is_none_or(|sent| {
+                self.data
+                    .exceeds_sensitivity(&sent, self.properties.change_sensitivity)
+            });
+            self.properties.data_event(exceeded)
+        } else {
+            None
+        };
+
+        match event.map(|event| self.write_event(event)) {
+            Some(Err(UsbHidError::WouldBlock)) | None => Ok(()),
+            Some(Ok(())) => {
+                self.state_changed = false;
+                Ok(())
+            }
+            Some(Err(e)) => Err(e),
+        }
+    }
+
+    fn set_report(
+        &mut self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &[u8],
+    ) -> usb_device::Result<()> {
+        if report_type != ReportType::Feature || report_id != REPORT_ID {
+            warn!("Unsupported sensor SET_REPORT report ID {:X}", report_id);
+            return Err(UsbError::Unsupported);
+        }
+        let report = FeatureReport::unpack_from_slice(data).map_err(|_| {
+            error!("Error unpacking sensor FeatureReport");
+            UsbError::ParseError
+        })?;
+        self.properties.set(&report);
+        Ok(())
+    }
+
+    fn get_report(
+        &mut self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &mut [u8],
+    ) -> usb_device::Result<usize> {
+        match (report_type, report_id) {
+            (ReportType::Feature, REPORT_ID) => {
+                let report = self.properties.feature_report();
+                let size =
+                    FeatureReport::packed_bytes_size(None).map_err(|_| UsbError::ParseError)?;
+                let Some(data) = data.get_mut(..size) else {
+                    error!("GetReport failed, buffer too short");
+                    return Err(UsbError::BufferOverflow);
+                };
+                report.pack_to_slice(data).map_err(|_| {
+                    error!("Error packing sensor FeatureReport");
+                    UsbError::ParseError
+                })?;
+                Ok(size)
+            }
+            (ReportType::Input, REPORT_ID) => self.input_report(SensorEvent::PollResponse, data),
+            _ => Err(UsbError::Unsupported),
+        }
+    }
+
+    fn get_report_ack(&mut self, _: ReportType, _: u8) -> usb_device::Result<()> {
+        Ok(())
+    }
+}
+
+pub struct SensorConfig<'a, S> {
+    interface: InterfaceConfig<'a, InBytes16, OutNone, ReportSingle>,
+    connection_type: ConnectionType,
+    report_interval: MillisDurationU32,
+    _data: PhantomData<S>,
+}
+
+/// Accelerometer 3D sensor config
+pub type Accelerometer3dConfig<'a> = SensorConfig<'a, Acceleration3d>;
+/// Ambient light sensor config
+pub type AmbientLightConfig<'a> = SensorConfig<'a, Illuminance>;
+/// Temperature sensor config
+pub type TemperatureSensorConfig<'a> = SensorConfig<'a, Temperature>;
+
+impl<'a, S: SensorData> Default for SensorConfig<'a, S> {
+    #[must_use]
+    fn default() -> Self {
+        Self::new(
+            unwrap!(unwrap!(InterfaceBuilder::new(S::report_descriptor()))
+                .description(S::DESCRIPTION)
+                .in_endpoint(10.millis()))
+            .without_out_endpoint()
+            .build(),
+        )
+    }
+}
+
+impl<'a, S> SensorConfig<'a, S> {
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes16, OutNone, ReportSingle>) -> Self {
+        Self {
+            interface,
+            connection_type: ConnectionType::Integrated,
+            report_interval: 100.millis(),
+            _data: PhantomData,
+        }
+    }
+
+    /// How the sensor is connected to the host, [`ConnectionType::Integrated`] by default
+    #[must_use]
+    pub fn connection_type(mut self, connection_type: ConnectionType) -> Self {
+        self.connection_type = connection_type;
+        self
+    }
+
+    /// Report interval used until the host selects one, 100ms by default
+    #[must_use]
+    pub fn report_interval(mut self, report_interval: MillisDurationU32) -> Self {
+        self.report_interval = report_interval;
+        self
+    }
+}
+
+impl<'a, B: UsbBus + 'a, S: SensorData> UsbAllocatable<'a, B> for SensorConfig<'a, S> {
+    type Allocated = SensorDevice<'a, B, S>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+            properties: Properties {
+                connection_type: self.connection_type,
+                reporting_state: ReportingState::default(),
+                power_state: PowerState::default(),
+                sensor_state: SensorState::default(),
+                default_report_interval: self.report_interval.to_millis().max(1),
+                report_interval: 0,
+                change_sensitivity: S::DEFAULT_SENSITIVITY,
+            },
+            data: S::default(),
+            sent: None,
+            state_changed: false,
+            elapsed_ms: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    fn properties() -> Properties {
+        Properties {
+            connection_type: ConnectionType::Integrated,
+            reporting_state: ReportingState::AllEvents,
+            power_state: PowerState::D0FullPower,
+            sensor_state: SensorState::Ready,
+            default_report_interval: 100,
+            report_interval: 0,
+            change_sensitivity: 2,
+        }
+    }
+
+    #[test]
+    fn feature_report_round_trip() {
+        let mut properties = properties();
+        let mut report = properties.feature_report();
+        assert_eq!(
+            report.pack().unwrap(),
+            [0x01, 0x00, 0x01, 0x01, 0x01, 0, 0, 0, 0, 0x02, 0x00]
+        );
+
+        report.reporting_state = ReportingState::ThresholdEvents;
+        report.sensor_state = SensorState::Error;
+        report.report_interval = 20;
+        properties.set(&report);
+        assert_eq!(properties.reporting_state, ReportingState::ThresholdEvents);
+        assert_eq!(properties.sensor_state, SensorState::Ready);
+        assert_eq!(properties.interval(), 20);
+    }
+
+    #[test]
+    fn data_events() {
+        let mut properties = properties();
+        assert_eq!(properties.data_event(false), Some(SensorEvent::DataUpdated));
+
+        properties.reporting_state = ReportingState::ThresholdEvents;
+        assert_eq!(properties.data_event(false), None);
+        assert_eq!(
+            properties.data_event(true),
+            Some(SensorEvent::ChangeSensitivity)
+        );
+
+        properties.power_state = PowerState::D4PowerOff;
+        assert_eq!(properties.data_event(true), None);
+    }
+
+    #[test]
+    fn change_sensitivity() {
+        let previous = Acceleration3d {
+            x: 0,
+            y: 100,
+            z: -100,
+        };
+        let small = Acceleration3d { x: 1, ..previous };
+        let large = Acceleration3d { z: -98, ..previous };
+        assert!(!small.exceeds_sensitivity(&previous, 2));
+        assert!(large.exceeds_sensitivity(&previous, 2));
+        assert!(!previous.exceeds_sensitivity(&previous, 0));
+    }
+
+    #[test]
+    fn descriptor_fits() {
+        for descriptor in [
+            ACCELEROMETER_3D_DESCRIPTOR,
+            AMBIENT_LIGHT_DESCRIPTOR,
+            TEMPERATURE_DESCRIPTOR,
+        ] {
+            // Usage Page (Sensors), Usage, Collection (Application), Report ID (1)
+            assert_eq!(descriptor.as_bytes()[..2], [0x05, 0x20]);
+            assert_eq!(descriptor.as_bytes()[4..8], [0xA1, 0x01, 0x85, 0x01]);
+            assert_eq!(descriptor.as_bytes().last(), Some(&0xC0));
+        }
+    }
+}
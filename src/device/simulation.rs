@@ -0,0 +1,291 @@
+//!HID simulation controls - racing wheel and flight controls
+//!
+//! Both devices report a Generic Desktop Joystick application collection so they are enumerated
+//! as game controllers, with axes labelled using the Simulation Controls usage page.
+use crate::usb_class::prelude::*;
+use core::default::Default;
+use fugit::ExtU32;
+use packed_struct::prelude::*;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+
+/// Racing wheel report descriptor
+///
+/// Signed 16 bit steering, unsigned 16 bit accelerator, brake and clutch pedals, shift up and shift
+/// down paddles (buttons 1 and 2) and 16 further buttons (buttons 3 to 18)
+#[rustfmt::skip]
+pub const RACING_WHEEL_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01,                   // Usage Page (Generic Desktop)
+    0x09, 0x04,                   // Usage (Joystick)
+    0xa1, 0x01,                   // Collection (Application)
+    0x05, 0x02,                   //   Usage Page (Simulation Controls)
+    0x09, 0xc8,                   //   Usage (Steering)
+    0x16, 0x01, 0x80,             //   Logical Minimum (-32767)
+    0x26, 0xff, 0x7f,             //   Logical Maximum (32767)
+    0x75, 0x10,                   //   Report Size (16)
+    0x95, 0x01,                   //   Report Count (1)
+    0x81, 0x02,                   //   Input (Data, Variable, Absolute)
+    0x09, 0xc4,                   //   Usage (Accelerator)
+    0x09, 0xc5,                   //   Usage (Brake)
+    0x09, 0xc6,                   //   Usage (Clutch)
+    0x15, 0x00,                   //   Logical Minimum (0)
+    0x27, 0xff, 0xff, 0x00, 0x00, //   Logical Maximum (65535)
+    0x95, 0x03,                   //   Report Count (3)
+    0x81, 0x02,                   //   Input (Data, Variable, Absolute)
+    0x09, 0xc7,                   //   Usage (Shifter)
+    0xa1, 0x02,                   //   Collection (Logical)
+    0x05, 0x09,                   //     Usage Page (Button)
+    0x19, 0x01,                   //     Usage Minimum (1)
+    0x29, 0x02,                   //     Usage Maximum (2)
+    0x25, 0x01,                   //     Logical Maximum (1)
+    0x75, 0x01,                   //     Report Size (1)
+    0x95, 0x02,                   //     Report Count (2)
+    0x81, 0x02,                   //     Input (Data, Variable, Absolute)
+    0x75, 0x06,                   //     Report Size (6)
+    0x95, 0x01,                   //     Report Count (1)
+    0x81, 0x01,                   //     Input (Constant) 6 bit padding
+    0xc0,                         //   End Collection
+    0x19, 0x03,                   //   Usage Minimum (3)
+    0x29, 0x12,                   //   Usage Maximum (18)
+    0x75, 0x01,                   //   Report Size (1)
+    0x95, 0x10,                   //   Report Count (16)
+    0x81, 0x02,                   //   Input (Data, Variable, Absolute)
+    0xc0,                         // End Collection
+];
+
+/// Flight controls report descriptor
+///
+/// Unsigned 16 bit throttle, signed 16 bit rudder and elevator trim and 8 buttons
+#[rustfmt::skip]
+pub const FLIGHT_CONTROLS_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01,                   // Usage Page (Generic Desktop)
+    0x09, 0x04,                   // Usage (Joystick)
+    0xa1, 0x01,                   // Collection (Application)
+    0x05, 0x02,                   //   Usage Page (Simulation Controls)
+    0x09, 0xbb,                   //   Usage (Throttle)
+    0x15, 0x00,                   //   Logical Minimum (0)
+    0x27, 0xff, 0xff, 0x00, 0x00, //   Logical Maximum (65535)
+    0x75, 0x10,                   //   Report Size (16)
+    0x95, 0x01,                   //   Report Count (1)
+    0x81, 0x02,                   //   Input (Data, Variable, Absolute)
+    0x09, 0xba,                   //   Usage (Rudder)
+    0x09, 0xb9,                   //   Usage (Elevator Trim)
+    0x16, 0x01, 0x80,             //   Logical Minimum (-32767)
+    0x26, 0xff, 0x7f,             //   Logical Maximum (32767)
+    0x95, 0x02,                   //   Report Count (2)
+    0x81, 0x02,                   //   Input (Data, Variable, Absolute)
+    0x05, 0x09,                   //   Usage Page (Button)
+    0x19, 0x01,                   //   Usage Minimum (1)
+    0x29, 0x08,                   //   Usage Maximum (8)
+    0x15, 0x00,                   //   Logical Minimum (0)
+    0x25, 0x01,                   //   Logical Maximum (1)
+    0x75, 0x01,                   //   Report Size (1)
+    0x95, 0x08,                   //   Report Count (8)
+    0x81, 0x02,                   //   Input (Data, Variable, Absolute)
+    0xc0,                         // End Collection
+];
+
+/// Racing wheel report
+///
+/// `buttons` bit 0 is button 3, following the shift paddles
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "11", bit_numbering = "msb0")]
+pub struct RacingWheelReport {
+    /// Negative values steer left, positive values steer right
+    #[packed_field(bytes = "0..=1")]
+    pub steering: i16,
+    #[packed_field(bytes = "2..=3")]
+    pub accelerator: u16,
+    #[packed_field(bytes = "4..=5")]
+    pub brake: u16,
+    #[packed_field(bytes = "6..=7")]
+    pub clutch: u16,
+    #[packed_field(bits = "71")]
+    pub shift_up: bool,
+    #[packed_field(bits = "70")]
+    pub shift_down: bool,
+    #[packed_field(bytes = "9..=10")]
+    pub buttons: u16,
+}
+
+pub struct RacingWheel<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes16, OutNone, ReportSingle>,
+}
+
+impl<'a, B: UsbBus> RacingWheel<'a, B> {
+    pub fn write_report(&mut self, report: &RacingWheelReport) -> Result<(), UsbHidError> {
+        let data = report.pack().map_err(|_| {
+            error!("Error packing RacingWheelReport");
+            UsbHidError::SerializationError
+        })?;
+        self.interface
+            .write_report(&data)
+            .map(|_| ())
+            .map_err(UsbHidError::from)
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for RacingWheel<'a, B> {
+    type I = Interface<'a, B, InBytes16, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {}
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        Ok(())
+    }
+}
+
+pub struct RacingWheelConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes16, OutNone, ReportSingle>,
+}
+
+impl<'a> Default for RacingWheelConfig<'a> {
+    #[must_use]
+    fn default() -> Self {
+        Self::new(
+            unwrap!(unwrap!(InterfaceBuilder::new(RACING_WHEEL_DESCRIPTOR))
+                .description("Racing Wheel")
+                .in_endpoint(1.millis()))
+            .without_out_endpoint()
+            .build(),
+        )
+    }
+}
+
+impl<'a> RacingWheelConfig<'a> {
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes16, OutNone, ReportSingle>) -> Self {
+        Self { interface }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for RacingWheelConfig<'a> {
+    type Allocated = RacingWheel<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+        }
+    }
+}
+
+/// Flight controls report
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "7")]
+pub struct FlightControlsReport {
+    #[packed_field]
+    pub throttle: u16,
+    #[packed_field]
+    pub rudder: i16,
+    #[packed_field]
+    pub elevator_trim: i16,
+    #[packed_field]
+    pub buttons: u8,
+}
+
+pub struct FlightControls<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes8, OutNone, ReportSingle>,
+}
+
+impl<'a, B: UsbBus> FlightControls<'a, B> {
+    pub fn write_report(&mut self, report: &FlightControlsReport) -> Result<(), UsbHidError> {
+        let data = report.pack().map_err(|_| {
+            error!("Error packing FlightControlsReport");
+            UsbHidError::SerializationError
+        })?;
+        self.interface
+            .write_report(&data)
+            .map(|_| ())
+            .map_err(UsbHidError::from)
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for FlightControls<'a, B> {
+    type I = Interface<'a, B, InBytes8, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {}
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        Ok(())
+    }
+}
+
+pub struct FlightControlsConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>,
+}
+
+impl<'a> Default for FlightControlsConfig<'a> {
+    #[must_use]
+    fn default() -> Self {
+        Self::new(
+            unwrap!(unwrap!(InterfaceBuilder::new(FLIGHT_CONTROLS_DESCRIPTOR))
+                .description("Flight Controls")
+                .in_endpoint(10.millis()))
+            .without_out_endpoint()
+            .build(),
+        )
+    }
+}
+
+impl<'a> FlightControlsConfig<'a> {
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>) -> Self {
+        Self { interface }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for FlightControlsConfig<'a> {
+    type Allocated = FlightControls<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn racing_wheel_report() {
+        let report = RacingWheelReport {
+            steering: -2,
+            accelerator: 0xFFFF,
+            brake: 0x1234,
+            clutch: 0,
+            shift_up: false,
+            shift_down: true,
+            buttons: 0x8001,
+        };
+        assert_eq!(
+            report.pack().unwrap(),
+            [0xFE, 0xFF, 0xFF, 0xFF, 0x34, 0x12, 0x00, 0x00, 0x02, 0x01, 0x80]
+        );
+    }
+
+    #[test]
+    fn flight_controls_report() {
+        let report = FlightControlsReport {
+            throttle: 0x8000,
+            rudder: -32767,
+            elevator_trim: 1,
+            buttons: 0x05,
+        };
+        assert_eq!(
+            report.pack().unwrap(),
+            [0x00, 0x80, 0x01, 0x80, 0x01, 0x00, 0x05]
+        );
+    }
+}
@@ -0,0 +1,359 @@
+//!HID telephony headset with call control buttons and status LEDs
+//!
+//! The hook switch is an absolute on/off control, the host treats the reported value as the hook
+//! state. Phone mute and redial are relative, each report with the bit set toggles the host's mute
+//! state or triggers a redial, so they are only reported once per button press. Flash is a
+//! momentary control, reported for as long as the button is held.
+use crate::usb_class::prelude::*;
+use core::default::Default;
+use fugit::ExtU32;
+use packed_struct::prelude::*;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+use usb_device::UsbError;
+
+#[rustfmt::skip]
+pub const TELEPHONY_HEADSET_DESCRIPTOR: &[u8] = &[
+    0x05, 0x0b, // Usage Page (Telephony)
+    0x09, 0x05, // Usage (Headset)
+    0xa1, 0x01, // Collection (Application)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x25, 0x01, //   Logical Maximum (1)
+    0x75, 0x01, //   Report Size (1)
+    0x95, 0x01, //   Report Count (1)
+    0x09, 0x20, //   Usage (Hook Switch)
+    0x81, 0x22, //   Input (Data, Variable, Absolute, No Preferred State)
+    0x09, 0x2f, //   Usage (Phone Mute)
+    0x81, 0x06, //   Input (Data, Variable, Relative)
+    0x09, 0x21, //   Usage (Flash)
+    0x81, 0x02, //   Input (Data, Variable, Absolute)
+    0x09, 0x24, //   Usage (Redial)
+    0x81, 0x06, //   Input (Data, Variable, Relative)
+    0x75, 0x04, //   Report Size (4)
+    0x81, 0x01, //   Input (Constant) 4 bit padding
+    0x05, 0x08, //   Usage Page (LEDs)
+    0x09, 0x17, //   Usage (Off-Hook)
+    0x09, 0x18, //   Usage (Ring)
+    0x09, 0x09, //   Usage (Mute)
+    0x75, 0x01, //   Report Size (1)
+    0x95, 0x03, //   Report Count (3)
+    0x91, 0x22, //   Output (Data, Variable, Absolute, No Preferred State)
+    0x75, 0x05, //   Report Size (5)
+    0x95, 0x01, //   Report Count (1)
+    0x91, 0x01, //   Output (Constant) 5 bit padding
+    0xc0,       // End Collection
+];
+
+/// Telephony headset input report
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", bit_numbering = "lsb0", size_bytes = "1")]
+pub struct TelephonyHeadsetReport {
+    /// Absolute hook state, `true` when off-hook
+    #[packed_field(bits = "0")]
+    pub hook_switch: bool,
+    /// Relative, toggles the mute state
+    #[packed_field(bits = "1")]
+    pub phone_mute: bool,
+    #[packed_field(bits = "2")]
+    pub flash: bool,
+    /// Relative, triggers a redial
+    #[packed_field(bits = "3")]
+    pub redial: bool,
+}
+
+/// LED states set by the host
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", bit_numbering = "lsb0", size_bytes = "1")]
+pub struct TelephonyLedsReport {
+    #[packed_field(bits = "0")]
+    pub off_hook: bool,
+    #[packed_field(bits = "1")]
+    pub ring: bool,
+    #[packed_field(bits = "2")]
+    pub mute: bool,
+}
+
+/// How the hook switch input is interpreted
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub enum HookSwitchMode {
+    /// A latching switch, e.g. a cradle, whose state is the hook state
+    Switch,
+    /// A momentary button, each press toggles between on-hook and off-hook. The hook state follows
+    /// the host's Off-Hook LED so calls answered or ended on the host stay in sync.
+    #[default]
+    Button,
+}
+
+/// Current state of the headset buttons
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub struct TelephonyHeadsetButtons {
+    pub hook_switch: bool,
+    pub phone_mute: bool,
+    pub flash: bool,
+    pub redial: bool,
+}
+
+/// Hook state and relative control tracking
+#[derive(Clone, Copy, Debug, Default)]
+struct CallControls {
+    mode: HookSwitchMode,
+    off_hook: bool,
+    buttons: TelephonyHeadsetButtons,
+    pending_mute: bool,
+    pending_redial: bool,
+    last_report: Option<TelephonyHeadsetReport>,
+}
+
+impl CallControls {
+    fn new(mode: HookSwitchMode) -> Self {
+        Self {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    /// Report for the new button states, `None` if there is nothing new to report
+    fn update(&mut self, buttons: TelephonyHeadsetButtons) -> Option<TelephonyHeadsetReport> {
+        let pressed = |now: bool, before: bool| now && !before;
+        match self.mode {
+            HookSwitchMode::Switch => self.off_hook = buttons.hook_switch,
+            HookSwitchMode::Button => {
+                if pressed(buttons.hook_switch, self.buttons.hook_switch) {
+                    self.off_hook = !self.off_hook;
+                }
+            }
+        }
+        self.pending_mute |= pressed(buttons.phone_mute, self.buttons.phone_mute);
+        self.pending_redial |= pressed(buttons.redial, self.buttons.redial);
+        self.buttons = buttons;
+
+        let report = TelephonyHeadsetReport {
+            hook_switch: self.off_hook,
+            phone_mute: self.pending_mute,
+            flash: buttons.flash,
+            redial: self.pending_redial,
+        };
+        // relative controls act on every report, so a repeated press is never a duplicate
+        let relative = report.phone_mute || report.redial;
+        if !relative && self.last_report == Some(report) {
+            None
+        } else {
+            Some(report)
+        }
+    }
+
+    fn report_sent(&mut self, report: TelephonyHeadsetReport) {
+        self.last_report = Some(report);
+        self.pending_mute = false;
+        self.pending_redial = false;
+    }
+
+    fn leds(&mut self, leds: TelephonyLedsReport) {
+        if self.mode == HookSwitchMode::Button {
+            self.off_hook = leds.off_hook;
+        }
+    }
+}
+
+pub struct TelephonyHeadset<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes8, OutBytes8, ReportSingle>,
+    controls: CallControls,
+}
+
+impl<'a, B: UsbBus> TelephonyHeadset<'a, B> {
+    /// Write a raw report, bypassing the hook switch and relative control handling
+    pub fn write_report(&mut self, report: &TelephonyHeadsetReport) -> Result<(), UsbHidError> {
+        let data = report.pack().map_err(|_| {
+            error!("Error packing TelephonyHeadsetReport");
+            UsbHidError::SerializationError
+        })?;
+        self.interface
+            .write_report(&data)
+            .map(|_| ())
+            .map_err(UsbHidError::from)
+    }
+
+    /// Update the headset with the current button states and report any changes to the host
+    ///
+    /// Returns [`UsbHidError::Duplicate`] if there was nothing new to report. Presses that could not
+    /// be reported are retained and reported by the next call.
+    pub fn update(&mut self, buttons: TelephonyHeadsetButtons) -> Result<(), UsbHidError> {
+        let report = self
+            .controls
+            .update(buttons)
+            .ok_or(UsbHidError::Duplicate)?;
+        self.write_report(&report)?;
+        self.controls.report_sent(report);
+        Ok(())
+    }
+
+    /// Read the LED states set by the host
+    ///
+    /// With [`HookSwitchMode::Button`] the hook state is updated from the Off-Hook LED, the new
+    /// state is reported by the next [`TelephonyHeadset::update`].
+    pub fn read_report(&mut self) -> usb_device::Result<TelephonyLedsReport> {
+        let data = &mut [0];
+        self.interface.read_report(data)?;
+        let leds = TelephonyLedsReport::unpack(data).map_err(|_| UsbError::ParseError)?;
+        self.controls.leds(leds);
+        Ok(leds)
+    }
+
+    /// `true` when off-hook, as last reported or about to be reported to the host
+    #[must_use]
+    pub fn off_hook(&self) -> bool {
+        self.controls.off_hook
+    }
+
+    #[must_use]
+    pub fn hook_switch_mode(&self) -> HookSwitchMode {
+        self.controls.mode
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for TelephonyHeadset<'a, B> {
+    type I = Interface<'a, B, InBytes8, OutBytes8, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {
+        self.controls = CallControls::new(self.controls.mode);
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        Ok(())
+    }
+}
+
+pub struct TelephonyHeadsetConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes8, OutBytes8, ReportSingle>,
+    mode: HookSwitchMode,
+}
+
+impl<'a> Default for TelephonyHeadsetConfig<'a> {
+    #[must_use]
+    fn default() -> Self {
+        Self::new(
+            HookSwitchMode::default(),
+            unwrap!(
+                unwrap!(unwrap!(InterfaceBuilder::new(TELEPHONY_HEADSET_DESCRIPTOR))
+                    .description("Headset")
+                    .in_endpoint(10.millis()))
+                .with_out_endpoint(100.millis())
+            )
+            .build(),
+        )
+    }
+}
+
+impl<'a> TelephonyHeadsetConfig<'a> {
+    #[must_use]
+    pub fn new(
+        mode: HookSwitchMode,
+        interface: InterfaceConfig<'a, InBytes8, OutBytes8, ReportSingle>,
+    ) -> Self {
+        Self { interface, mode }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for TelephonyHeadsetConfig<'a> {
+    type Allocated = TelephonyHeadset<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+            controls: CallControls::new(self.mode),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn headset_report() {
+        let report = TelephonyHeadsetReport {
+            hook_switch: true,
+            phone_mute: false,
+            flash: false,
+            redial: true,
+        };
+        assert_eq!(report.pack().unwrap(), [0x09]);
+    }
+
+    #[test]
+    fn hook_button_toggles_and_follows_host() {
+        let mut controls = CallControls::new(HookSwitchMode::Button);
+        let hook = TelephonyHeadsetButtons {
+            hook_switch: true,
+            ..Default::default()
+        };
+
+        let report = controls.update(hook).unwrap();
+        assert!(report.hook_switch);
+        controls.report_sent(report);
+        // held, no change
+        assert_eq!(controls.update(hook), None);
+
+        // call ended on the host
+        controls.leds(TelephonyLedsReport::default());
+        let report = controls.update(TelephonyHeadsetButtons::default()).unwrap();
+        assert!(!report.hook_switch);
+    }
+
+    #[test]
+    fn hook_switch_is_absolute() {
+        let mut controls = CallControls::new(HookSwitchMode::Switch);
+        let off_hook = TelephonyHeadsetButtons {
+            hook_switch: true,
+            ..Default::default()
+        };
+        assert!(controls.update(off_hook).unwrap().hook_switch);
+        controls.leds(TelephonyLedsReport::default());
+        assert!(controls.off_hook);
+    }
+
+    #[test]
+    fn mute_reported_once_per_press() {
+        let mut controls = CallControls::new(HookSwitchMode::Button);
+        let mute = TelephonyHeadsetButtons {
+            phone_mute: true,
+            ..Default::default()
+        };
+
+        let report = controls.update(mute).unwrap();
+        assert!(report.phone_mute);
+        controls.report_sent(report);
+
+        // still held, the toggle must not be repeated
+        let report = controls.update(mute).unwrap();
+        assert!(!report.phone_mute);
+        controls.report_sent(report);
+        assert_eq!(controls.update(mute), None);
+
+        // an unsent press is retained
+        controls.update(TelephonyHeadsetButtons::default());
+        assert!(controls.update(mute).unwrap().phone_mute);
+        assert!(controls.update(mute).unwrap().phone_mute);
+    }
+
+    #[test]
+    fn leds_report() {
+        let leds = TelephonyLedsReport::unpack(&[0x05]).unwrap();
+        assert_eq!(
+            leds,
+            TelephonyLedsReport {
+                off_hook: true,
+                ring: false,
+                mute: true,
+            }
+        );
+    }
+}
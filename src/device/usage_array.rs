@@ -0,0 +1,435 @@
+//!Generic device reporting an array of active usages from one usage page
+//!
+//! Reports up to [`UsageArrayLayout::length`] simultaneously active usages from a range of a single
+//! usage page, as [`MULTIPLE_CODE_REPORT_DESCRIPTOR`](crate::device::consumer::MULTIPLE_CODE_REPORT_DESCRIPTOR)
+//! does for consumer controls, e.g. for AC application launch, telephony keypads or Game page
+//! controls. The report descriptor is generated from the [`UsageArrayLayout`].
+//!
+//! When more usages are active than the array holds, every field reports `ErrorRollOver` if the
+//! usage range includes [`Keyboard::ErrorRollOver`](crate::page::Keyboard::ErrorRollOver).
+//! Other pages have no `ErrorRollOver` usage, so the last report is held instead, leaving the host
+//! with the same state it would keep on `ErrorRollOver`.
+//!
+//! ```
+//! # use usb_device::bus::UsbBusAllocator;
+//! # use usbd_human_interface_device::prelude::*;
+//! use usbd_human_interface_device::descriptor::builder::ReportDescriptorBuilder;
+//! use usbd_human_interface_device::device::usage_array::*;
+//! use usbd_human_interface_device::page::{Telephony, UsagePage};
+//!
+//! const KEYPAD: UsageArrayLayout = UsageArrayLayout {
+//!     page: UsagePage::Telephony,
+//!     application: Telephony::Phone as u16,
+//!     usage_minimum: Telephony::PhoneKey0 as u16,
+//!     usage_maximum: Telephony::PhoneKeyD as u16,
+//!     length: 2,
+//! };
+//! static DESCRIPTOR: ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> = KEYPAD.descriptor();
+//!
+//! # fn example<B: usb_device::bus::UsbBus>(usb_alloc: &UsbBusAllocator<B>) {
+//! let mut keypad = UsbHidClassBuilder::new()
+//!     .add_device(UsageArrayConfig::with_descriptor(KEYPAD, DESCRIPTOR.as_bytes()))
+//!     .build(usb_alloc);
+//!
+//! let device = keypad.device();
+//! device.add(Telephony::PhoneKey5);
+//! device.write_pending().ok();
+//! # }
+//! ```
+use crate::descriptor::builder::{Collection, ItemFlags, ReportDescriptorBuilder};
+use crate::page::{Keyboard, Usage, UsagePage};
+use crate::usb_class::prelude::*;
+use fugit::ExtU32;
+use heapless::Vec;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+
+/// Largest number of fields in an array
+pub const MAX_LENGTH: usize = 16;
+/// Largest number of usages tracked as active, beyond which added usages are ignored
+pub const MAX_ACTIVE: usize = 32;
+/// Capacity of a descriptor from [`UsageArrayLayout::descriptor`]
+pub const DESCRIPTOR_CAPACITY: usize = 32;
+
+const REPORT_BYTES: usize = MAX_LENGTH * 2;
+
+/// Usage page, usage range and number of fields of a usage array
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct UsageArrayLayout {
+    pub page: UsagePage,
+    /// Usage of the application collection
+    pub application: u16,
+    pub usage_minimum: u16,
+    pub usage_maximum: u16,
+    /// Number of fields, at most [`MAX_LENGTH`]
+    pub length: usize,
+}
+
+impl UsageArrayLayout {
+    /// Layout of [`MULTIPLE_CODE_REPORT_DESCRIPTOR`](crate::device::consumer::MULTIPLE_CODE_REPORT_DESCRIPTOR)
+    pub const CONSUMER_CONTROL: Self = Self {
+        page: UsagePage::Consumer,
+        application: 0x01,
+        usage_minimum: 0x00,
+        usage_maximum: 0x029C,
+        length: 4,
+    };
+
+    /// Size of each field in bytes
+    #[must_use]
+    pub const fn field_size(&self) -> usize {
+        if self.usage_maximum > 0xFF {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Size of the report in bytes
+    #[must_use]
+    pub const fn report_size(&self) -> usize {
+        self.field_size() * self.length
+    }
+
+    /// Report descriptor for this layout
+    ///
+    /// Fields report the usage ID, values outside the usage range mean no usage.
+    #[allow(clippy::cast_possible_truncation)]
+    #[must_use]
+    pub const fn descriptor(&self) -> ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> {
+        core::assert!(self.length <= MAX_LENGTH, "Too many fields");
+        core::assert!(
+            self.usage_minimum <= self.usage_maximum,
+            "Empty usage range"
+        );
+        ReportDescriptorBuilder::new()
+            .usage_page(self.page as u16)
+            .usage(self.application)
+            .collection(Collection::Application)
+            .report_size(self.field_size() as u32 * 8)
+            .report_count(self.length as u32)
+            .logical_minimum(self.usage_minimum as i32)
+            .logical_maximum(self.usage_maximum as i32)
+            .usage_minimum(self.usage_minimum)
+            .usage_maximum(self.usage_maximum)
+            .input(ItemFlags::DATA_ARRAY_ABSOLUTE)
+            .end_collection()
+    }
+
+    const fn contains(&self, id: u16) -> bool {
+        id >= self.usage_minimum && id <= self.usage_maximum
+    }
+
+    /// Value reported in every field on roll over, if the page has one
+    fn error_roll_over(&self) -> Option<u16> {
+        let id = Keyboard::ErrorRollOver as u16;
+        (self.page == UsagePage::Keyboard && self.contains(id)).then_some(id)
+    }
+}
+
+pub struct UsageArray<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes32, OutNone, ReportSingle>,
+    layout: UsageArrayLayout,
+    /// Active usages, oldest first
+    active: Vec<u16, MAX_ACTIVE>,
+    sent: Option<[u8; REPORT_BYTES]>,
+}
+
+impl<'a, B: UsbBus> UsageArray<'a, B> {
+    fn id<U: Usage>(&self, usage: U) -> Option<u16> {
+        let id = usage.id();
+        (U::PAGE == self.layout.page && self.layout.contains(id)).then_some(id)
+    }
+
+    /// Make `usage` active, sent by [`UsageArray::write_pending`]
+    ///
+    /// Returns `false` if `usage` is not in the usage range of the device or [`MAX_ACTIVE`]
+    /// usages are already active.
+    pub fn add<U: Usage>(&mut self, usage: U) -> bool {
+        let Some(id) = self.id(usage) else {
+            warn!("Usage {:X} not in array usage range", usage.id());
+            return false;
+        };
+        if self.active.contains(&id) {
+            return true;
+        }
+        if self.active.push(id).is_err() {
+            warn!("Usage {:X} ignored, too many active usages", id);
+            return false;
+        }
+        true
+    }
+
+    /// Make `usage` inactive
+    ///
+    /// Returns `false` if `usage` was not active.
+    pub fn remove<U: Usage>(&mut self, usage: U) -> bool {
+        let Some(id) = self.id(usage) else {
+            return false;
+        };
+        match self.active.iter().position(|a| *a == id) {
+            Some(i) => {
+                self.active.remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Make all usages inactive
+    pub fn clear(&mut self) {
+        self.active.clear();
+    }
+
+    /// True if more usages are active than the array holds
+    #[must_use]
+    pub fn is_rolled_over(&self) -> bool {
+        self.active.len() > self.layout.length
+    }
+
+    /// The report for the active usages, `None` while rolled over without an `ErrorRollOver` usage
+    fn report(&self) -> Option<[u8; REPORT_BYTES]> {
+        let mut report = [0; REPORT_BYTES];
+        let size = self.layout.field_size();
+        let mut write = |i: usize, id: u16| {
+            report[i * size..(i + 1) * size].copy_from_slice(&id.to_le_bytes()[..size]);
+        };
+        if self.is_rolled_over() {
+            let error_roll_over = self.layout.error_roll_over()?;
+            for i in 0..self.layout.length {
+                write(i, error_roll_over);
+            }
+        } else {
+            for (i, id) in self.active.iter().enumerate() {
+                write(i, *id);
+            }
+        }
+        Some(report)
+    }
+
+    /// Send a report if the active usages changed
+    ///
+    /// Called by [`tick`](DeviceClass::tick), returns [`UsbHidError::Duplicate`] if there is
+    /// nothing to send.
+    pub fn write_pending(&mut self) -> Result<(), UsbHidError> {
+        let report = match self.report() {
+            Some(report) if self.sent != Some(report) => report,
+            _ => return Err(UsbHidError::Duplicate),
+        };
+        self.interface
+            .write_report(&report[..self.layout.report_size()])?;
+        self.sent = Some(report);
+        Ok(())
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for UsageArray<'a, B> {
+    type I = Interface<'a, B, InBytes32, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {
+        self.sent = None;
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        match self.write_pending() {
+            Err(UsbHidError::WouldBlock | UsbHidError::Duplicate) => Ok(()),
+            result => result,
+        }
+    }
+}
+
+pub struct UsageArrayConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes32, OutNone, ReportSingle>,
+    layout: UsageArrayLayout,
+}
+
+impl<'a> UsageArrayConfig<'a> {
+    /// `layout` must match the report descriptor of `interface`, see
+    /// [`UsageArrayLayout::descriptor`]
+    ///
+    /// Panics if the layout has more than [`MAX_LENGTH`] fields.
+    #[must_use]
+    pub fn new(
+        layout: UsageArrayLayout,
+        interface: InterfaceConfig<'a, InBytes32, OutNone, ReportSingle>,
+    ) -> Self {
+        assert!(layout.length <= MAX_LENGTH, "Too many fields");
+        Self { interface, layout }
+    }
+
+    /// Array with `descriptor` from [`UsageArrayLayout::descriptor`]
+    #[must_use]
+    pub fn with_descriptor(layout: UsageArrayLayout, descriptor: &'a [u8]) -> Self {
+        Self::new(
+            layout,
+            unwrap!(unwrap!(InterfaceBuilder::new(descriptor))
+                .description("Controls")
+                .in_endpoint(10.millis()))
+            .without_out_endpoint()
+            .build(),
+        )
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for UsageArrayConfig<'a> {
+    type Allocated = UsageArray<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        UsageArray {
+            interface: Interface::new(usb_alloc, self.interface),
+            layout: self.layout,
+            active: Vec::new(),
+            sent: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::device::consumer::MULTIPLE_CODE_REPORT_DESCRIPTOR;
+    use crate::page::{Consumer, Telephony};
+    use crate::test_bus::{build_device, TestBusState, TestUsbBus};
+
+    const KEYBOARD: UsageArrayLayout = UsageArrayLayout {
+        page: UsagePage::Keyboard,
+        application: 0x06,
+        usage_minimum: 0x00,
+        usage_maximum: 0x65,
+        length: 2,
+    };
+
+    const KEYPAD: UsageArrayLayout = UsageArrayLayout {
+        page: UsagePage::Telephony,
+        application: Telephony::Phone as u16,
+        usage_minimum: Telephony::PhoneKey0 as u16,
+        usage_maximum: Telephony::PhoneKeyD as u16,
+        length: 2,
+    };
+
+    #[test]
+    fn consumer_control_descriptor() {
+        const DESCRIPTOR: ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> =
+            UsageArrayLayout::CONSUMER_CONTROL.descriptor();
+        assert_eq!(DESCRIPTOR.as_bytes(), MULTIPLE_CODE_REPORT_DESCRIPTOR);
+    }
+
+    #[test]
+    fn error_roll_over() {
+        let keyboard = UsageArrayLayout {
+            page: UsagePage::Keyboard,
+            application: 0x06,
+            usage_minimum: 0x00,
+            usage_maximum: 0x65,
+            length: 6,
+        };
+        assert_eq!(keyboard.error_roll_over(), Some(0x01));
+        assert_eq!(keyboard.report_size(), 6);
+        assert_eq!(UsageArrayLayout::CONSUMER_CONTROL.error_roll_over(), None);
+        assert_eq!(UsageArrayLayout::CONSUMER_CONTROL.report_size(), 8);
+    }
+
+    #[test]
+    fn add_and_remove() {
+        let descriptor = KEYPAD.descriptor();
+        let state = TestBusState::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&state));
+        let mut keypad =
+            UsageArrayConfig::with_descriptor(KEYPAD, descriptor.as_bytes()).allocate(&usb_alloc);
+
+        assert!(keypad.add(Telephony::PhoneKey5));
+        assert!(keypad.add(Telephony::PhoneKey5));
+        assert!(!keypad.add(Telephony::PhoneMute));
+        assert!(!keypad.add(Consumer::PlayPause));
+        assert!(keypad.remove(Telephony::PhoneKey5));
+        assert!(!keypad.remove(Telephony::PhoneKey5));
+        assert!(!keypad.remove(Telephony::PhoneMute));
+    }
+
+    #[test]
+    fn fields_packed_oldest_first() {
+        let descriptor = UsageArrayLayout::CONSUMER_CONTROL.descriptor();
+        let state = TestBusState::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&state));
+        let mut consumer = UsageArrayConfig::with_descriptor(
+            UsageArrayLayout::CONSUMER_CONTROL,
+            descriptor.as_bytes(),
+        )
+        .allocate(&usb_alloc);
+        let _usb_dev = build_device(&usb_alloc);
+
+        consumer.add(Consumer::Mute);
+        consumer.add(Consumer::PlayPause);
+        consumer.write_pending().unwrap();
+        assert_eq!(
+            state.take_written(),
+            [[0xE2, 0x00, 0xCD, 0x00, 0x00, 0x00, 0x00, 0x00]]
+        );
+        assert!(matches!(
+            consumer.write_pending(),
+            Err(UsbHidError::Duplicate)
+        ));
+
+        consumer.remove(Consumer::Mute);
+        consumer.write_pending().unwrap();
+        assert_eq!(
+            state.take_written(),
+            [[0xCD, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]]
+        );
+    }
+
+    #[test]
+    fn roll_over_reports_error_roll_over() {
+        let descriptor = KEYBOARD.descriptor();
+        let state = TestBusState::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&state));
+        let mut keyboard =
+            UsageArrayConfig::with_descriptor(KEYBOARD, descriptor.as_bytes()).allocate(&usb_alloc);
+        let _usb_dev = build_device(&usb_alloc);
+
+        keyboard.add(Keyboard::A);
+        keyboard.add(Keyboard::B);
+        keyboard.add(Keyboard::C);
+        assert!(keyboard.is_rolled_over());
+        keyboard.write_pending().unwrap();
+        assert_eq!(state.take_written(), [[0x01, 0x01]]);
+
+        keyboard.remove(Keyboard::A);
+        keyboard.write_pending().unwrap();
+        assert_eq!(state.take_written(), [[0x05, 0x06]]);
+    }
+
+    #[test]
+    fn roll_over_holds_last_report() {
+        let descriptor = KEYPAD.descriptor();
+        let state = TestBusState::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&state));
+        let mut keypad =
+            UsageArrayConfig::with_descriptor(KEYPAD, descriptor.as_bytes()).allocate(&usb_alloc);
+        let _usb_dev = build_device(&usb_alloc);
+
+        keypad.add(Telephony::PhoneKey1);
+        keypad.add(Telephony::PhoneKey2);
+        keypad.write_pending().unwrap();
+        assert_eq!(state.take_written(), [[0xB1, 0xB2]]);
+
+        keypad.add(Telephony::PhoneKey3);
+        assert!(keypad.is_rolled_over());
+        assert!(matches!(
+            keypad.write_pending(),
+            Err(UsbHidError::Duplicate)
+        ));
+        assert!(state.take_written().is_empty());
+
+        keypad.remove(Telephony::PhoneKey1);
+        keypad.write_pending().unwrap();
+        assert_eq!(state.take_written(), [[0xB2, 0xB3]]);
+    }
+}
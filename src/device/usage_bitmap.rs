@@ -0,0 +1,244 @@
+//!Generic device reporting a bitmap of usages from one usage page
+//!
+//! Reports the on/off state of any list of up to [`MAX_USAGES`] usages from a single usage page,
+//! one bit per usage, e.g. the media keys of [`FIXED_FUNCTION_REPORT_DESCRIPTOR`](crate::device::consumer::FIXED_FUNCTION_REPORT_DESCRIPTOR)
+//! or a set of Desktop system controls. The report descriptor is generated from the same list of
+//! usages with [`usage_bitmap_descriptor`].
+//!
+//! ```
+//! # use usb_device::bus::UsbBusAllocator;
+//! # use usbd_human_interface_device::prelude::*;
+//! use usbd_human_interface_device::descriptor::builder::ReportDescriptorBuilder;
+//! use usbd_human_interface_device::device::usage_bitmap::*;
+//! use usbd_human_interface_device::page::{Consumer, UsagePage};
+//!
+//! const USAGES: &[u16] = &[
+//!     Consumer::Mute as u16,
+//!     Consumer::VolumeIncrement as u16,
+//!     Consumer::VolumeDecrement as u16,
+//! ];
+//! static DESCRIPTOR: ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> =
+//!     usage_bitmap_descriptor(UsagePage::Consumer, Consumer::ConsumerControl as u16, USAGES);
+//!
+//! # fn example<B: usb_device::bus::UsbBus>(usb_alloc: &UsbBusAllocator<B>) {
+//! let mut controls = UsbHidClassBuilder::new()
+//!     .add_device(UsageBitmapConfig::with_descriptor(
+//!         UsagePage::Consumer,
+//!         USAGES,
+//!         DESCRIPTOR.as_bytes(),
+//!     ))
+//!     .build(usb_alloc);
+//!
+//! let device = controls.device();
+//! device.set(Consumer::Mute, true);
+//! device.write_pending().ok();
+//! # }
+//! ```
+use crate::descriptor::builder::{Collection, ItemFlags, ReportDescriptorBuilder};
+use crate::page::{Usage, UsagePage};
+use crate::usb_class::prelude::*;
+use fugit::ExtU32;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+
+/// Largest number of usages in a bitmap
+pub const MAX_USAGES: usize = 64;
+/// Capacity of a descriptor from [`usage_bitmap_descriptor`]
+pub const DESCRIPTOR_CAPACITY: usize = 23 + 3 * MAX_USAGES;
+
+const REPORT_BYTES: usize = MAX_USAGES / 8;
+
+/// Report descriptor for a bitmap of `usages` from `page`, in an application collection of
+/// `application`
+///
+/// Usage `usages[n]` is reported in bit `n % 8` of byte `n / 8`.
+#[allow(clippy::cast_possible_truncation)]
+#[must_use]
+pub const fn usage_bitmap_descriptor(
+    page: UsagePage,
+    application: u16,
+    usages: &[u16],
+) -> ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> {
+    core::assert!(usages.len() <= MAX_USAGES, "Too many usages");
+    let mut descriptor = ReportDescriptorBuilder::new()
+        .usage_page(page as u16)
+        .usage(application)
+        .collection(Collection::Application)
+        .logical_minimum(0)
+        .logical_maximum(1)
+        .report_size(1)
+        .report_count(usages.len() as u32);
+    let mut i = 0;
+    while i < usages.len() {
+        descriptor = descriptor.usage(usages[i]);
+        i += 1;
+    }
+    descriptor = descriptor.input(ItemFlags::DATA_VARIABLE_ABSOLUTE);
+    let padding = (usages.len().div_ceil(8) * 8 - usages.len()) as u32;
+    if padding > 0 {
+        descriptor = descriptor.report_count(padding).input(ItemFlags::CONSTANT);
+    }
+    descriptor.end_collection()
+}
+
+pub struct UsageBitmap<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes8, OutNone, ReportSingle>,
+    page: UsagePage,
+    usages: &'a [u16],
+    state: [u8; REPORT_BYTES],
+    sent: Option<[u8; REPORT_BYTES]>,
+}
+
+impl<'a, B: UsbBus> UsageBitmap<'a, B> {
+    fn bit<U: Usage>(&self, usage: U) -> Option<usize> {
+        if U::PAGE != self.page {
+            return None;
+        }
+        self.usages.iter().position(|u| *u == usage.id())
+    }
+
+    /// Turn `usage` on or off, sent by [`UsageBitmap::write_pending`]
+    ///
+    /// Returns `false` if `usage` is not one of the usages of the device.
+    pub fn set<U: Usage>(&mut self, usage: U, on: bool) -> bool {
+        let Some(bit) = self.bit(usage) else {
+            warn!("Usage {:X} not in bitmap", usage.id());
+            return false;
+        };
+        if on {
+            self.state[bit / 8] |= 1 << (bit % 8);
+        } else {
+            self.state[bit / 8] &= !(1 << (bit % 8));
+        }
+        true
+    }
+
+    #[must_use]
+    pub fn get<U: Usage>(&self, usage: U) -> bool {
+        self.bit(usage)
+            .is_some_and(|bit| self.state[bit / 8] & (1 << (bit % 8)) != 0)
+    }
+
+    /// Turn all usages off
+    pub fn clear(&mut self) {
+        self.state = [0; REPORT_BYTES];
+    }
+
+    /// Send a report if any usage changed
+    ///
+    /// Called by [`tick`](DeviceClass::tick), returns [`UsbHidError::Duplicate`] if there is
+    /// nothing to send.
+    pub fn write_pending(&mut self) -> Result<(), UsbHidError> {
+        if self.sent == Some(self.state) {
+            return Err(UsbHidError::Duplicate);
+        }
+        let len = self.usages.len().div_ceil(8);
+        self.interface.write_report(&self.state[..len])?;
+        self.sent = Some(self.state);
+        Ok(())
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for UsageBitmap<'a, B> {
+    type I = Interface<'a, B, InBytes8, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {
+        self.sent = None;
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        match self.write_pending() {
+            Err(UsbHidError::WouldBlock | UsbHidError::Duplicate) => Ok(()),
+            result => result,
+        }
+    }
+}
+
+pub struct UsageBitmapConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>,
+    page: UsagePage,
+    usages: &'a [u16],
+}
+
+impl<'a> UsageBitmapConfig<'a> {
+    /// `page` and `usages` must match the report descriptor of `interface`, see
+    /// [`usage_bitmap_descriptor`]
+    ///
+    /// Panics if there are more than [`MAX_USAGES`] usages.
+    #[must_use]
+    pub fn new(
+        page: UsagePage,
+        usages: &'a [u16],
+        interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>,
+    ) -> Self {
+        assert!(usages.len() <= MAX_USAGES, "Too many usages");
+        Self {
+            interface,
+            page,
+            usages,
+        }
+    }
+
+    /// Bitmap of `usages` from `page` with `descriptor` from [`usage_bitmap_descriptor`]
+    #[must_use]
+    pub fn with_descriptor(page: UsagePage, usages: &'a [u16], descriptor: &'a [u8]) -> Self {
+        Self::new(
+            page,
+            usages,
+            unwrap!(unwrap!(InterfaceBuilder::new(descriptor))
+                .description("Controls")
+                .in_endpoint(10.millis()))
+            .without_out_endpoint()
+            .build(),
+        )
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for UsageBitmapConfig<'a> {
+    type Allocated = UsageBitmap<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        UsageBitmap {
+            interface: Interface::new(usb_alloc, self.interface),
+            page: self.page,
+            usages: self.usages,
+            state: [0; REPORT_BYTES],
+            sent: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::device::consumer::FIXED_FUNCTION_REPORT_DESCRIPTOR;
+    use crate::page::Consumer;
+
+    #[test]
+    fn fixed_function_descriptor() {
+        const DESCRIPTOR: ReportDescriptorBuilder<DESCRIPTOR_CAPACITY> = usage_bitmap_descriptor(
+            UsagePage::Consumer,
+            Consumer::ConsumerControl as u16,
+            &[
+                Consumer::ScanNextTrack as u16,
+                Consumer::ScanPreviousTrack as u16,
+                Consumer::Stop as u16,
+                Consumer::PlayPause as u16,
+                Consumer::Mute as u16,
+                Consumer::VolumeIncrement as u16,
+                Consumer::VolumeDecrement as u16,
+            ],
+        );
+        // the fixed function descriptor repeats the usage page inside the collection
+        let expected = [
+            &FIXED_FUNCTION_REPORT_DESCRIPTOR[..6],
+            &FIXED_FUNCTION_REPORT_DESCRIPTOR[8..],
+        ]
+        .concat();
+        assert_eq!(DESCRIPTOR.as_bytes(), expected);
+    }
+}
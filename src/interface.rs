@@ -239,15 +239,20 @@ option_block_idle_storage!(Reports32, Block32);
 option_block_idle_storage!(Reports64, Block64);
 option_block_idle_storage!(Reports128, Block128);
 
+/// Interface configuration, see [`InterfaceBuilder`]
+///
+/// The report descriptor `D` is borrowed by default. Descriptors built at runtime can instead be
+/// owned by the interface in a [`heapless::Vec`], see [`InterfaceBuilder::with_owned_descriptor`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct InterfaceConfig<'a, I, O, R>
+pub struct InterfaceConfig<'a, I, O, R, D = &'a [u8]>
 where
     I: InSize,
     O: OutSize,
     R: ReportCount,
+    D: AsRef<[u8]>,
 {
     marker: PhantomData<(I, O, R)>,
-    report_descriptor: &'a [u8],
+    report_descriptor: D,
     report_descriptor_length: u16,
     description: Option<&'a str>,
     protocol: InterfaceProtocol,
@@ -256,15 +261,16 @@ where
     in_endpoint: EndpointConfig,
 }
 
-pub struct Interface<'a, B, I, O, R>
+pub struct Interface<'a, B, I, O, R, D = &'a [u8]>
 where
     B: UsbBus,
     I: InSize,
     O: OutSize,
     R: ReportCount,
+    D: AsRef<[u8]>,
 {
     id: InterfaceNumber,
-    config: InterfaceConfig<'a, I, O, R>,
+    config: InterfaceConfig<'a, I, O, R, D>,
     out_endpoint: Option<EndpointOut<'a, B>>,
     in_endpoint: EndpointIn<'a, B>,
     description_index: Option<StringIndex>,
@@ -275,26 +281,28 @@ where
     control_out_report_buffer: O::Buffer,
 }
 
-impl<'a, B: UsbBus + 'a, I, O, R> UsbAllocatable<'a, B> for InterfaceConfig<'a, I, O, R>
+impl<'a, B: UsbBus + 'a, I, O, R, D> UsbAllocatable<'a, B> for InterfaceConfig<'a, I, O, R, D>
 where
     B: UsbBus,
     I: InSize,
     O: OutSize,
     R: ReportCount,
+    D: AsRef<[u8]>,
 {
-    type Allocated = Interface<'a, B, I, O, R>;
+    type Allocated = Interface<'a, B, I, O, R, D>;
 
     fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
         Interface::new(usb_alloc, self)
     }
 }
 
-impl<'a, B, I, O, R> DeviceClass<'a> for Interface<'a, B, I, O, R>
+impl<'a, B, I, O, R, D> DeviceClass<'a> for Interface<'a, B, I, O, R, D>
 where
     B: UsbBus,
     I: InSize,
     O: OutSize,
     R: ReportCount,
+    D: AsRef<[u8]>,
 {
     type I = Self;
 
@@ -311,14 +319,15 @@ where
     }
 }
 
-impl<'a, B: UsbBus, I, O, R> Interface<'a, B, I, O, R>
+impl<'a, B: UsbBus, I, O, R, D> Interface<'a, B, I, O, R, D>
 where
     B: UsbBus,
     I: InSize,
     O: OutSize,
     R: ReportCount,
+    D: AsRef<[u8]>,
 {
-    pub fn new(usb_alloc: &'a UsbBusAllocator<B>, config: InterfaceConfig<'a, I, O, R>) -> Self {
+    pub fn new(usb_alloc: &'a UsbBusAllocator<B>, config: InterfaceConfig<'a, I, O, R, D>) -> Self {
         Interface {
             id: usb_alloc.interface(),
             in_endpoint: usb_alloc.interrupt(I::Buffer::CAPACITY, config.in_endpoint.poll_interval),
@@ -411,12 +420,13 @@ where
         }
     }
 }
-impl<'a, B: UsbBus, I, O, R> InterfaceClass<'a> for Interface<'a, B, I, O, R>
+impl<'a, B: UsbBus, I, O, R, D> InterfaceClass<'a> for Interface<'a, B, I, O, R, D>
 where
     B: UsbBus,
     I: InSize,
     O: OutSize,
     R: ReportCount,
+    D: AsRef<[u8]>,
 {
     fn hid_descriptor_body(&self) -> [u8; 7] {
         match (HidDescriptorBody {
@@ -434,7 +444,7 @@ where
     }
 
     fn report_descriptor(&self) -> &'_ [u8] {
-        self.config.report_descriptor
+        self.config.report_descriptor.as_ref()
     }
 
     fn id(&self) -> InterfaceNumber {
@@ -570,13 +580,14 @@ struct EndpointConfig {
 
 #[must_use = "this `UsbHidInterfaceBuilder` must be assigned or consumed by `::build_interface()`"]
 #[derive(Copy, Clone, Debug)]
-pub struct InterfaceBuilder<'a, I, O, R>
+pub struct InterfaceBuilder<'a, I, O, R, D = &'a [u8]>
 where
     I: InSize,
     O: OutSize,
     R: ReportCount,
+    D: AsRef<[u8]>,
 {
-    config: InterfaceConfig<'a, I, O, R>,
+    config: InterfaceConfig<'a, I, O, R, D>,
 }
 
 impl<'a, I, O, R> InterfaceBuilder<'a, I, O, R>
@@ -586,12 +597,70 @@ where
     R: ReportCount,
 {
     pub fn new(report_descriptor: &'a [u8]) -> BuilderResult<Self> {
+        Self::with_descriptor(report_descriptor)
+    }
+}
+
+impl<'a, I, O, R, const N: usize> InterfaceBuilder<'a, I, O, R, Vec<u8, N>>
+where
+    I: InSize,
+    O: OutSize,
+    R: ReportCount,
+{
+    /// Interface owning a report descriptor built at runtime, e.g. from a layout read from flash
+    ///
+    /// ```
+    /// # use fugit::ExtU32;
+    /// # use usbd_human_interface_device::usb_class::UsbHidBuilderError;
+    /// use heapless::Vec;
+    /// use usbd_human_interface_device::descriptor::builder::*;
+    /// use usbd_human_interface_device::page::{Button, Desktop, UsagePage};
+    /// use usbd_human_interface_device::usb_class::prelude::*;
+    ///
+    /// # fn example(key_count: u32) -> Result<(), UsbHidBuilderError> {
+    /// let descriptor: Vec<u8, 64> = ReportDescriptorBuilder::checked()
+    ///     .usage_page(UsagePage::GenericDesktop as u16)
+    ///     .usage(Desktop::Keypad as u16)
+    ///     .collection(Collection::Application)
+    ///     .usage_page(UsagePage::Button as u16)
+    ///     .usage_minimum(Button::Primary as u16)
+    ///     .usage_maximum(key_count as u16)
+    ///     .logical_maximum(1)
+    ///     .report_size(1)
+    ///     .report_count(key_count)
+    ///     .input(ItemFlags::DATA_VARIABLE_ABSOLUTE)
+    ///     .input_padding(key_count.next_multiple_of(8) - key_count)
+    ///     .end_collection()
+    ///     .try_into()?;
+    ///
+    /// let config: InterfaceConfig<InBytes8, OutNone, ReportSingle, _> =
+    ///     InterfaceBuilder::with_owned_descriptor(descriptor)?
+    ///         .description("Keys")
+    ///         .in_endpoint(10.millis())?
+    ///         .without_out_endpoint()
+    ///         .build();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_owned_descriptor(report_descriptor: Vec<u8, N>) -> BuilderResult<Self> {
+        Self::with_descriptor(report_descriptor)
+    }
+}
+
+impl<'a, I, O, R, D> InterfaceBuilder<'a, I, O, R, D>
+where
+    I: InSize,
+    O: OutSize,
+    R: ReportCount,
+    D: AsRef<[u8]>,
+{
+    fn with_descriptor(report_descriptor: D) -> BuilderResult<Self> {
         Ok(InterfaceBuilder {
             config: InterfaceConfig {
                 marker: PhantomData,
-                report_descriptor,
-                report_descriptor_length: u16::try_from(report_descriptor.len())
+                report_descriptor_length: u16::try_from(report_descriptor.as_ref().len())
                     .map_err(|_| UsbHidBuilderError::SliceLengthOverflow)?,
+                report_descriptor,
                 description: None,
                 protocol: InterfaceProtocol::None,
                 idle_default: 0,
@@ -650,7 +719,7 @@ where
     }
 
     #[must_use]
-    pub fn build(self) -> InterfaceConfig<'a, I, O, R> {
+    pub fn build(self) -> InterfaceConfig<'a, I, O, R, D> {
         self.config
     }
 }
@@ -146,13 +146,21 @@ extern crate std;
 
 use usb_device::UsbError;
 
+pub mod absolute_pointer;
 pub mod descriptor;
 pub mod device;
 pub mod interface;
+pub mod mouse_keys;
 pub mod page;
 pub mod prelude;
+pub mod socd;
 pub mod usb_class;
 
+#[cfg(test)]
+mod test_bus;
+#[cfg(test)]
+mod test_descriptor;
+
 #[derive(Debug)]
 pub enum UsbHidError {
     WouldBlock,
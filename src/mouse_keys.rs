@@ -0,0 +1,391 @@
+//! Mouse keys and analog stick pointer control
+//!
+//! Turns held direction keys, or analog stick values, into relative pointer and wheel motion for
+//! a [`RelativePointer`], e.g. [`WheelMouse::pointer`](crate::device::mouse::WheelMouse::pointer),
+//! so that keyboards and gamepads can drive the pointer without a sensor.
+//!
+//! Speeds are in counts per second, ramped up while keys are held, or scaled with the stick
+//! deflection, following an [`AccelerationCurve`].
+//!
+//! ```
+//! use usbd_human_interface_device::device::mouse::RelativePointer;
+//! use usbd_human_interface_device::mouse_keys::{MotionConfig, MouseKeys};
+//! use usbd_human_interface_device::socd::Directions;
+//!
+//! let mut mouse_keys = MouseKeys::new(MotionConfig::default(), MotionConfig::wheel());
+//! let mut pointer = RelativePointer::new();
+//!
+//! mouse_keys.set_directions(Directions { right: true, ..Default::default() });
+//! for _ in 0..100 {
+//!     mouse_keys.tick(&mut pointer);
+//! }
+//!
+//! let report = pointer.next_report(127, 127).unwrap();
+//! assert!(report.x > 0);
+//! assert_eq!(report.y, 0);
+//! ```
+use crate::device::mouse::RelativePointer;
+use crate::socd::Directions;
+
+/// Fixed point one, for key hold progress, stick deflection and speed fractions
+const ONE: u32 = 1 << 16;
+/// Tick rate, in ticks per second
+const TICKS_PER_SECOND: i32 = 1000;
+
+/// Curve from key hold time or stick deflection to speed
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub enum AccelerationCurve {
+    /// Speed increases linearly from the initial speed to the maximum speed
+    #[default]
+    Linear,
+    /// Speed increases slowly at first and quickly towards the maximum speed
+    ///
+    /// `expo` is the percentage, from 0 to 100, of cubic response blended with linear response,
+    /// as in the expo setting of radio control transmitters.
+    Exponential { expo: u8 },
+    /// Mouse keys as in QMK firmware
+    ///
+    /// Pressing a key moves `step` counts immediately, then nothing for `delay` ms, after which
+    /// the speed increases linearly from the initial speed to the maximum speed. Analog sticks
+    /// are treated as [`AccelerationCurve::Linear`].
+    Qmk { step: u8, delay: u16 },
+}
+
+impl AccelerationCurve {
+    /// Map `x` from 0 to [`ONE`] to a fraction of the speed range from 0 to [`ONE`]
+    #[allow(clippy::cast_possible_truncation)]
+    fn apply(self, x: u32) -> u32 {
+        let x = x.min(ONE);
+        match self {
+            Self::Linear | Self::Qmk { .. } => x,
+            Self::Exponential { expo } => {
+                let expo = u64::from(expo.min(100));
+                let x = u64::from(x);
+                let cubic = x * x / u64::from(ONE) * x / u64::from(ONE);
+                ((x * (100 - expo) + cubic * expo) / 100) as u32
+            }
+        }
+    }
+}
+
+/// Speed and acceleration of pointer or wheel motion
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MotionConfig {
+    pub curve: AccelerationCurve,
+    /// Speed when a key is first pressed or the stick leaves the dead zone, in counts per second
+    pub initial_speed: u32,
+    /// Speed with a key held for [`MotionConfig::time_to_max`] or the stick fully deflected, in
+    /// counts per second
+    pub max_speed: u32,
+    /// Time a key must be held to reach the maximum speed, in ms
+    pub time_to_max: u32,
+    /// Stick deflection ignored around the center, out of 32767
+    pub dead_zone: u16,
+}
+
+impl Default for MotionConfig {
+    /// Pointer motion, similar to the QMK mouse keys defaults
+    fn default() -> Self {
+        Self {
+            curve: AccelerationCurve::Linear,
+            initial_speed: 400,
+            max_speed: 4000,
+            time_to_max: 600,
+            dead_zone: 4000,
+        }
+    }
+}
+
+impl MotionConfig {
+    /// Wheel motion, similar to the QMK mouse keys defaults
+    #[must_use]
+    pub fn wheel() -> Self {
+        Self {
+            initial_speed: 12,
+            max_speed: 100,
+            time_to_max: 3000,
+            ..Self::default()
+        }
+    }
+
+    /// Speed, in counts per second, with a key held for `held` ms
+    fn key_speed(&self, held: u32) -> u32 {
+        let held = match self.curve {
+            AccelerationCurve::Qmk { delay, .. } => match held.checked_sub(delay.into()) {
+                Some(held) => held,
+                None => return 0,
+            },
+            _ => held,
+        };
+        let progress = if held >= self.time_to_max {
+            ONE
+        } else {
+            let progress = u64::from(held) * u64::from(ONE) / u64::from(self.time_to_max);
+            u32::try_from(progress).unwrap_or(ONE)
+        };
+        self.speed(progress)
+    }
+
+    /// Speed, in counts per second, for a stick deflection from -32767 to 32767
+    #[allow(clippy::cast_possible_wrap)]
+    fn stick_speed(&self, value: i16) -> i32 {
+        let dead_zone = u32::from(self.dead_zone.min(i16::MAX as u16 - 1));
+        let magnitude = u32::from(value.unsigned_abs()).min(i16::MAX as u32);
+        let Some(deflection) = magnitude.checked_sub(dead_zone).filter(|d| *d > 0) else {
+            return 0;
+        };
+        let progress = deflection * ONE / (i16::MAX as u32 - dead_zone);
+        let speed = self.speed(progress) as i32;
+        if value < 0 {
+            -speed
+        } else {
+            speed
+        }
+    }
+
+    fn speed(&self, progress: u32) -> u32 {
+        let range = u64::from(self.max_speed.saturating_sub(self.initial_speed));
+        let fraction = u64::from(self.curve.apply(progress));
+        let speed = u64::from(self.initial_speed) + range * fraction / u64::from(ONE);
+        u32::try_from(speed)
+            .unwrap_or(u32::MAX)
+            .min(i32::MAX as u32)
+    }
+}
+
+/// Motion along two axes from direction keys and a stick
+#[derive(Clone, Copy, Debug, Default)]
+struct Motion {
+    config: MotionConfig,
+    directions: Directions,
+    stick: (i16, i16),
+    /// Time the direction keys have been held, in ms
+    held: Option<u32>,
+    /// Motion not yet added to the pointer, in thousandths of a count
+    remainder: (i32, i32),
+}
+
+impl Motion {
+    fn new(config: MotionConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    fn set_directions(&mut self, directions: Directions) {
+        self.directions = directions;
+        if directions == Directions::default() {
+            self.held = None;
+        }
+    }
+
+    /// Motion for this tick, in counts, with left and up negative
+    #[allow(clippy::cast_possible_wrap)]
+    fn tick(&mut self) -> (i32, i32) {
+        let max_speed = self.config.max_speed.min(i32::MAX as u32) as i32;
+        let mut step = 0;
+        let key_speed = if self.directions == Directions::default() {
+            0
+        } else {
+            let held = self.held.map_or(0, |held| held.saturating_add(1));
+            if let (None, AccelerationCurve::Qmk { step: s, .. }) = (self.held, self.config.curve) {
+                step = i32::from(s);
+            }
+            self.held = Some(held);
+            self.config.key_speed(held) as i32
+        };
+        let (key_x, key_y) = self.directions.axes(-key_speed, 0, key_speed);
+        let (step_x, step_y) = self.directions.axes(-step, 0, step);
+        let axis = |key: i32, stick: i16, step: i32, remainder: &mut i32| {
+            let speed = key
+                .saturating_add(self.config.stick_speed(stick))
+                .clamp(-max_speed, max_speed);
+            let total = remainder.saturating_add(speed);
+            *remainder = total % TICKS_PER_SECOND;
+            total / TICKS_PER_SECOND + step
+        };
+        let (mut rx, mut ry) = self.remainder;
+        let motion = (
+            axis(key_x, self.stick.0, step_x, &mut rx),
+            axis(key_y, self.stick.1, step_y, &mut ry),
+        );
+        self.remainder = (rx, ry);
+        motion
+    }
+}
+
+/// Mouse keys and analog stick to pointer and wheel motion
+///
+/// Set the held direction keys and stick positions whenever they are sampled and call
+/// [`MouseKeys::tick`] every 1ms. Keys and stick can be used together, their speeds add up to at
+/// most the maximum speed. Wheel directions follow the pointer, up scrolls up and right pans
+/// right.
+#[derive(Clone, Copy, Debug)]
+pub struct MouseKeys {
+    pointer: Motion,
+    wheel: Motion,
+}
+
+impl Default for MouseKeys {
+    fn default() -> Self {
+        Self::new(MotionConfig::default(), MotionConfig::wheel())
+    }
+}
+
+impl MouseKeys {
+    #[must_use]
+    pub fn new(pointer: MotionConfig, wheel: MotionConfig) -> Self {
+        Self {
+            pointer: Motion::new(pointer),
+            wheel: Motion::new(wheel),
+        }
+    }
+
+    /// Set the held pointer direction keys
+    pub fn set_directions(&mut self, directions: Directions) {
+        self.pointer.set_directions(directions);
+    }
+
+    /// Set the held wheel direction keys
+    pub fn set_wheel_directions(&mut self, directions: Directions) {
+        self.wheel.set_directions(directions);
+    }
+
+    /// Set the pointer stick position, from -32767 to 32767 with left and up negative
+    pub fn set_stick(&mut self, x: i16, y: i16) {
+        self.pointer.stick = (x, y);
+    }
+
+    /// Set the wheel stick position, from -32767 to 32767 with left and up negative
+    pub fn set_wheel_stick(&mut self, x: i16, y: i16) {
+        self.wheel.stick = (x, y);
+    }
+
+    /// Change the pointer speed and acceleration
+    pub fn set_pointer_config(&mut self, config: MotionConfig) {
+        self.pointer.config = config;
+    }
+
+    /// Change the wheel speed and acceleration
+    pub fn set_wheel_config(&mut self, config: MotionConfig) {
+        self.wheel.config = config;
+    }
+
+    /// Release all keys, center the sticks and discard motion not yet added to the pointer
+    pub fn reset(&mut self) {
+        *self = Self::new(self.pointer.config, self.wheel.config);
+    }
+
+    /// Call every 1ms, adds the motion since the last tick to `pointer`
+    pub fn tick(&mut self, pointer: &mut RelativePointer) {
+        let (x, y) = self.pointer.tick();
+        let (horizontal, vertical) = self.wheel.tick();
+        pointer.move_by(x, y);
+        pointer.scroll(vertical.saturating_neg(), horizontal);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    const RIGHT: Directions = Directions {
+        up: false,
+        down: false,
+        left: false,
+        right: true,
+    };
+
+    fn run(mouse_keys: &mut MouseKeys, ms: u32) -> (i32, i32, i32, i32) {
+        let mut pointer = RelativePointer::new();
+        for _ in 0..ms {
+            mouse_keys.tick(&mut pointer);
+        }
+        pointer
+            .next_report(i32::MAX, i32::MAX)
+            .map_or((0, 0, 0, 0), |r| {
+                (r.x, r.y, r.vertical_wheel, r.horizontal_wheel)
+            })
+    }
+
+    #[test]
+    fn curves() {
+        assert_eq!(AccelerationCurve::Linear.apply(ONE / 2), ONE / 2);
+        assert_eq!(
+            AccelerationCurve::Exponential { expo: 100 }.apply(ONE / 2),
+            ONE / 8
+        );
+        assert_eq!(
+            AccelerationCurve::Exponential { expo: 0 }.apply(ONE / 2),
+            ONE / 2
+        );
+        assert_eq!(AccelerationCurve::Exponential { expo: 50 }.apply(ONE), ONE);
+    }
+
+    #[test]
+    fn long_time_to_max() {
+        let config = MotionConfig {
+            initial_speed: 0,
+            max_speed: 1000,
+            time_to_max: 200_000,
+            ..MotionConfig::default()
+        };
+        assert_eq!(config.key_speed(100_000), 500);
+        assert_eq!(config.key_speed(200_000), 1000);
+    }
+
+    #[test]
+    fn keys_reach_max_speed() {
+        let config = MotionConfig {
+            initial_speed: 1000,
+            max_speed: 2000,
+            time_to_max: 1000,
+            ..MotionConfig::default()
+        };
+        let mut mouse_keys = MouseKeys::new(config, MotionConfig::wheel());
+        mouse_keys.set_directions(RIGHT);
+        assert_eq!(run(&mut mouse_keys, 1), (1, 0, 0, 0));
+        run(&mut mouse_keys, 999);
+        assert_eq!(run(&mut mouse_keys, 1000), (2000, 0, 0, 0));
+
+        mouse_keys.set_directions(Directions::default());
+        assert_eq!(run(&mut mouse_keys, 1000), (0, 0, 0, 0));
+    }
+
+    #[test]
+    fn qmk_step_and_delay() {
+        let config = MotionConfig {
+            curve: AccelerationCurve::Qmk {
+                step: 8,
+                delay: 100,
+            },
+            ..MotionConfig::default()
+        };
+        let mut mouse_keys = MouseKeys::new(config, MotionConfig::wheel());
+        mouse_keys.set_directions(Directions {
+            up: true,
+            ..Default::default()
+        });
+        assert_eq!(run(&mut mouse_keys, 100), (0, -8, 0, 0));
+        assert!(run(&mut mouse_keys, 100).1 < 0);
+    }
+
+    #[test]
+    fn stick_and_wheel() {
+        let mut mouse_keys = MouseKeys::default();
+        mouse_keys.set_stick(1000, -i16::MAX);
+        mouse_keys.set_wheel_directions(Directions {
+            up: true,
+            ..Default::default()
+        });
+        let (x, y, vertical, horizontal) = run(&mut mouse_keys, 1000);
+        assert_eq!((x, y), (0, -4000));
+        assert!(vertical > 0);
+        assert_eq!(horizontal, 0);
+    }
+}
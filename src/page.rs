@@ -14,6 +14,74 @@ use packed_struct::prelude::*;
 // * Squash spaces and punctuation: [^\w=,]
 // * Unmangle reserved: (.*)(reserved)=(.*) - //0x$1-$3 $2
 
+/// Usage page identifiers
+///
+/// See [Universal Serial Bus (USB) HID Usage Tables Version 1.12](<https://www.usb.org/sites/default/files/documents/hut1_12v2.pdf>):
+/// Section 3 Usage Pages
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    PrimitiveEnum,
+    IntoPrimitive,
+    FromPrimitive,
+)]
+#[repr(u16)]
+pub enum UsagePage {
+    #[num_enum(default)]
+    Undefined = 0x00,
+    GenericDesktop = 0x01,
+    Simulation = 0x02,
+    VirtualReality = 0x03,
+    Sport = 0x04,
+    Game = 0x05,
+    GenericDevice = 0x06,
+    Keyboard = 0x07,
+    Leds = 0x08,
+    Button = 0x09,
+    Ordinal = 0x0A,
+    Telephony = 0x0B,
+    Consumer = 0x0C,
+    Digitizer = 0x0D,
+    //0x0E Reserved
+    PhysicalInterfaceDevice = 0x0F,
+    Unicode = 0x10,
+    //0x11-0x13 Reserved
+    AlphanumericDisplay = 0x14,
+    //0x15-0x1F Reserved
+    Sensors = 0x20,
+    //0x21-0x3F Reserved
+    MedicalInstruments = 0x40,
+    //0x41-0x58 Reserved
+    LightingAndIllumination = 0x59,
+    //0x5A-0x7F Reserved
+    //0x80-0x83 Monitor pages
+    PowerDevice = 0x84,
+    BatterySystem = 0x85,
+    //0x86-0x87 Power pages
+    //0x88-0x8B Reserved
+    BarCodeScanner = 0x8C,
+    Scale = 0x8D,
+    MagneticStripeReading = 0x8E,
+    //0x8F Reserved Point of Sale pages
+    Camera = 0x90,
+    Arcade = 0x91,
+    //0x92-0xFEFF Reserved
+    //0xFF00-0xFFFF Vendor-defined
+}
+
+impl Default for UsagePage {
+    fn default() -> Self {
+        Self::Undefined
+    }
+}
+
 /// LEDs usage page
 ///
 /// See [Universal Serial Bus (USB) HID Usage Tables Version 1.12](<https://www.usb.org/sites/default/files/documents/hut1_12v2.pdf>):
@@ -562,7 +630,14 @@ pub enum Desktop {
     Keypad = 0x07,
     MultiAxisController = 0x08,
     TabletPcSystemControls = 0x09,
-    //0x0A-0x2F Reserved
+    WaterCoolingDevice = 0x0A,
+    ComputerChassisDevice = 0x0B,
+    WirelessRadioControls = 0x0C,
+    PortableDeviceControl = 0x0D,
+    SystemMultiAxisController = 0x0E,
+    SpatialController = 0x0F,
+    AssistiveControl = 0x10,
+    //0x11-0x2F Reserved
     X = 0x30,
     Y = 0x31,
     Z = 0x32,
@@ -1069,3 +1144,876 @@ impl Default for Telephony {
         Self::Unassigned
     }
 }
+
+/// Physical Interface Device usage page
+///
+/// See [Device Class Definition for Physical Interface Devices (PID) Version 1.0](<https://www.usb.org/sites/default/files/documents/pid1_01.pdf>):
+/// Section 5 PID Usages (0x0F)
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    PrimitiveEnum,
+    IntoPrimitive,
+    FromPrimitive,
+)]
+#[repr(u8)]
+pub enum Pid {
+    #[num_enum(default)]
+    Undefined = 0x00,
+    PhysicalInterfaceDevice = 0x01,
+    //0x02-0x1F Reserved
+    Normal = 0x20,
+    SetEffectReport = 0x21,
+    EffectBlockIndex = 0x22,
+    ParameterBlockOffset = 0x23,
+    RomFlag = 0x24,
+    EffectType = 0x25,
+    EtConstantForce = 0x26,
+    EtRamp = 0x27,
+    EtCustomForceData = 0x28,
+    //0x29-0x2F Reserved
+    EtSquare = 0x30,
+    EtSine = 0x31,
+    EtTriangle = 0x32,
+    EtSawtoothUp = 0x33,
+    EtSawtoothDown = 0x34,
+    //0x35-0x3F Reserved
+    EtSpring = 0x40,
+    EtDamper = 0x41,
+    EtInertia = 0x42,
+    EtFriction = 0x43,
+    //0x44-0x4F Reserved
+    Duration = 0x50,
+    SamplePeriod = 0x51,
+    Gain = 0x52,
+    TriggerButton = 0x53,
+    TriggerRepeatInterval = 0x54,
+    AxesEnable = 0x55,
+    DirectionEnable = 0x56,
+    Direction = 0x57,
+    TypeSpecificBlockOffset = 0x58,
+    BlockType = 0x59,
+    SetEnvelopeReport = 0x5A,
+    AttackLevel = 0x5B,
+    AttackTime = 0x5C,
+    FadeLevel = 0x5D,
+    FadeTime = 0x5E,
+    SetConditionReport = 0x5F,
+    CpOffset = 0x60,
+    PositiveCoefficient = 0x61,
+    NegativeCoefficient = 0x62,
+    PositiveSaturation = 0x63,
+    NegativeSaturation = 0x64,
+    DeadBand = 0x65,
+    DownloadForceSample = 0x66,
+    IsochCustomForceEnable = 0x67,
+    CustomForceDataReport = 0x68,
+    CustomForceData = 0x69,
+    CustomForceVendorDefinedData = 0x6A,
+    SetCustomForceReport = 0x6B,
+    CustomForceDataOffset = 0x6C,
+    SampleCount = 0x6D,
+    SetPeriodicReport = 0x6E,
+    Offset = 0x6F,
+    Magnitude = 0x70,
+    Phase = 0x71,
+    Period = 0x72,
+    SetConstantForceReport = 0x73,
+    SetRampForceReport = 0x74,
+    RampStart = 0x75,
+    RampEnd = 0x76,
+    EffectOperationReport = 0x77,
+    EffectOperation = 0x78,
+    OpEffectStart = 0x79,
+    OpEffectStartSolo = 0x7A,
+    OpEffectStop = 0x7B,
+    LoopCount = 0x7C,
+    DeviceGainReport = 0x7D,
+    DeviceGain = 0x7E,
+    PidPoolReport = 0x7F,
+    RamPoolSize = 0x80,
+    RomPoolSize = 0x81,
+    RomEffectBlockCount = 0x82,
+    SimultaneousEffectsMax = 0x83,
+    PoolAlignment = 0x84,
+    PidPoolMoveReport = 0x85,
+    MoveSource = 0x86,
+    MoveDestination = 0x87,
+    MoveLength = 0x88,
+    PidBlockLoadReport = 0x89,
+    //0x8A Reserved
+    BlockLoadStatus = 0x8B,
+    BlockLoadSuccess = 0x8C,
+    BlockLoadFull = 0x8D,
+    BlockLoadError = 0x8E,
+    BlockHandle = 0x8F,
+    PidBlockFreeReport = 0x90,
+    TypeSpecificBlockHandle = 0x91,
+    PidStateReport = 0x92,
+    //0x93 Reserved
+    EffectPlaying = 0x94,
+    PidDeviceControlReport = 0x95,
+    PidDeviceControl = 0x96,
+    DcEnableActuators = 0x97,
+    DcDisableActuators = 0x98,
+    DcStopAllEffects = 0x99,
+    DcDeviceReset = 0x9A,
+    DcDevicePause = 0x9B,
+    DcDeviceContinue = 0x9C,
+    //0x9D-0x9E Reserved
+    DevicePaused = 0x9F,
+    ActuatorsEnabled = 0xA0,
+    //0xA1-0xA3 Reserved
+    SafetySwitch = 0xA4,
+    ActuatorOverrideSwitch = 0xA5,
+    ActuatorPower = 0xA6,
+    StartDelay = 0xA7,
+    ParameterBlockSize = 0xA8,
+    DeviceManagedPool = 0xA9,
+    SharedParameterBlocks = 0xAA,
+    CreateNewEffectReport = 0xAB,
+    RamPoolAvailable = 0xAC,
+    //0xAD-0xFFFF Reserved
+}
+impl Default for Pid {
+    fn default() -> Self {
+        Self::Undefined
+    }
+}
+
+/// Digitizers usage page
+///
+/// See [HID Usage Tables for USB Version 1.3](<https://usb.org/sites/default/files/hut1_3_0.pdf>):
+/// Section 16 Digitizers Page (0x0D)
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    PrimitiveEnum,
+    IntoPrimitive,
+    FromPrimitive,
+)]
+#[repr(u8)]
+pub enum Digitizer {
+    #[num_enum(default)]
+    Undefined = 0x00,
+    Digitizer = 0x01,
+    Pen = 0x02,
+    LightPen = 0x03,
+    TouchScreen = 0x04,
+    TouchPad = 0x05,
+    Whiteboard = 0x06,
+    CoordinateMeasuringMachine = 0x07,
+    Digitizer3D = 0x08,
+    StereoPlotter = 0x09,
+    ArticulatedArm = 0x0A,
+    Armature = 0x0B,
+    MultiplePointDigitizer = 0x0C,
+    FreeSpaceWand = 0x0D,
+    DeviceConfiguration = 0x0E,
+    CapacitiveHeatMapDigitizer = 0x0F,
+    //0x10-0x1F Reserved
+    Stylus = 0x20,
+    Puck = 0x21,
+    Finger = 0x22,
+    DeviceSettings = 0x23,
+    CharacterGesture = 0x24,
+    //0x25-0x2F Reserved
+    TipPressure = 0x30,
+    BarrelPressure = 0x31,
+    InRange = 0x32,
+    Touch = 0x33,
+    Untouch = 0x34,
+    Tap = 0x35,
+    Quality = 0x36,
+    DataValid = 0x37,
+    TransducerIndex = 0x38,
+    TabletFunctionKeys = 0x39,
+    ProgramChangeKeys = 0x3A,
+    BatteryStrength = 0x3B,
+    Invert = 0x3C,
+    XTilt = 0x3D,
+    YTilt = 0x3E,
+    Azimuth = 0x3F,
+    Altitude = 0x40,
+    Twist = 0x41,
+    TipSwitch = 0x42,
+    SecondaryTipSwitch = 0x43,
+    BarrelSwitch = 0x44,
+    Eraser = 0x45,
+    TabletPick = 0x46,
+    Confidence = 0x47,
+    Width = 0x48,
+    Height = 0x49,
+    //0x4A-0x50 Reserved
+    ContactIdentifier = 0x51,
+    DeviceMode = 0x52,
+    DeviceIdentifier = 0x53,
+    ContactCount = 0x54,
+    ContactCountMaximum = 0x55,
+    ScanTime = 0x56,
+    SurfaceSwitch = 0x57,
+    ButtonSwitch = 0x58,
+    PadType = 0x59,
+    SecondaryBarrelSwitch = 0x5A,
+    TransducerSerialNumber = 0x5B,
+    //0x5C-0xFF not listed, see HUT 1.3 for the remaining Digitizer usages
+}
+impl Default for Digitizer {
+    fn default() -> Self {
+        Self::Undefined
+    }
+}
+
+/// Button usage page
+///
+/// See [Universal Serial Bus (USB) HID Usage Tables Version 1.12](<https://www.usb.org/sites/default/files/documents/hut1_12v2.pdf>):
+/// Section 12 Button Page (0x09)
+///
+/// The specification names the usages Button 1 to Button 65535, the first five are named after
+/// their conventional role on a mouse
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    PrimitiveEnum,
+    IntoPrimitive,
+    FromPrimitive,
+)]
+#[repr(u8)]
+pub enum Button {
+    #[num_enum(default)]
+    NoButtonPressed = 0x00,
+    Primary = 0x01,
+    Secondary = 0x02,
+    Tertiary = 0x03,
+    Back = 0x04,
+    Forward = 0x05,
+    Button6 = 0x06,
+    Button7 = 0x07,
+    Button8 = 0x08,
+    Button9 = 0x09,
+    Button10 = 0x0A,
+    Button11 = 0x0B,
+    Button12 = 0x0C,
+    Button13 = 0x0D,
+    Button14 = 0x0E,
+    Button15 = 0x0F,
+    Button16 = 0x10,
+    //0x11-0xFFFF Button 17 to Button 65535
+}
+impl Default for Button {
+    fn default() -> Self {
+        Self::NoButtonPressed
+    }
+}
+
+/// Generic Device Controls usage page
+///
+/// See [Universal Serial Bus (USB) HID Usage Tables Version 1.12](<https://www.usb.org/sites/default/files/documents/hut1_12v2.pdf>):
+/// Section 9 Generic Device Controls Page (0x06)
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    PrimitiveEnum,
+    IntoPrimitive,
+    FromPrimitive,
+)]
+#[repr(u8)]
+pub enum GenericDevice {
+    #[num_enum(default)]
+    Undefined = 0x00,
+    BackgroundNonuserControls = 0x01,
+    //0x02-0x1F Reserved
+    BatteryStrength = 0x20,
+    WirelessChannel = 0x21,
+    WirelessId = 0x22,
+    DiscoverWirelessControl = 0x23,
+    SecurityCodeCharacterEntered = 0x24,
+    SecurityCodeCharacterErased = 0x25,
+    SecurityCodeCleared = 0x26,
+    SequenceId = 0x27,
+    SequenceIdReset = 0x28,
+    RfSignalStrength = 0x29,
+    SoftwareVersion = 0x2A,
+    ProtocolVersion = 0x2B,
+    HardwareVersion = 0x2C,
+    Major = 0x2D,
+    Minor = 0x2E,
+    Revision = 0x2F,
+    Handedness = 0x30,
+    EitherHand = 0x31,
+    LeftHand = 0x32,
+    RightHand = 0x33,
+    BothHands = 0x34,
+    //0x35-0x3F Reserved
+    GripPoseOffset = 0x40,
+    PointerPoseOffset = 0x41,
+    //0x42-0xFF Reserved
+}
+impl Default for GenericDevice {
+    fn default() -> Self {
+        Self::Undefined
+    }
+}
+
+/// Sensors usage page
+///
+/// Sensor types, events, properties, selectors and the data fields of the motion, environmental
+/// and light sensors. Data field usages can be combined with a data field modifier
+/// in the upper 4 bits, e.g. [`Sensors::ChangeSensitivityAbsolute`].
+///
+/// See [HID Usage Tables for USB Version 1.3](<https://usb.org/sites/default/files/hut1_3_0.pdf>):
+/// Section 22 Sensors Page (0x20)
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    PrimitiveEnum,
+    IntoPrimitive,
+    FromPrimitive,
+)]
+#[repr(u16)]
+pub enum Sensors {
+    #[num_enum(default)]
+    Undefined = 0x00,
+    Sensor = 0x01,
+    //0x02-0x0F Reserved
+    Biometric = 0x10,
+    //0x11-0x1F Biometric sensors
+    Electrical = 0x20,
+    //0x21-0x2F Electrical sensors
+    Environmental = 0x30,
+    EnvironmentalAtmosphericPressure = 0x31,
+    EnvironmentalHumidity = 0x32,
+    EnvironmentalTemperature = 0x33,
+    //0x34-0x3F Environmental sensors
+    Light = 0x40,
+    LightAmbientLight = 0x41,
+    LightConsumerInfrared = 0x42,
+    //0x43-0x4F Light sensors
+    Location = 0x50,
+    //0x51-0x5F Location sensors
+    Mechanical = 0x60,
+    //0x61-0x6F Mechanical sensors
+    Motion = 0x70,
+    MotionAccelerometer1D = 0x71,
+    MotionAccelerometer2D = 0x72,
+    MotionAccelerometer3D = 0x73,
+    MotionGyrometer1D = 0x74,
+    MotionGyrometer2D = 0x75,
+    MotionGyrometer3D = 0x76,
+    //0x77-0x7F Motion sensors
+    Orientation = 0x80,
+    OrientationCompass1D = 0x81,
+    OrientationCompass2D = 0x82,
+    OrientationCompass3D = 0x83,
+    OrientationInclinometer1D = 0x84,
+    OrientationInclinometer2D = 0x85,
+    OrientationInclinometer3D = 0x86,
+    //0x87-0x1FF Sensor types
+    Event = 0x200,
+    EventSensorState = 0x201,
+    EventSensorEvent = 0x202,
+    //0x203-0x2FF Reserved
+    Property = 0x300,
+    PropertyFriendlyName = 0x301,
+    PropertyPersistentUniqueId = 0x302,
+    PropertySensorStatus = 0x303,
+    PropertyMinimumReportInterval = 0x304,
+    PropertySensorManufacturer = 0x305,
+    PropertySensorModel = 0x306,
+    PropertySensorSerialNumber = 0x307,
+    PropertySensorDescription = 0x308,
+    PropertySensorConnectionType = 0x309,
+    PropertySensorDevicePath = 0x30A,
+    PropertyHardwareRevision = 0x30B,
+    PropertyFirmwareVersion = 0x30C,
+    PropertyReleaseDate = 0x30D,
+    PropertyReportInterval = 0x30E,
+    PropertyChangeSensitivityAbsolute = 0x30F,
+    PropertyChangeSensitivityPercentOfRange = 0x310,
+    PropertyChangeSensitivityPercentRelative = 0x311,
+    PropertyAccuracy = 0x312,
+    PropertyResolution = 0x313,
+    PropertyMaximum = 0x314,
+    PropertyMinimum = 0x315,
+    PropertyReportingState = 0x316,
+    PropertySamplingRate = 0x317,
+    PropertyResponseCurve = 0x318,
+    PropertyPowerState = 0x319,
+    //0x31A-0x3FF Properties
+    DataFieldLocation = 0x400,
+    //0x401-0x42F Location data fields
+    DataFieldEnvironmental = 0x430,
+    DataFieldAtmosphericPressure = 0x431,
+    //0x432 Reserved
+    DataFieldRelativeHumidity = 0x433,
+    DataFieldTemperature = 0x434,
+    //0x435-0x44F Environmental data fields
+    DataFieldMotion = 0x450,
+    DataFieldMotionState = 0x451,
+    DataFieldAcceleration = 0x452,
+    DataFieldAccelerationAxisX = 0x453,
+    DataFieldAccelerationAxisY = 0x454,
+    DataFieldAccelerationAxisZ = 0x455,
+    DataFieldAngularVelocity = 0x456,
+    DataFieldAngularVelocityAboutXAxis = 0x457,
+    DataFieldAngularVelocityAboutYAxis = 0x458,
+    DataFieldAngularVelocityAboutZAxis = 0x459,
+    //0x45A-0x4CF Motion, orientation, mechanical and biometric data fields
+    DataFieldLight = 0x4D0,
+    DataFieldIlluminance = 0x4D1,
+    DataFieldColorTemperature = 0x4D2,
+    //0x4D3-0x7FF Data fields
+    SensorStateUndefined = 0x800,
+    SensorStateReady = 0x801,
+    SensorStateNotAvailable = 0x802,
+    SensorStateNoData = 0x803,
+    SensorStateInitializing = 0x804,
+    SensorStateAccessDenied = 0x805,
+    SensorStateError = 0x806,
+    //0x807-0x80F Reserved
+    SensorEventUnknown = 0x810,
+    SensorEventStateChanged = 0x811,
+    SensorEventPropertyChanged = 0x812,
+    SensorEventDataUpdated = 0x813,
+    SensorEventPollResponse = 0x814,
+    SensorEventChangeSensitivity = 0x815,
+    //0x816-0x82F Sensor events
+    ConnectionTypePcIntegrated = 0x830,
+    ConnectionTypePcAttached = 0x831,
+    ConnectionTypePcExternal = 0x832,
+    //0x833-0x83F Reserved
+    ReportingStateReportNoEvents = 0x840,
+    ReportingStateReportAllEvents = 0x841,
+    ReportingStateReportThresholdEvents = 0x842,
+    ReportingStateWakeOnNoEvents = 0x843,
+    ReportingStateWakeOnAllEvents = 0x844,
+    ReportingStateWakeOnThresholdEvents = 0x845,
+    //0x846-0x84F Reserved
+    PowerStateUndefined = 0x850,
+    PowerStateD0FullPower = 0x851,
+    PowerStateD1LowPower = 0x852,
+    PowerStateD2StandbyWithWake = 0x853,
+    PowerStateD3SleepWithWake = 0x854,
+    PowerStateD4PowerOff = 0x855,
+    //0x856-0xFFF Selectors
+    /// Data field modifier, to be combined with a data field usage
+    ChangeSensitivityAbsolute = 0x1000,
+    /// Data field modifier, to be combined with a data field usage
+    Maximum = 0x2000,
+    /// Data field modifier, to be combined with a data field usage
+    Minimum = 0x3000,
+    /// Data field modifier, to be combined with a data field usage
+    Accuracy = 0x4000,
+    /// Data field modifier, to be combined with a data field usage
+    Resolution = 0x5000,
+    /// Data field modifier, to be combined with a data field usage
+    ThresholdHigh = 0x6000,
+    /// Data field modifier, to be combined with a data field usage
+    ThresholdLow = 0x7000,
+    /// Data field modifier, to be combined with a data field usage
+    CalibrationOffset = 0x8000,
+    /// Data field modifier, to be combined with a data field usage
+    CalibrationMultiplier = 0x9000,
+    /// Data field modifier, to be combined with a data field usage
+    ReportInterval = 0xA000,
+    /// Data field modifier, to be combined with a data field usage
+    FrequencyMax = 0xB000,
+    /// Data field modifier, to be combined with a data field usage
+    PeriodMax = 0xC000,
+    /// Data field modifier, to be combined with a data field usage
+    ChangeSensitivityPercentOfRange = 0xD000,
+    /// Data field modifier, to be combined with a data field usage
+    ChangeSensitivityPercentRelative = 0xE000,
+    /// Data field modifier, to be combined with a data field usage
+    VendorReserved = 0xF000,
+}
+impl Default for Sensors {
+    fn default() -> Self {
+        Self::Undefined
+    }
+}
+
+/// Lighting and Illumination usage page
+///
+/// See [HID Usage Tables for USB Version 1.3](<https://usb.org/sites/default/files/hut1_3_0.pdf>):
+/// Section 26 Lighting and Illumination Page (0x59)
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    PrimitiveEnum,
+    IntoPrimitive,
+    FromPrimitive,
+)]
+#[repr(u8)]
+pub enum LightingAndIllumination {
+    #[num_enum(default)]
+    Undefined = 0x00,
+    LampArray = 0x01,
+    LampArrayAttributesReport = 0x02,
+    LampCount = 0x03,
+    BoundingBoxWidthInMicrometers = 0x04,
+    BoundingBoxHeightInMicrometers = 0x05,
+    BoundingBoxDepthInMicrometers = 0x06,
+    LampArrayKind = 0x07,
+    MinUpdateIntervalInMicroseconds = 0x08,
+    //0x09-0x1F Reserved
+    LampAttributesRequestReport = 0x20,
+    LampId = 0x21,
+    LampAttributesResponseReport = 0x22,
+    PositionXInMicrometers = 0x23,
+    PositionYInMicrometers = 0x24,
+    PositionZInMicrometers = 0x25,
+    LampPurposes = 0x26,
+    UpdateLatencyInMicroseconds = 0x27,
+    RedLevelCount = 0x28,
+    GreenLevelCount = 0x29,
+    BlueLevelCount = 0x2A,
+    IntensityLevelCount = 0x2B,
+    IsProgrammable = 0x2C,
+    InputBinding = 0x2D,
+    //0x2E-0x4F Reserved
+    LampMultiUpdateReport = 0x50,
+    RedUpdateChannel = 0x51,
+    GreenUpdateChannel = 0x52,
+    BlueUpdateChannel = 0x53,
+    IntensityUpdateChannel = 0x54,
+    LampUpdateFlags = 0x55,
+    //0x56-0x5F Reserved
+    LampRangeUpdateReport = 0x60,
+    LampIdStart = 0x61,
+    LampIdEnd = 0x62,
+    //0x63-0x6F Reserved
+    LampArrayControlReport = 0x70,
+    AutonomousMode = 0x71,
+    //0x72-0xFF Reserved
+}
+impl Default for LightingAndIllumination {
+    fn default() -> Self {
+        Self::Undefined
+    }
+}
+
+/// Power Device usage page
+///
+/// See [Universal Serial Bus Usage Tables for HID Power Devices Release 1.0](<https://www.usb.org/sites/default/files/pdcv10.pdf>):
+/// Section 4.1 Power Device Page (0x84)
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    PrimitiveEnum,
+    IntoPrimitive,
+    FromPrimitive,
+)]
+#[repr(u8)]
+pub enum PowerDevice {
+    #[num_enum(default)]
+    Undefined = 0x00,
+    IName = 0x01,
+    PresentStatus = 0x02,
+    ChangedStatus = 0x03,
+    Ups = 0x04,
+    PowerSupply = 0x05,
+    //0x06-0x0F Reserved
+    BatterySystem = 0x10,
+    BatterySystemId = 0x11,
+    Battery = 0x12,
+    BatteryId = 0x13,
+    Charger = 0x14,
+    ChargerId = 0x15,
+    PowerConverter = 0x16,
+    PowerConverterId = 0x17,
+    OutletSystem = 0x18,
+    OutletSystemId = 0x19,
+    Input = 0x1A,
+    InputId = 0x1B,
+    Output = 0x1C,
+    OutputId = 0x1D,
+    Flow = 0x1E,
+    FlowId = 0x1F,
+    Outlet = 0x20,
+    OutletId = 0x21,
+    Gang = 0x22,
+    GangId = 0x23,
+    PowerSummary = 0x24,
+    PowerSummaryId = 0x25,
+    //0x26-0x2F Reserved
+    Voltage = 0x30,
+    Current = 0x31,
+    Frequency = 0x32,
+    ApparentPower = 0x33,
+    ActivePower = 0x34,
+    PercentLoad = 0x35,
+    Temperature = 0x36,
+    Humidity = 0x37,
+    BadCount = 0x38,
+    //0x39-0x3F Reserved
+    ConfigVoltage = 0x40,
+    ConfigCurrent = 0x41,
+    ConfigFrequency = 0x42,
+    ConfigApparentPower = 0x43,
+    ConfigActivePower = 0x44,
+    ConfigPercentLoad = 0x45,
+    ConfigTemperature = 0x46,
+    ConfigHumidity = 0x47,
+    //0x48-0x4F Reserved
+    SwitchOnControl = 0x50,
+    SwitchOffControl = 0x51,
+    ToggleControl = 0x52,
+    LowVoltageTransfer = 0x53,
+    HighVoltageTransfer = 0x54,
+    DelayBeforeReboot = 0x55,
+    DelayBeforeStartup = 0x56,
+    DelayBeforeShutdown = 0x57,
+    Test = 0x58,
+    ModuleReset = 0x59,
+    AudibleAlarmControl = 0x5A,
+    //0x5B-0x5F Reserved
+    Present = 0x60,
+    Good = 0x61,
+    InternalFailure = 0x62,
+    VoltageOutOfRange = 0x63,
+    FrequencyOutOfRange = 0x64,
+    Overload = 0x65,
+    OverCharged = 0x66,
+    OverTemperature = 0x67,
+    ShutdownRequested = 0x68,
+    ShutdownImminent = 0x69,
+    //0x6A Reserved
+    SwitchOnOff = 0x6B,
+    Switchable = 0x6C,
+    Used = 0x6D,
+    Boost = 0x6E,
+    Buck = 0x6F,
+    Initialized = 0x70,
+    Tested = 0x71,
+    AwaitingPower = 0x72,
+    CommunicationLost = 0x73,
+    //0x74-0xFC Reserved
+    IManufacturer = 0xFD,
+    IProduct = 0xFE,
+    ISerialNumber = 0xFF,
+}
+impl Default for PowerDevice {
+    fn default() -> Self {
+        Self::Undefined
+    }
+}
+
+/// Battery System usage page
+///
+/// See [Universal Serial Bus Usage Tables for HID Power Devices Release 1.0](<https://www.usb.org/sites/default/files/pdcv10.pdf>):
+/// Section 4.2 Battery System Page (0x85)
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    PrimitiveEnum,
+    IntoPrimitive,
+    FromPrimitive,
+)]
+#[repr(u8)]
+pub enum BatterySystem {
+    #[num_enum(default)]
+    Undefined = 0x00,
+    SmbBatteryMode = 0x01,
+    SmbBatteryStatus = 0x02,
+    SmbAlarmWarning = 0x03,
+    SmbChargerMode = 0x04,
+    SmbChargerStatus = 0x05,
+    SmbChargerSpecInfo = 0x06,
+    SmbSelectorState = 0x07,
+    SmbSelectorPresets = 0x08,
+    SmbSelectorInfo = 0x09,
+    //0x0A-0x0F Reserved
+    OptionalMfgFunction1 = 0x10,
+    OptionalMfgFunction2 = 0x11,
+    OptionalMfgFunction3 = 0x12,
+    OptionalMfgFunction4 = 0x13,
+    OptionalMfgFunction5 = 0x14,
+    ConnectionToSmBus = 0x15,
+    OutputConnection = 0x16,
+    ChargerConnection = 0x17,
+    BatteryInsertion = 0x18,
+    UseNext = 0x19,
+    OkToUse = 0x1A,
+    BatterySupported = 0x1B,
+    SelectorRevision = 0x1C,
+    ChargingIndicator = 0x1D,
+    //0x1E-0x27 Reserved
+    ManufacturerAccess = 0x28,
+    RemainingCapacityLimit = 0x29,
+    RemainingTimeLimit = 0x2A,
+    AtRate = 0x2B,
+    CapacityMode = 0x2C,
+    BroadcastToCharger = 0x2D,
+    PrimaryBattery = 0x2E,
+    ChargeController = 0x2F,
+    //0x30-0x3F Reserved
+    TerminateCharge = 0x40,
+    TerminateDischarge = 0x41,
+    BelowRemainingCapacityLimit = 0x42,
+    RemainingTimeLimitExpired = 0x43,
+    Charging = 0x44,
+    Discharging = 0x45,
+    FullyCharged = 0x46,
+    FullyDischarged = 0x47,
+    ConditioningFlag = 0x48,
+    AtRateOk = 0x49,
+    SmbErrorCode = 0x4A,
+    NeedReplacement = 0x4B,
+    //0x4C-0x5F Reserved
+    AtRateTimeToFull = 0x60,
+    AtRateTimeToEmpty = 0x61,
+    AverageCurrent = 0x62,
+    MaxError = 0x63,
+    RelativeStateOfCharge = 0x64,
+    AbsoluteStateOfCharge = 0x65,
+    RemainingCapacity = 0x66,
+    FullChargeCapacity = 0x67,
+    RunTimeToEmpty = 0x68,
+    AverageTimeToEmpty = 0x69,
+    AverageTimeToFull = 0x6A,
+    CycleCount = 0x6B,
+    //0x6C-0x7F Reserved
+    BattPackModelLevel = 0x80,
+    InternalChargeController = 0x81,
+    PrimaryBatterySupport = 0x82,
+    DesignCapacity = 0x83,
+    SpecificationInfo = 0x84,
+    ManufactureDate = 0x85,
+    SerialNumber = 0x86,
+    IManufacturerName = 0x87,
+    IDeviceName = 0x88,
+    IDeviceChemistry = 0x89,
+    ManufacturerData = 0x8A,
+    Rechargeable = 0x8B,
+    WarningCapacityLimit = 0x8C,
+    CapacityGranularity1 = 0x8D,
+    CapacityGranularity2 = 0x8E,
+    IOemInformation = 0x8F,
+    //0x90-0xBF Reserved
+    InhibitCharge = 0xC0,
+    EnablePolling = 0xC1,
+    ResetToZero = 0xC2,
+    //0xC3-0xCF Reserved
+    AcPresent = 0xD0,
+    BatteryPresent = 0xD1,
+    PowerFail = 0xD2,
+    AlarmInhibited = 0xD3,
+    ThermistorUnderRange = 0xD4,
+    ThermistorHot = 0xD5,
+    ThermistorCold = 0xD6,
+    ThermistorOverRange = 0xD7,
+    VoltageOutOfRange = 0xD8,
+    CurrentOutOfRange = 0xD9,
+    CurrentNotRegulated = 0xDA,
+    VoltageNotRegulated = 0xDB,
+    MasterMode = 0xDC,
+    //0xDD-0xEF Reserved
+    ChargerSelectorSupport = 0xF0,
+    ChargerSpec = 0xF1,
+    Level2 = 0xF2,
+    Level3 = 0xF3,
+    //0xF4-0xFF Reserved
+}
+impl Default for BatterySystem {
+    fn default() -> Self {
+        Self::Undefined
+    }
+}
+
+/// A usage from one of the usage page enums
+pub trait Usage: Copy {
+    /// Usage page of the usage
+    const PAGE: UsagePage;
+
+    /// Usage ID within [`Usage::PAGE`]
+    fn id(self) -> u16;
+}
+
+macro_rules! impl_usage {
+    ($($usage:ty => $page:ident),* $(,)?) => {
+        $(
+            impl Usage for $usage {
+                const PAGE: UsagePage = UsagePage::$page;
+
+                fn id(self) -> u16 {
+                    self as u16
+                }
+            }
+        )*
+    };
+}
+
+impl_usage!(
+    Desktop => GenericDesktop,
+    Simulation => Simulation,
+    Game => Game,
+    GenericDevice => GenericDevice,
+    Keyboard => Keyboard,
+    Leds => Leds,
+    Button => Button,
+    Telephony => Telephony,
+    Consumer => Consumer,
+    Digitizer => Digitizer,
+    Pid => PhysicalInterfaceDevice,
+    Sensors => Sensors,
+    LightingAndIllumination => LightingAndIllumination,
+    PowerDevice => PowerDevice,
+    BatterySystem => BatterySystem,
+);
@@ -0,0 +1,273 @@
+//! Simultaneous Opposite Cardinal Direction (SOCD) cleaning
+//!
+//! Resolves raw up/down/left/right inputs, e.g. from a leverless fighting game controller, so that
+//! opposite directions are never reported together. The cleaned directions can then be reported
+//! as a [`HatSwitch`] or as axis values for [`JoystickReport`](crate::device::joystick::JoystickReport)
+//! and [`GamepadReport`](crate::device::gamepad::GamepadReport).
+//!
+//! ```
+//! use usbd_human_interface_device::device::joystick::HatSwitch;
+//! use usbd_human_interface_device::socd::{Directions, SocdMode, SocdResolver};
+//!
+//! let mut socd = SocdResolver::new(SocdMode::LastInputWins);
+//!
+//! socd.update(Directions { left: true, ..Default::default() });
+//! socd.tick();
+//! socd.update(Directions { left: true, right: true, ..Default::default() });
+//!
+//! assert_eq!(socd.hat_switch(), HatSwitch::E);
+//! ```
+use crate::device::joystick::HatSwitch;
+
+/// SOCD resolution mode
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub enum SocdMode {
+    /// Opposite directions cancel each other out
+    #[default]
+    Neutral,
+    /// The most recently pressed direction wins
+    LastInputWins,
+    /// The first pressed direction wins
+    FirstInputWins,
+    /// Up wins over down, left and right cancel each other out
+    UpPriority,
+}
+
+/// Directional inputs
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub struct Directions {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+}
+
+impl Directions {
+    /// [`HatSwitch`] position for these directions
+    #[must_use]
+    pub fn hat_switch(self) -> HatSwitch {
+        HatSwitch::from_directions(self.up, self.down, self.left, self.right)
+    }
+
+    /// Horizontal and vertical axis values for these directions
+    ///
+    /// Left and up report `negative`, right and down report `positive`, following the HID
+    /// convention of Y increasing downwards.
+    pub fn axes<T: Copy>(self, negative: T, center: T, positive: T) -> (T, T) {
+        let axis = |n: bool, p: bool| match (n, p) {
+            (true, false) => negative,
+            (false, true) => positive,
+            _ => center,
+        };
+        (axis(self.left, self.right), axis(self.up, self.down))
+    }
+}
+
+/// Press times, in ms ticks, of a pair of opposite directions
+#[derive(Clone, Copy, Debug, Default)]
+struct AxisState {
+    negative: Option<u32>,
+    positive: Option<u32>,
+}
+
+impl AxisState {
+    fn update(&mut self, negative: bool, positive: bool, now: u32) {
+        fn press(t: &mut Option<u32>, pressed: bool, now: u32) {
+            if !pressed {
+                *t = None;
+            } else if t.is_none() {
+                *t = Some(now);
+            }
+        }
+        press(&mut self.negative, negative, now);
+        press(&mut self.positive, positive, now);
+    }
+
+    /// Resolve to (negative, positive), `negative_priority` selects the winner for
+    /// [`SocdMode::UpPriority`]
+    fn resolve(self, mode: SocdMode, now: u32, negative_priority: bool) -> (bool, bool) {
+        match (self.negative, self.positive) {
+            (Some(n), Some(p)) => {
+                // ages are wrapping safe, presses are compared by how long ago they happened
+                let negative_age = now.wrapping_sub(n);
+                let positive_age = now.wrapping_sub(p);
+                match mode {
+                    SocdMode::Neutral => (false, false),
+                    SocdMode::LastInputWins => {
+                        (negative_age < positive_age, positive_age < negative_age)
+                    }
+                    SocdMode::FirstInputWins => {
+                        (negative_age > positive_age, positive_age > negative_age)
+                    }
+                    SocdMode::UpPriority => (negative_priority, false),
+                }
+            }
+            (n, p) => (n.is_some(), p.is_some()),
+        }
+    }
+}
+
+/// SOCD resolver
+///
+/// Call [`SocdResolver::update`] whenever the inputs are sampled and [`SocdResolver::tick`] every
+/// 1ms. Opposite directions pressed within the same tick are treated as simultaneous and cancel
+/// each other out in [`SocdMode::LastInputWins`] and [`SocdMode::FirstInputWins`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SocdResolver {
+    mode: SocdMode,
+    now: u32,
+    horizontal: AxisState,
+    vertical: AxisState,
+}
+
+impl SocdResolver {
+    #[must_use]
+    pub fn new(mode: SocdMode) -> Self {
+        Self {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    #[must_use]
+    pub fn mode(&self) -> SocdMode {
+        self.mode
+    }
+
+    /// Change the resolution mode, press history is retained
+    pub fn set_mode(&mut self, mode: SocdMode) {
+        self.mode = mode;
+    }
+
+    /// Update with the current raw inputs
+    pub fn update(&mut self, raw: Directions) {
+        self.horizontal.update(raw.left, raw.right, self.now);
+        self.vertical.update(raw.up, raw.down, self.now);
+    }
+
+    /// Call every 1ms
+    pub fn tick(&mut self) {
+        self.now = self.now.wrapping_add(1);
+    }
+
+    /// Clear the press history
+    pub fn reset(&mut self) {
+        self.horizontal = AxisState::default();
+        self.vertical = AxisState::default();
+    }
+
+    /// The cleaned directions, never containing opposite directions
+    #[must_use]
+    pub fn resolve(&self) -> Directions {
+        let (left, right) = self.horizontal.resolve(self.mode, self.now, false);
+        let (up, down) = self.vertical.resolve(self.mode, self.now, true);
+        Directions {
+            up,
+            down,
+            left,
+            right,
+        }
+    }
+
+    /// The cleaned directions as a [`HatSwitch`]
+    #[must_use]
+    pub fn hat_switch(&self) -> HatSwitch {
+        self.resolve().hat_switch()
+    }
+
+    /// The cleaned directions as horizontal and vertical axis values, see [`Directions::axes`]
+    pub fn axes<T: Copy>(&self, negative: T, center: T, positive: T) -> (T, T) {
+        self.resolve().axes(negative, center, positive)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const UP_DOWN: Directions = Directions {
+        up: true,
+        down: true,
+        left: false,
+        right: false,
+    };
+
+    fn press_in_turn(mode: SocdMode, first: Directions, both: Directions) -> Directions {
+        let mut socd = SocdResolver::new(mode);
+        socd.update(first);
+        socd.tick();
+        socd.update(both);
+        socd.resolve()
+    }
+
+    #[test]
+    fn neutral() {
+        let first = Directions {
+            up: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            press_in_turn(SocdMode::Neutral, first, UP_DOWN),
+            Directions::default()
+        );
+    }
+
+    #[test]
+    fn last_input_wins() {
+        let first = Directions {
+            up: true,
+            ..Default::default()
+        };
+        let resolved = press_in_turn(SocdMode::LastInputWins, first, UP_DOWN);
+        assert!(resolved.down);
+        assert!(!resolved.up);
+    }
+
+    #[test]
+    fn first_input_wins() {
+        let first = Directions {
+            down: true,
+            ..Default::default()
+        };
+        let resolved = press_in_turn(SocdMode::FirstInputWins, first, UP_DOWN);
+        assert!(resolved.down);
+        assert!(!resolved.up);
+    }
+
+    #[test]
+    fn up_priority() {
+        let mut socd = SocdResolver::new(SocdMode::UpPriority);
+        socd.update(Directions {
+            up: true,
+            down: true,
+            left: true,
+            right: true,
+        });
+        assert_eq!(socd.hat_switch(), HatSwitch::N);
+        assert_eq!(socd.axes(0_u8, 128, 255), (128, 0));
+    }
+
+    #[test]
+    fn release_restores_held_direction() {
+        let mut socd = SocdResolver::new(SocdMode::LastInputWins);
+        socd.update(Directions {
+            left: true,
+            ..Default::default()
+        });
+        socd.tick();
+        socd.update(Directions {
+            left: true,
+            right: true,
+            ..Default::default()
+        });
+        assert_eq!(socd.hat_switch(), HatSwitch::E);
+        socd.tick();
+        socd.update(Directions {
+            left: true,
+            ..Default::default()
+        });
+        assert_eq!(socd.hat_switch(), HatSwitch::W);
+    }
+}
@@ -0,0 +1,113 @@
+//! USB bus for device unit tests
+//!
+//! Records the reports written to in endpoints and returns queued reports from out endpoints,
+//! without enumerating a USB device.
+#![allow(clippy::unwrap_used)]
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::vec::Vec;
+use usb_device::bus::{PollResult, UsbBus, UsbBusAllocator};
+use usb_device::device::{UsbDevice, UsbDeviceBuilder, UsbVidPid};
+use usb_device::endpoint::{EndpointAddress, EndpointType};
+use usb_device::{UsbDirection, UsbError};
+
+#[derive(Default)]
+pub struct TestBusState {
+    written: Mutex<Vec<Vec<u8>>>,
+    out: Mutex<VecDeque<Vec<u8>>>,
+    blocked: Mutex<bool>,
+}
+
+impl TestBusState {
+    /// Reports written by the device since the last call
+    pub fn take_written(&self) -> Vec<Vec<u8>> {
+        core::mem::take(&mut *self.written.lock().unwrap())
+    }
+
+    /// Queue an output report sent by the host
+    pub fn host_write(&self, data: &[u8]) {
+        self.out.lock().unwrap().push_back(data.to_vec());
+    }
+
+    /// Make writes to in endpoints fail with [`UsbError::WouldBlock`]
+    pub fn set_blocked(&self, blocked: bool) {
+        *self.blocked.lock().unwrap() = blocked;
+    }
+}
+
+pub struct TestUsbBus<'a> {
+    next_ep_index: usize,
+    state: &'a TestBusState,
+}
+
+impl<'a> TestUsbBus<'a> {
+    pub fn new(state: &'a TestBusState) -> Self {
+        Self {
+            next_ep_index: 0,
+            state,
+        }
+    }
+}
+
+/// Finish allocation, required before devices allocated from `usb_alloc` can write reports
+pub fn build_device<'a, 'b>(
+    usb_alloc: &'a UsbBusAllocator<TestUsbBus<'b>>,
+) -> UsbDevice<'a, TestUsbBus<'b>> {
+    UsbDeviceBuilder::new(usb_alloc, UsbVidPid(0x1209, 0x0001)).build()
+}
+
+impl UsbBus for TestUsbBus<'_> {
+    fn alloc_ep(
+        &mut self,
+        ep_dir: UsbDirection,
+        _ep_addr: Option<EndpointAddress>,
+        _ep_type: EndpointType,
+        _max_packet_size: u16,
+        _interval: u8,
+    ) -> usb_device::Result<EndpointAddress> {
+        let ep = EndpointAddress::from_parts(self.next_ep_index, ep_dir);
+        self.next_ep_index += 1;
+        Ok(ep)
+    }
+
+    fn enable(&mut self) {}
+
+    fn reset(&self) {}
+
+    fn set_device_address(&self, _addr: u8) {}
+
+    fn write(&self, _ep_addr: EndpointAddress, buf: &[u8]) -> usb_device::Result<usize> {
+        if *self.state.blocked.lock().unwrap() {
+            return Err(UsbError::WouldBlock);
+        }
+        self.state.written.lock().unwrap().push(buf.to_vec());
+        Ok(buf.len())
+    }
+
+    fn read(&self, _ep_addr: EndpointAddress, buf: &mut [u8]) -> usb_device::Result<usize> {
+        let data = self
+            .state
+            .out
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or(UsbError::WouldBlock)?;
+        buf[..data.len()].copy_from_slice(&data);
+        Ok(data.len())
+    }
+
+    fn set_stalled(&self, _ep_addr: EndpointAddress, _stalled: bool) {}
+
+    fn is_stalled(&self, _ep_addr: EndpointAddress) -> bool {
+        false
+    }
+
+    fn suspend(&self) {}
+
+    fn resume(&self) {}
+
+    fn poll(&self) -> PollResult {
+        PollResult::None
+    }
+}
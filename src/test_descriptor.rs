@@ -0,0 +1,39 @@
+//! Report descriptor parsing for device unit tests
+#![allow(clippy::cast_possible_truncation)]
+
+/// Short items of `descriptor` as the item prefix, without the size bits, and the item data
+pub fn items(mut descriptor: &[u8]) -> impl Iterator<Item = (u8, u32)> + '_ {
+    core::iter::from_fn(move || {
+        let (&prefix, rest) = descriptor.split_first()?;
+        let len = [0, 1, 2, 4][usize::from(prefix & 0x03)];
+        let value = rest[..len]
+            .iter()
+            .rev()
+            .fold(0_u32, |v, &b| (v << 8) | u32::from(b));
+        descriptor = &rest[len..];
+        Some((prefix & 0xFC, value))
+    })
+}
+
+/// Bits of the input, output and feature reports of each report ID below `N`
+pub fn report_sizes<const N: usize>(descriptor: &[u8]) -> [[u32; N]; 3] {
+    let mut sizes = [[0; N]; 3];
+    let (mut id, mut size, mut count) = (0, 0, 0);
+    for (prefix, value) in items(descriptor) {
+        match prefix {
+            0x84 => id = value as usize,
+            0x74 => size = value,
+            0x94 => count = value,
+            0x80 => sizes[0][id] += size * count,
+            0x90 => sizes[1][id] += size * count,
+            0xB0 => sizes[2][id] += size * count,
+            _ => {}
+        }
+    }
+    sizes
+}
+
+/// Bits of a report of `packed_bytes` bytes, excluding the report ID
+pub fn report_bits(packed_bytes: usize) -> u32 {
+    (packed_bytes as u32 - 1) * 8
+}
@@ -1,6 +1,6 @@
 //! USB Class for implementing Human Interface Devices
 
-use crate::descriptor::{DescriptorType, HidProtocol, HidRequest};
+use crate::descriptor::{DescriptorType, HidProtocol, HidRequest, ReportType};
 use crate::device::{DeviceClass, DeviceHList};
 use crate::interface::{InterfaceClass, UsbAllocatable};
 use crate::UsbHidError;
@@ -25,7 +25,7 @@ pub mod prelude {
     //! use usbd_human_interface_device::usb_class::prelude::*;
     //! ```
 
-    pub use crate::descriptor::{HidProtocol, InterfaceProtocol};
+    pub use crate::descriptor::{HidProtocol, InterfaceProtocol, ReportType};
     pub use crate::device::DeviceClass;
     pub use crate::interface::{
         InBytes16, InBytes32, InBytes64, InBytes8, InNone, Interface, InterfaceBuilder,
@@ -214,8 +214,35 @@ where
 
         match HidRequest::try_from(request.request) {
             Ok(HidRequest::SetReport) => {
-                interface.set_report(transfer.data()).ok();
-                transfer.accept().ok();
+                let Ok(report_type) = ReportType::try_from((request.value >> 8) as u8) else {
+                    warn!("Unsupported report type, value: {}", request.value);
+                    transfer.reject().ok();
+                    return;
+                };
+                let report_id = (request.value & 0xFF) as u8;
+                let interface_id = interface.id().into();
+                match self.devices.get_mut().set_report(
+                    interface_id,
+                    report_type,
+                    report_id,
+                    transfer.data(),
+                ) {
+                    Some(Ok(())) => {
+                        transfer.accept().ok();
+                    }
+                    Some(Err(e @ (UsbError::Unsupported | UsbError::ParseError))) => {
+                        warn!("SetReport rejected - {:?}", e);
+                        transfer.reject().ok();
+                    }
+                    Some(Err(e)) => {
+                        // e.g. the previous output report has not been read yet
+                        warn!("SetReport dropped - {:?}", e);
+                        transfer.accept().ok();
+                    }
+                    None => {
+                        transfer.reject().ok();
+                    }
+                }
             }
             Ok(HidRequest::SetIdle) => {
                 if request.length != 0 {
@@ -289,8 +316,17 @@ where
 
                 match HidRequest::try_from(request.request) {
                     Ok(HidRequest::GetReport) => {
+                        let Ok(report_type) = ReportType::try_from((request.value >> 8) as u8)
+                        else {
+                            warn!("Unsupported report type, value: {}", request.value);
+                            return;
+                        };
+                        let report_id = (request.value & 0xFF) as u8;
+                        let devices = self.devices.get_mut();
                         let mut data = [0_u8; 64];
-                        if let Ok(n) = interface.get_report(&mut data) {
+                        if let Some(Ok(n)) =
+                            devices.get_report(interface_id, report_type, report_id, &mut data)
+                        {
                             if n != transfer.request().length.into() {
                                 warn!(
                                     "GetReport expected {} bytes, got {} bytes",
@@ -302,7 +338,11 @@ where
                                 error!("Failed to send report - {:?}", e);
                             } else {
                                 trace!("Sent report, {} bytes", n);
-                                unwrap!(interface.get_report_ack());
+                                if let Some(result) =
+                                    devices.get_report_ack(interface_id, report_type, report_id)
+                                {
+                                    unwrap!(result);
+                                }
                             }
                         }
                     }
@@ -360,7 +400,10 @@ mod test {
     use std::vec::Vec;
 
     use crate::descriptor::USB_CLASS_HID;
-    use crate::interface::{InBytes64, InterfaceBuilder, OutBytes64, ReportSingle, Reports8};
+    use crate::device::mouse::HighResolutionWheelMouseConfig;
+    use crate::interface::{
+        InBytes64, Interface, InterfaceBuilder, OutBytes64, ReportSingle, Reports8,
+    };
     use env_logger::Env;
     use fugit::MillisDurationU32;
     use log::SetLoggerError;
@@ -382,6 +425,9 @@ mod test {
     struct UsbTestManager {
         in_buf: Mutex<RefCell<Vec<u8>>>,
         setup_buf: Mutex<RefCell<Vec<u8>>>,
+        out_buf: Mutex<RefCell<Vec<u8>>>,
+        status_in: Mutex<RefCell<bool>>,
+        in_complete: Mutex<RefCell<bool>>,
     }
 
     impl UsbTestManager {
@@ -395,16 +441,42 @@ mod test {
             }
         }
 
+        /// Data stage of a control out transfer, read after the setup packet
+        fn host_write_out(&self, data: &[u8]) {
+            self.out_buf
+                .lock()
+                .unwrap()
+                .borrow_mut()
+                .extend_from_slice(data);
+        }
+
         fn host_read_in(&self) -> Vec<u8> {
             self.in_buf.lock().unwrap().take()
         }
 
+        /// True if a zero length packet acknowledging a control out transfer was written
+        fn take_status_in(&self) -> bool {
+            self.status_in.lock().unwrap().take()
+        }
+
         fn has_setup_data(&self) -> bool {
             !self.setup_buf.lock().unwrap().borrow().is_empty()
         }
 
+        fn has_out_data(&self) -> bool {
+            !self.out_buf.lock().unwrap().borrow().is_empty()
+        }
+
+        fn take_in_complete(&self) -> bool {
+            self.in_complete.lock().unwrap().take()
+        }
+
         fn device_read_setup(&self, data: &mut [u8]) -> Result<usize> {
-            let buf = self.setup_buf.lock().unwrap();
+            let buf = if self.has_setup_data() {
+                self.setup_buf.lock().unwrap()
+            } else {
+                self.out_buf.lock().unwrap()
+            };
             if buf.borrow().is_empty() {
                 Err(UsbError::WouldBlock)
             } else {
@@ -415,6 +487,10 @@ mod test {
         }
 
         fn device_write(&self, data: &[u8]) -> Result<usize> {
+            if data.is_empty() {
+                self.status_in.lock().unwrap().replace(true);
+            }
+            self.in_complete.lock().unwrap().replace(true);
             let buf = self.in_buf.lock().unwrap();
             if buf.borrow().is_empty() {
                 buf.borrow_mut().extend_from_slice(data);
@@ -478,8 +554,8 @@ mod test {
         }
         fn poll(&self) -> PollResult {
             PollResult::Data {
-                ep_out: 0,
-                ep_in_complete: 1,
+                ep_out: u16::from(self.manager.has_out_data()),
+                ep_in_complete: u16::from(self.manager.take_in_complete()),
                 ep_setup: u16::from(self.manager.has_setup_data()),
             }
         }
@@ -500,6 +576,20 @@ mod test {
         length: u16,
     }
 
+    fn set_report(report_type: ReportType, report_id: u8, index: u16, length: u16) -> [u8; 8] {
+        UsbRequest {
+            direction: UsbDirection::In != UsbDirection::In,
+            request_type: RequestType::Class as u8,
+            recipient: Recipient::Interface as u8,
+            request: HidRequest::SetReport.into(),
+            value: u16::from(u8::from(report_type)) << 8 | u16::from(report_id),
+            index,
+            length,
+        }
+        .pack()
+        .unwrap()
+    }
+
     #[test]
     fn descriptor_ordering_satisfies_boot_spec() {
         init_logging();
@@ -1033,6 +1123,66 @@ mod test {
         );
     }
 
+    #[test]
+    fn set_report_accepted_while_report_unread() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        for report in [[0x01, 0x02], [0x03, 0x04]] {
+            manager
+                .host_write_setup(&set_report(ReportType::Output, 0, 0, 2))
+                .unwrap();
+            manager.host_write_out(&report);
+            usb_dev.poll(&mut [&mut hid]);
+            usb_dev.poll(&mut [&mut hid]);
+            assert!(manager.take_status_in(), "SetReport rejected");
+        }
+
+        // The second report is dropped until the first is read
+        let mut data = [0; 64];
+        let interface = hid.device::<Interface<_, InBytes64, OutBytes64, ReportSingle>, _>();
+        assert_eq!(interface.read_report(&mut data).unwrap(), 2);
+        assert_eq!(data[..2], [0x01, 0x02]);
+    }
+
+    #[test]
+    fn set_report_rejected_when_unsupported() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(HighResolutionWheelMouseConfig::default())
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        manager
+            .host_write_setup(&set_report(ReportType::Output, 0x7F, 0, 0))
+            .unwrap();
+        usb_dev.poll(&mut [&mut hid]);
+        assert!(!manager.take_status_in(), "SetReport accepted");
+    }
+
     #[test]
     fn set_report_idle() {
         const IDLE_DEFAULT: MillisDurationU32 = MillisDurationU32::millis(40);